@@ -0,0 +1,267 @@
+use barter::engine::state::EngineState;
+use barter::engine::state::instrument::InstrumentState;
+use barter::engine::state::instrument::data::InstrumentDataState;
+use barter::engine::state::instrument::filter::InstrumentFilter;
+use barter_execution::order::id::StrategyId;
+use barter_execution::order::request::OrderRequestOpen;
+use barter_execution::order::state::ActiveOrderState;
+use barter_instrument::exchange::ExchangeIndex;
+use barter_instrument::instrument::InstrumentIndex;
+use barter_instrument::Side;
+use chrono::Local;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use crate::algorithm::data::AlgorithmData;
+use crate::algorithm::equity::{quote_equity, CapitalMode};
+
+/// How equity is split between instruments
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AllocationScheme {
+    /// Every instrument gets the same share
+    Equal,
+    /// Shares proportional to 1 / realized volatility, so each instrument carries similar risk
+    VolatilityParity,
+    /// Fixed weights by instrument name (eg/ "BTCUSDT"), normalised to sum to one.
+    /// Instruments without a weight get nothing.
+    Manual(HashMap<String, Decimal>),
+}
+
+/// Gives each instrument a capital budget so a strategy can't commit more than its share of equity
+#[derive(Debug, Clone)]
+pub struct CapitalAllocator {
+    scheme: AllocationScheme,
+    strategy_share: Decimal,
+}
+
+impl CapitalAllocator {
+    pub fn new(scheme: AllocationScheme) -> Self {
+        Self {
+            scheme,
+            strategy_share: dec!(1),
+        }
+    }
+
+    /// Fraction of equity (0-1) this strategy may use, leaving the rest to other strategies
+    #[allow(dead_code)]
+    pub fn with_strategy_share(mut self, strategy_share: Decimal) -> Self {
+        self.strategy_share = strategy_share;
+        self
+    }
+
+    /// Share of the strategy's capital for each instrument
+    ///
+    /// # Arguments
+    /// * `instruments` - Instrument names with their realized volatility, if known
+    pub fn weights(&self, instruments: &[(String, Option<Decimal>)]) -> HashMap<String, Decimal> {
+        if instruments.is_empty() {
+            return HashMap::new();
+        }
+
+        let raw: Vec<(String, Decimal)> = match &self.scheme {
+            AllocationScheme::Equal => instruments.iter().map(|(name, _)| (name.clone(), dec!(1))).collect(),
+            AllocationScheme::VolatilityParity => {
+                let inverse: Vec<Option<Decimal>> = instruments.iter()
+                    .map(|(_, volatility)| volatility.filter(|volatility| *volatility > dec!(0)).map(|volatility| dec!(1) / volatility))
+                    .collect();
+
+                // Instruments still warming up get the average inverse volatility
+                let known: Vec<Decimal> = inverse.iter().flatten().copied().collect();
+                let fallback = if known.is_empty() {
+                    dec!(1)
+                } else {
+                    known.iter().sum::<Decimal>() / Decimal::from(known.len())
+                };
+
+                instruments.iter()
+                    .zip(inverse)
+                    .map(|((name, _), inverse)| (name.clone(), inverse.unwrap_or(fallback)))
+                    .collect()
+            }
+            AllocationScheme::Manual(weights) => instruments.iter()
+                .map(|(name, _)| (name.clone(), weights.get(name).copied().unwrap_or(dec!(0)).max(dec!(0))))
+                .collect(),
+        };
+
+        let total: Decimal = raw.iter().map(|(_, weight)| *weight).sum();
+        raw.into_iter()
+            .map(|(name, weight)| {
+                let weight = if total == dec!(0) { dec!(0) } else { weight / total };
+                (name, weight)
+            })
+            .collect()
+    }
+
+    /// Capital budget of one instrument given the equity available to all of them
+    pub fn budget(&self, equity: Decimal, weight: Decimal) -> Decimal {
+        equity * self.strategy_share * weight
+    }
+
+    /// Each engine instrument's share of equity for this cycle
+    pub fn instrument_weights<GlobalData>(&self, state: &EngineState<GlobalData, AlgorithmData>) -> HashMap<String, Decimal> {
+        let instruments: Vec<(String, Option<Decimal>)> = state.instruments.instruments(&InstrumentFilter::None)
+            .map(|instrument_state| (
+                instrument_state.instrument.name_exchange.name().to_string(),
+                instrument_state.data.realized_volatility.value(),
+            ))
+            .collect();
+        self.weights(&instruments)
+    }
+
+    /// Budget `strategy` has left on an instrument after its position and resting buy orders
+    ///
+    /// The budget is a share of total equity, counting back the quote resting buys reserve out of
+    /// the free balance, so those buys only come off it once. The engine runs a single strategy,
+    /// so the instrument's position is all `strategy`'s.
+    ///
+    /// # Arguments
+    /// * `weights` - Instrument weights from [`CapitalAllocator::instrument_weights`]
+    /// * `capital` - Starting capital, used until the engine has a quote balance
+    /// * `capital_mode` - How the strategy's wallet size follows equity
+    pub fn remaining_budget<GlobalData>(
+        &self,
+        state: &EngineState<GlobalData, AlgorithmData>,
+        instrument_state: &InstrumentState<AlgorithmData>,
+        weights: &HashMap<String, Decimal>,
+        capital: Decimal,
+        capital_mode: CapitalMode,
+        strategy: &StrategyId,
+    ) -> Decimal {
+        let instrument_key = instrument_state.instrument.name_exchange.name().to_string();
+        let quote = instrument_state.instrument.underlying.quote;
+        let reserved = state.assets.asset_index(&quote).balance.as_ref()
+            .map(|balance| balance.value.total - balance.value.free)
+            .unwrap_or_default();
+        let equity = quote_equity(state, quote).map(|equity| equity + reserved).unwrap_or(capital);
+        let wallet_size = capital_mode.wallet_size(capital, equity);
+        let budget = self.budget(wallet_size, weights.get(&instrument_key).copied().unwrap_or_default());
+        budget - committed_capital(instrument_state, strategy)
+    }
+}
+
+/// Whether a buy fits in the remaining budget, reserving its value if it does.
+/// Always passes without a budget, ie/ when the strategy has no allocator.
+pub fn reserve_budget(order: &OrderRequestOpen<ExchangeIndex, InstrumentIndex>, instrument_key: &str, remaining_budget: &mut Option<Decimal>) -> bool {
+    let Some(remaining) = remaining_budget.as_mut() else { return true; };
+    let order_value = order.state.price * order.state.quantity;
+
+    if order_value > *remaining {
+        println!("[{}] 🚫 {} BUY REJECTED: {} | Value: ${:.2} | Remaining budget: ${:.2}",
+                 Local::now().format("%d-%m-%y %H:%M:%S"),
+                 order.key.strategy.0.to_uppercase(),
+                 instrument_key,
+                 order_value,
+                 remaining
+        );
+        return false;
+    }
+
+    *remaining -= order_value;
+    true
+}
+
+/// Capital an instrument already ties up: the open position's value plus
+/// unfilled buy orders the strategy has resting or in flight
+pub fn committed_capital(instrument_state: &InstrumentState<AlgorithmData>, strategy: &StrategyId) -> Decimal {
+    let position = instrument_state.position.current.as_ref()
+        .map(|position| {
+            let price = instrument_state.data.price().unwrap_or(position.price_entry_average);
+            position.quantity_abs * price
+        })
+        .unwrap_or_default();
+
+    let resting: Decimal = instrument_state.orders.0.values()
+        .filter(|order| order.key.strategy == *strategy && order.side == Side::Buy)
+        .map(|order| {
+            let filled = match &order.state {
+                ActiveOrderState::Open(open) => open.filled_quantity,
+                _ => dec!(0),
+            };
+            (order.quantity - filled).max(dec!(0)) * order.price
+        })
+        .sum();
+
+    position + resting
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter::engine::state::builder::EngineStateBuilder;
+    use barter::engine::state::global::DefaultGlobalData;
+    use barter_execution::balance::Balance;
+    use barter_execution::order::id::{ClientOrderId, OrderId};
+    use barter_execution::order::state::Open;
+    use barter_execution::order::{Order, OrderKey, OrderKind, TimeInForce};
+    use barter_instrument::asset::name::AssetNameInternal;
+    use barter_instrument::exchange::ExchangeId;
+    use chrono::Utc;
+    use crate::test_util::btc_eth_instruments;
+
+    fn instruments() -> Vec<(String, Option<Decimal>)> {
+        vec![
+            ("BTCUSDT".to_string(), Some(dec!(0.01))),
+            ("SOLUSDT".to_string(), Some(dec!(0.03))),
+            ("LINKUSDT".to_string(), None),
+        ]
+    }
+
+    #[test]
+    fn test_equal_weights() {
+        let weights = CapitalAllocator::new(AllocationScheme::Equal).weights(&instruments());
+        assert_eq!(weights["BTCUSDT"], dec!(1) / dec!(3));
+        assert_eq!(weights.values().sum::<Decimal>().round_dp(10), dec!(1));
+    }
+
+    #[test]
+    fn test_volatility_parity_weights() {
+        let weights = CapitalAllocator::new(AllocationScheme::VolatilityParity).weights(&instruments());
+
+        // Inverse vols 100 and 33.3, LINK takes their average of 66.7
+        assert!(weights["BTCUSDT"] > weights["LINKUSDT"]);
+        assert!(weights["LINKUSDT"] > weights["SOLUSDT"]);
+        assert_eq!((weights["BTCUSDT"] / weights["SOLUSDT"]).round_dp(6), dec!(3));
+    }
+
+    #[test]
+    fn test_manual_weights_and_strategy_share() {
+        let manual = HashMap::from([("BTCUSDT".to_string(), dec!(3)), ("SOLUSDT".to_string(), dec!(1))]);
+        let allocator = CapitalAllocator::new(AllocationScheme::Manual(manual)).with_strategy_share(dec!(0.5));
+        let weights = allocator.weights(&instruments());
+
+        assert_eq!(weights["BTCUSDT"], dec!(0.75));
+        assert_eq!(weights["LINKUSDT"], dec!(0));
+        // Half of $10,000 equity for the strategy, 75% of that to BTC
+        assert_eq!(allocator.budget(dec!(10000), weights["BTCUSDT"]), dec!(3750));
+    }
+
+    #[test]
+    fn test_resting_buys_count_against_the_budget_once() {
+        let instruments = btc_eth_instruments();
+        let usdt = Balance::new(dec!(1200), dec!(1000));
+        let mut state = EngineStateBuilder::new(&instruments, DefaultGlobalData, |_| AlgorithmData::default())
+            .balances([(ExchangeId::BinanceSpot, AssetNameInternal::from("usdt"), usdt)])
+            .build();
+
+        // A resting buy of 2 @ 100 holds the 200 of quote that isn't free
+        let key = OrderKey { exchange: ExchangeIndex(0), instrument: InstrumentIndex(0), strategy: StrategyId::new("grid"), cid: ClientOrderId::new("1") };
+        let order = Order {
+            key: key.clone(),
+            side: Side::Buy,
+            price: dec!(100),
+            quantity: dec!(2),
+            kind: OrderKind::Limit,
+            time_in_force: TimeInForce::GoodUntilCancelled { post_only: false },
+            state: ActiveOrderState::Open(Open::new(OrderId::new("1"), Utc::now(), dec!(0))),
+        };
+        state.instruments.instrument_index_mut(&InstrumentIndex(0)).orders.0.insert(key.cid.clone(), order);
+
+        // Half of the 1,200 equity for BTC, less the resting buy
+        let allocator = CapitalAllocator::new(AllocationScheme::Equal);
+        let weights = allocator.instrument_weights(&state);
+        let btc = state.instruments.instrument_index(&InstrumentIndex(0));
+        let remaining = allocator.remaining_budget(&state, btc, &weights, dec!(10000), CapitalMode::Compounding, &key.strategy);
+        assert_eq!(remaining, dec!(400));
+    }
+}
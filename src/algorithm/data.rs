@@ -6,11 +6,40 @@ use barter_execution::{AccountEvent, AccountEventKind};
 use barter_execution::order::request::{OrderRequestCancel, OrderRequestOpen};
 use barter_instrument::exchange::ExchangeIndex;
 use barter_instrument::instrument::InstrumentIndex;
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
-use std::time::Duration;
 use crate::algorithm::indicators::rsi::RSI;
-use crate::algorithm::indicators::VwapIndicator;
+use crate::algorithm::indicators::{BarAggregator, OrderBookIndicator, TradeFlowIndicator, VwapIndicator};
+use crate::algorithm::indicators::adx::ADX;
+use crate::algorithm::indicators::atr::ATR;
+use crate::algorithm::indicators::volatility::RealizedVolatility;
+use crate::algorithm::indicators::volume_profile::VolumeProfile;
+use crate::algorithm::indicators::macd::MACD;
+use crate::algorithm::indicators::stochastic::Stochastic;
 use crate::algorithm::indicators::sma::SMA;
+use crate::algorithm::indicators::vwap::VwapReset;
+use crate::algorithm::regime::RegimeDetector;
+
+/// Indicators a strategy can depend on, used to hold back trading until they are warmed up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indicator {
+    Rsi,
+    Sma,
+    Vwap,
+    OrderBook,
+    TradeFlow,
+    Macd,
+    Stochastic,
+    Adx,
+    Regime,
+    Atr,
+    RealizedVolatility,
+}
+
+/// Strategies that set up the per-instrument `AlgorithmData` they read, e.g. with their own indicator periods
+pub trait StrategyData {
+    fn algorithm_data(&self) -> AlgorithmData;
+}
 
 #[derive(Debug, Clone)]
 pub struct AlgorithmData {
@@ -18,6 +47,17 @@ pub struct AlgorithmData {
     pub rsi: RSI,
     pub vwap: VwapIndicator,
     pub sma: SMA,
+    pub order_book: OrderBookIndicator,
+    pub trade_flow: TradeFlowIndicator,
+    pub bars: BarAggregator,
+    pub macd: MACD,
+    pub stochastic: Stochastic,
+    pub adx: ADX,
+    pub regime: RegimeDetector,
+    pub atr: ATR,
+    pub realized_volatility: RealizedVolatility,
+    pub volume_profile: VolumeProfile,
+    pub last_update: Option<DateTime<Utc>>,
 }
 
 impl AlgorithmData {
@@ -27,27 +67,55 @@ impl AlgorithmData {
             rsi: RSI::new(rsi_period),
             vwap: VwapIndicator::daily(), // Daily VWAP by default
             sma: SMA::new(14), // Default SMA period of 14
+            order_book: OrderBookIndicator::default(),
+            trade_flow: TradeFlowIndicator::default(),
+            bars: BarAggregator::default(),
+            macd: MACD::default(),
+            stochastic: Stochastic::default(),
+            adx: ADX::default(),
+            regime: RegimeDetector::default(),
+            atr: ATR::default(),
+            realized_volatility: RealizedVolatility::default(),
+            volume_profile: VolumeProfile::default(),
+            last_update: None,
         }
     }
 
     pub fn new_with_periods(rsi_period: usize, sma_period: usize) -> Self {
         Self {
-            market_data: DefaultInstrumentMarketData::default(),
-            rsi: RSI::new(rsi_period),
-            vwap: VwapIndicator::daily(),
             sma: SMA::new(sma_period),
+            ..Self::new(rsi_period)
         }
     }
 
-    #[allow(dead_code)]
-    pub fn new_with_vwap(rsi_period: usize, vwap_reset_period: Duration) -> Self {
+    pub fn new_with_vwap(rsi_period: usize, vwap_reset: VwapReset) -> Self {
         Self {
-            market_data: DefaultInstrumentMarketData::default(),
-            rsi: RSI::new(rsi_period),
-            vwap: VwapIndicator::new(vwap_reset_period),
-            sma: SMA::new(14),
+            vwap: VwapIndicator::with_reset(vwap_reset),
+            ..Self::new(rsi_period)
         }
     }
+
+    /// Whether the indicator has seen enough data to produce a value
+    pub fn is_ready(&self, indicator: Indicator) -> bool {
+        match indicator {
+            Indicator::Rsi => self.rsi.value().is_some(),
+            Indicator::Sma => self.sma.is_ready(),
+            Indicator::Vwap => self.vwap.value().is_some(),
+            Indicator::OrderBook => self.order_book.spread().is_some(),
+            Indicator::TradeFlow => self.trade_flow.buy_ratio().is_some(),
+            Indicator::Macd => self.macd.value().is_some(),
+            Indicator::Stochastic => self.stochastic.value().is_some(),
+            Indicator::Adx => self.adx.value().is_some(),
+            Indicator::Regime => self.regime.current().is_some(),
+            Indicator::Atr => self.atr.value().is_some(),
+            Indicator::RealizedVolatility => self.realized_volatility.value().is_some(),
+        }
+    }
+
+    /// The subset of `required` indicators that are not ready yet
+    pub fn pending_indicators(&self, required: &[Indicator]) -> Vec<Indicator> {
+        required.iter().copied().filter(|indicator| !self.is_ready(*indicator)).collect()
+    }
 }
 
 impl InstrumentDataState for AlgorithmData {
@@ -64,6 +132,7 @@ impl<InstrumentKey> Processor<&MarketEvent<InstrumentKey, DataKind>> for Algorit
     fn process(&mut self, event: &MarketEvent<InstrumentKey, DataKind>) -> Self::Audit {
         // Process the market data first
         self.market_data.process(event);
+        self.last_update = Some(event.time_received);
 
         // Update indicators with new price data
         if let Some(price) = self.market_data.price() {
@@ -71,9 +140,28 @@ impl<InstrumentKey> Processor<&MarketEvent<InstrumentKey, DataKind>> for Algorit
             self.sma.update(price);
         }
 
-        // Update VWAP on trade events
+        // Update VWAP and trade flow on trade events
         if let DataKind::Trade(trade) = &event.kind {
-            self.vwap.update(Decimal::try_from(trade.price).unwrap(), Decimal::try_from(trade.amount).unwrap(), event.time_received);
+            let price = Decimal::try_from(trade.price).unwrap();
+            let amount = Decimal::try_from(trade.amount).unwrap();
+            self.vwap.update(price, amount, event.time_received);
+            self.trade_flow.update(trade.side, price, amount, event.time_received);
+            self.volume_profile.update(price, amount);
+
+            // Bar-based oscillators only move when a bar closes
+            if let Some(bar) = self.bars.update(price, amount, event.time_received) {
+                self.macd.update(bar.close);
+                self.stochastic.update(&bar);
+                self.adx.update(&bar);
+                self.atr.update(&bar);
+                self.realized_volatility.update(bar.close);
+                self.regime.update(&bar, self.adx.value(), self.sma.value());
+            }
+        }
+
+        // Maintain the local L2 book for microstructure indicators
+        if let DataKind::OrderBook(book_event) = &event.kind {
+            self.order_book.update(book_event);
         }
     }
 }
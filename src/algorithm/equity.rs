@@ -0,0 +1,115 @@
+use barter::engine::state::EngineState;
+use barter::engine::state::instrument::data::InstrumentDataState;
+use barter::engine::state::instrument::filter::InstrumentFilter;
+use barter_instrument::asset::AssetIndex;
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+use crate::algorithm::data::AlgorithmData;
+
+/// How the wallet size used for sizing follows account equity
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapitalMode {
+    /// Size from current equity, so profits and losses feed back into order size
+    Compounding,
+    /// Size from the starting capital, shrinking only if equity falls below it
+    FixedCapital,
+}
+
+impl CapitalMode {
+    /// Wallet size to size orders from given the starting capital and current equity
+    pub fn wallet_size(&self, capital: Decimal, equity: Decimal) -> Decimal {
+        match self {
+            CapitalMode::Compounding => equity,
+            CapitalMode::FixedCapital => capital.min(equity),
+        }
+    }
+}
+
+/// Equity in the `quote` asset: its free balance plus base holdings of every instrument
+/// quoted in it, marked to the latest price
+///
+/// Returns `None` until the engine has a balance for the quote asset. Base assets without
+/// a balance or price yet are left out.
+pub fn quote_equity<GlobalData>(state: &EngineState<GlobalData, AlgorithmData>, quote: AssetIndex) -> Option<Decimal> {
+    let free_quote = state.assets.asset_index(&quote).balance.as_ref()?.value.free;
+
+    // Several instruments can share a base asset, only count each holding once
+    let mut valued = HashSet::new();
+    let holdings = state.instruments.instruments(&InstrumentFilter::None)
+        .filter(|instrument_state| instrument_state.instrument.underlying.quote == quote)
+        .filter_map(|instrument_state| {
+            let base = instrument_state.instrument.underlying.base;
+            let balance = state.assets.asset_index(&base).balance.as_ref()?;
+            let price = instrument_state.data.price()?;
+            valued.insert(base).then(|| balance.value.total * price)
+        })
+        .sum::<Decimal>();
+
+    Some(free_quote + holdings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter::engine::Processor;
+    use barter::engine::state::builder::EngineStateBuilder;
+    use barter::engine::state::global::DefaultGlobalData;
+    use barter_data::event::{DataKind, MarketEvent};
+    use barter_data::subscription::trade::PublicTrade;
+    use barter_execution::balance::Balance;
+    use barter_instrument::Side;
+    use barter_instrument::asset::name::AssetNameInternal;
+    use barter_instrument::exchange::ExchangeId;
+    use barter_instrument::index::IndexedInstruments;
+    use barter_instrument::instrument::{Instrument, InstrumentIndex};
+    use barter_instrument::Underlying;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn trade(instrument: InstrumentIndex, price: f64) -> MarketEvent<InstrumentIndex, DataKind> {
+        MarketEvent {
+            time_exchange: Utc::now(),
+            time_received: Utc::now(),
+            exchange: ExchangeId::BinanceSpot,
+            instrument,
+            kind: DataKind::Trade(PublicTrade { id: "1".to_string(), price, amount: 1.0, side: Side::Buy }),
+        }
+    }
+
+    #[test]
+    fn test_quote_equity_marks_base_holdings_to_market() {
+        let instruments = IndexedInstruments::new([
+            Instrument::spot(ExchangeId::BinanceSpot, "binance_spot_btc_usdt", "BTCUSDT", Underlying::new("btc", "usdt"), None),
+            Instrument::spot(ExchangeId::BinanceSpot, "binance_spot_sol_usdt", "SOLUSDT", Underlying::new("sol", "usdt"), None),
+        ]);
+        let balance = |asset: &str, total: Decimal, free: Decimal| {
+            (ExchangeId::BinanceSpot, AssetNameInternal::from(asset), Balance::new(total, free))
+        };
+
+        let mut state = EngineStateBuilder::new(&instruments, DefaultGlobalData, |_| AlgorithmData::default())
+            .balances([
+                balance("usdt", dec!(1200), dec!(1000)),
+                balance("btc", dec!(0.1), dec!(0.1)),
+                balance("sol", dec!(2), dec!(2)),
+            ])
+            .build();
+        let usdt = instruments.find_asset_index(ExchangeId::BinanceSpot, &AssetNameInternal::from("usdt")).unwrap();
+
+        // No prices yet, only free quote counts
+        assert_eq!(quote_equity(&state, usdt), Some(dec!(1000)));
+
+        state.instruments.instrument_index_mut(&InstrumentIndex(0)).data.process(&trade(InstrumentIndex(0), 50000.0));
+        state.instruments.instrument_index_mut(&InstrumentIndex(1)).data.process(&trade(InstrumentIndex(1), 100.0));
+
+        // 1000 free + 0.1 * 50,000 + 2 * 100
+        assert_eq!(quote_equity(&state, usdt), Some(dec!(6200)));
+    }
+
+    #[test]
+    fn test_capital_modes() {
+        assert_eq!(CapitalMode::Compounding.wallet_size(dec!(10000), dec!(12000)), dec!(12000));
+        assert_eq!(CapitalMode::FixedCapital.wallet_size(dec!(10000), dec!(12000)), dec!(10000));
+        assert_eq!(CapitalMode::FixedCapital.wallet_size(dec!(10000), dec!(8000)), dec!(8000));
+    }
+}
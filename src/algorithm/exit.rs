@@ -0,0 +1,151 @@
+use barter_instrument::Side;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    StopLoss,
+    TakeProfit,
+    TrailingStop,
+    TimeStop,
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExitReason::StopLoss => "STOP_LOSS",
+            ExitReason::TakeProfit => "TAKE_PROFIT",
+            ExitReason::TrailingStop => "TRAILING_STOP",
+            ExitReason::TimeStop => "TIME_STOP",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Per-position exit rules, percentages are fractions of price (0.02 = 2%)
+#[derive(Debug, Clone, Default)]
+pub struct ExitRules {
+    stop_loss: Option<Decimal>,
+    take_profit: Option<Decimal>,
+    trailing_stop: Option<Decimal>,
+    max_holding_time: Option<Duration>,
+}
+
+impl ExitRules {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Close once price moves `percentage` against the entry price
+    #[allow(dead_code)]
+    pub fn with_stop_loss(mut self, percentage: Decimal) -> Self {
+        self.stop_loss = Some(percentage);
+        self
+    }
+
+    /// Close once price moves `percentage` in favour of the entry price
+    #[allow(dead_code)]
+    pub fn with_take_profit(mut self, percentage: Decimal) -> Self {
+        self.take_profit = Some(percentage);
+        self
+    }
+
+    /// Close once price gives back `percentage` from the best price seen since entry
+    #[allow(dead_code)]
+    pub fn with_trailing_stop(mut self, percentage: Decimal) -> Self {
+        self.trailing_stop = Some(percentage);
+        self
+    }
+
+    /// Close positions held for longer than `max_holding_time`
+    #[allow(dead_code)]
+    pub fn with_max_holding_time(mut self, max_holding_time: Duration) -> Self {
+        self.max_holding_time = Some(max_holding_time);
+        self
+    }
+
+    pub fn stop_loss(&self) -> Option<Decimal> {
+        self.stop_loss
+    }
+
+    /// Check the rules against a position, stops take priority over targets
+    ///
+    /// # Arguments
+    /// * `side` - Position direction (Buy = long, Sell = short)
+    /// * `entry_price` - Average entry price of the position
+    /// * `best_price` - Most favourable price seen since entry
+    /// * `price` - Current price
+    /// * `held` - Time since the position was entered
+    pub fn evaluate(
+        &self,
+        side: Side,
+        entry_price: Decimal,
+        best_price: Decimal,
+        price: Decimal,
+        held: Duration,
+    ) -> Option<ExitReason> {
+        // Signed move in favour of the position relative to a reference price
+        let favourable_move = |reference: Decimal| {
+            if reference == dec!(0) {
+                return dec!(0);
+            }
+            match side {
+                Side::Buy => (price - reference) / reference,
+                Side::Sell => (reference - price) / reference,
+            }
+        };
+
+        if self.stop_loss.is_some_and(|stop_loss| favourable_move(entry_price) <= -stop_loss) {
+            return Some(ExitReason::StopLoss);
+        }
+        if self.trailing_stop.is_some_and(|trailing| favourable_move(best_price) <= -trailing) {
+            return Some(ExitReason::TrailingStop);
+        }
+        if self.take_profit.is_some_and(|take_profit| favourable_move(entry_price) >= take_profit) {
+            return Some(ExitReason::TakeProfit);
+        }
+        if self.max_holding_time.is_some_and(|max_holding_time| held >= max_holding_time) {
+            return Some(ExitReason::TimeStop);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> ExitRules {
+        ExitRules::new()
+            .with_stop_loss(dec!(0.02))
+            .with_take_profit(dec!(0.05))
+            .with_trailing_stop(dec!(0.03))
+            .with_max_holding_time(Duration::from_secs(3600))
+    }
+
+    #[test]
+    fn test_long_exits() {
+        let rules = rules();
+        let held = Duration::from_secs(60);
+
+        assert_eq!(rules.evaluate(Side::Buy, dec!(100), dec!(100), dec!(99), held), None);
+        assert_eq!(rules.evaluate(Side::Buy, dec!(100), dec!(100), dec!(98), held), Some(ExitReason::StopLoss));
+        assert_eq!(rules.evaluate(Side::Buy, dec!(100), dec!(105), dec!(105), held), Some(ExitReason::TakeProfit));
+        // Ran to 104 then gave back 3%
+        assert_eq!(rules.evaluate(Side::Buy, dec!(100), dec!(104), dec!(100.88), held), Some(ExitReason::TrailingStop));
+        assert_eq!(rules.evaluate(Side::Buy, dec!(100), dec!(100), dec!(100), Duration::from_secs(3600)), Some(ExitReason::TimeStop));
+    }
+
+    #[test]
+    fn test_short_exits() {
+        let rules = rules();
+        let held = Duration::from_secs(60);
+
+        assert_eq!(rules.evaluate(Side::Sell, dec!(100), dec!(100), dec!(102), held), Some(ExitReason::StopLoss));
+        assert_eq!(rules.evaluate(Side::Sell, dec!(100), dec!(95), dec!(95), held), Some(ExitReason::TakeProfit));
+        assert_eq!(rules.evaluate(Side::Sell, dec!(100), dec!(96), dec!(98.88), held), Some(ExitReason::TrailingStop));
+    }
+}
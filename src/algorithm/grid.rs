@@ -3,14 +3,15 @@ use barter::engine::state::EngineState;
 use barter::engine::state::global::DefaultGlobalData;
 use barter::engine::state::instrument::data::InstrumentDataState;
 use barter::engine::state::instrument::filter::InstrumentFilter;
+use barter::engine::state::position::Position;
 use barter::strategy::algo::AlgoStrategy;
 use barter::strategy::close_positions::ClosePositionsStrategy;
 use barter::strategy::on_disconnect::OnDisconnectStrategy;
 use barter::strategy::on_trading_disabled::OnTradingDisabled;
 use barter_execution::order::id::StrategyId;
 use barter_execution::order::request::{OrderRequestCancel, OrderRequestOpen, RequestOpen};
-use barter_execution::order::{OrderKey, OrderKind, TimeInForce};
-use barter_instrument::asset::AssetIndex;
+use barter_execution::order::{OrderKey, OrderKind};
+use barter_instrument::asset::{AssetIndex, QuoteAsset};
 use barter_instrument::exchange::{ExchangeId, ExchangeIndex};
 use barter_instrument::instrument::InstrumentIndex;
 use barter_instrument::Side;
@@ -18,10 +19,19 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use smol_str::SmolStr;
 use std::collections::{HashMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Mutex;
-use chrono::Local;
-use crate::algorithm::data::AlgorithmData;
-use crate::algorithm::position::PositionSizer;
+use std::time::Duration;
+use chrono::{DateTime, Local, Utc};
+use crate::algorithm::allocation::{reserve_budget, CapitalAllocator};
+use crate::algorithm::data::{AlgorithmData, Indicator, StrategyData};
+use crate::algorithm::equity::CapitalMode;
+use crate::algorithm::indicators::volume_profile::VolumeProfile;
+use crate::algorithm::order::time_in_force;
+use crate::algorithm::position::{PositionSizer, SizingMethod, SizingModel};
+use crate::algorithm::regime::MarketRegime;
+use crate::algorithm::transition::{in_cooldown, StateConfirmation, TransitionFilter};
 
 #[derive(Debug, Clone, PartialEq)]
 enum GridZone {
@@ -37,18 +47,19 @@ enum TmaState {
     Sideways,          // Price oscillating around TMA
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 enum SignalType {
     Buy,
     Sell,
     None,
 }
 
-#[derive(Debug, Clone)]
-struct GridLevel {
-    price: Decimal,
-    level_type: GridLevelType,
-    is_filled: bool,
+/// Part a signal plays in trend following
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TrendFollowLeg {
+    Entry,
+    /// Closes the quantity the entry traded
+    Exit(Decimal),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -57,6 +68,142 @@ enum GridLevelType {
     Sell,
 }
 
+/// What the grid does while the market is in a given regime
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegimeAction {
+    /// Trade the grid as normal
+    Trade,
+    /// Keep selling into levels but take no new buys
+    PauseEntries,
+    /// Rebuild the grid with spacing multiplied by the given factor
+    WidenSpacing(Decimal),
+    /// Stop the grid and ride the trend with a single entry, exited when the regime ends or flips
+    TrendFollow,
+}
+
+/// Maps each market regime to a grid action
+#[derive(Debug, Clone)]
+pub struct RegimePolicy {
+    pub trending_up: RegimeAction,
+    pub trending_down: RegimeAction,
+    pub ranging: RegimeAction,
+    pub high_volatility: RegimeAction,
+}
+
+impl RegimePolicy {
+    pub fn action(&self, regime: MarketRegime) -> RegimeAction {
+        match regime {
+            MarketRegime::TrendingUp => self.trending_up,
+            MarketRegime::TrendingDown => self.trending_down,
+            MarketRegime::Ranging => self.ranging,
+            MarketRegime::HighVolatility => self.high_volatility,
+        }
+    }
+}
+
+impl Default for RegimePolicy {
+    fn default() -> Self {
+        Self {
+            trending_up: RegimeAction::TrendFollow,
+            trending_down: RegimeAction::PauseEntries,
+            ranging: RegimeAction::Trade,
+            high_volatility: RegimeAction::WidenSpacing(dec!(2)),
+        }
+    }
+}
+
+/// Smallest volume weighted multiplier, so levels nothing traded around still place an order
+const MIN_VOLUME_WEIGHT: Decimal = dec!(0.25);
+
+/// How order quantity changes from one grid level to the next, relative to the sizer's base quantity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuantityProfile {
+    /// Same quantity at every level
+    Flat,
+    /// Quantity grows by `step` times the base at each level further from the grid centre
+    Linear { step: Decimal },
+    /// Quantity is multiplied by `multiplier` at each level further from the grid centre
+    Martingale { multiplier: Decimal },
+    /// Quantity follows the volume traded around each level, relative to the average level.
+    /// Levels with little or no volume still get `MIN_VOLUME_WEIGHT` of the base quantity.
+    VolumeWeighted,
+}
+
+impl FromStr for QuantityProfile {
+    type Err = String;
+
+    /// Parses `flat`, `linear:0.5` (step), `martingale:2` (multiplier) and `volume`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let decimal = |value: &str| {
+            value.trim().parse::<Decimal>()
+                .ok()
+                .filter(|value| *value >= Decimal::ZERO)
+                .ok_or_else(|| format!("invalid quantity profile parameter '{}'", value))
+        };
+
+        let (kind, params) = s.split_once(':').unwrap_or((s, ""));
+        match kind.to_lowercase().as_str() {
+            "flat" => Ok(QuantityProfile::Flat),
+            "linear" => Ok(QuantityProfile::Linear { step: decimal(params)? }),
+            "martingale" => Ok(QuantityProfile::Martingale { multiplier: decimal(params)? }),
+            "volume" => Ok(QuantityProfile::VolumeWeighted),
+            other => Err(format!("unknown quantity profile '{}', expected flat, linear, martingale or volume", other)),
+        }
+    }
+}
+
+impl fmt::Display for QuantityProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantityProfile::Flat => write!(f, "flat"),
+            QuantityProfile::Linear { step } => write!(f, "linear:{}", step),
+            QuantityProfile::Martingale { multiplier } => write!(f, "martingale:{}", multiplier),
+            QuantityProfile::VolumeWeighted => write!(f, "volume"),
+        }
+    }
+}
+
+impl QuantityProfile {
+    /// Quantity multiplier of each level, ordered from the level nearest the grid centre outwards
+    ///
+    /// # Arguments
+    /// * `levels` - Level prices, nearest the centre first
+    /// * `spacing` - Distance between levels, the price range each level covers for volume weighting
+    /// * `volume_profile` - Traded volume by price for the instrument
+    fn multipliers(&self, levels: &[Decimal], spacing: Decimal, volume_profile: &VolumeProfile) -> Vec<Decimal> {
+        match self {
+            QuantityProfile::Flat => vec![dec!(1); levels.len()],
+            QuantityProfile::Linear { step } => (0..levels.len())
+                .map(|depth| dec!(1) + *step * Decimal::from(depth))
+                .collect(),
+            QuantityProfile::Martingale { multiplier } => {
+                let mut weight = dec!(1);
+                levels.iter()
+                    .map(|_| {
+                        let current = weight;
+                        weight *= *multiplier;
+                        current
+                    })
+                    .collect()
+            }
+            QuantityProfile::VolumeWeighted => {
+                let half_spacing = spacing / dec!(2);
+                let volumes: Vec<Decimal> = levels.iter()
+                    .map(|level| volume_profile.volume_between(*level - half_spacing, *level + half_spacing))
+                    .collect();
+                let total: Decimal = volumes.iter().sum();
+
+                // Nothing traded near the grid yet, fall back to flat
+                if total == dec!(0) {
+                    return vec![dec!(1); levels.len()];
+                }
+                let average = total / Decimal::from(levels.len());
+                volumes.into_iter().map(|volume| (volume / average).max(MIN_VOLUME_WEIGHT)).collect()
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct GridSignal {
     signal_type: SignalType,
@@ -70,44 +217,81 @@ struct GridSignal {
     volatility: Decimal,
     signal_source: String,
     grid_level: Option<Decimal>,
+    /// Quantity multiplier from the quantity profile
+    level_weight: Decimal,
+    /// Price and quantity multiplier of every level on this side of the grid, used to fit the
+    /// whole side in the grid budget
+    side_levels: Vec<(Decimal, Decimal)>,
+    /// Trend-follow entry or exit rather than a grid level
+    trend_follow: Option<TrendFollowLeg>,
+    /// Distance between grid levels when the signal fired
+    grid_spacing: Decimal,
 }
 
 #[derive(Debug, Clone)]
 struct InstrumentGridState {
     current_price: Decimal,
-    price_history: Vec<Decimal>,
     buy_levels: BTreeSet<Decimal>,
     sell_levels: BTreeSet<Decimal>,
     filled_levels: BTreeSet<Decimal>,
+    deferred_buy_levels: BTreeSet<Decimal>,
     grid_spacing: Decimal,
-    last_grid_zone: GridZone,
+    zone_confirmation: StateConfirmation<GridZone>,
     last_tma_state: TmaState,
+    trend_paused: bool,
+    last_regime: Option<MarketRegime>,
+    /// Quantity the open trend-follow entry traded, kept apart from grid inventory
+    trend_follow_quantity: Decimal,
+    spacing_multiplier: Decimal,
+    last_signal_time: Option<DateTime<Utc>>,
 }
 
 pub struct Grid {
     instrument_grids: Mutex<HashMap<String, InstrumentGridState>>,
-    position_sizer: PositionSizer,
+    position_sizer: Mutex<PositionSizer>,
+    capital: Decimal,
+    capital_mode: CapitalMode,
+    allocator: Option<CapitalAllocator>,
+    sizing: SizingMethod,
     band_percentage: Decimal,
+    /// Period of the SMA the TMA is based on
     tma_period: usize,
     grid_spacing_percentage: Decimal,
     max_grid_levels: usize,
-    price_history_length: usize,
+    book_skew_threshold: Option<Decimal>,
+    max_adx: Option<Decimal>,
+    regime_policy: Option<RegimePolicy>,
+    zone_filter: TransitionFilter,
+    signal_cooldown: Option<Duration>,
+    quantity_profile: QuantityProfile,
+    grid_budget: Option<Decimal>,
+    order_kind: OrderKind,
 }
 
 impl Grid {
-    #[allow(dead_code)]
     pub const ID: StrategyId = StrategyId(SmolStr::new_static("grid"));
 
     /// Creates a new Grid strategy with default parameters
     pub fn new(wallet_size: Decimal) -> Self {
         Self {
             instrument_grids: Mutex::new(HashMap::new()),
-            position_sizer: PositionSizer::new(wallet_size),
+            position_sizer: Mutex::new(PositionSizer::new(wallet_size)),
+            capital: wallet_size,
+            capital_mode: CapitalMode::Compounding,
+            allocator: None,
+            sizing: SizingMethod::Notional,
             band_percentage: dec!(0.05), // 5% bands
             tma_period: 14,
             grid_spacing_percentage: dec!(0.02), // 2% spacing between grid levels
             max_grid_levels: 10,
-            price_history_length: 50,
+            book_skew_threshold: None,
+            max_adx: None,
+            regime_policy: None,
+            zone_filter: TransitionFilter::default(),
+            signal_cooldown: None,
+            quantity_profile: QuantityProfile::Flat,
+            grid_budget: None,
+            order_kind: OrderKind::Limit,
         }
     }
 
@@ -122,13 +306,190 @@ impl Grid {
     ) -> Self {
         Self {
             instrument_grids: Mutex::new(HashMap::new()),
-            position_sizer: PositionSizer::with_risk(wallet_size, risk_percentage),
+            position_sizer: Mutex::new(PositionSizer::with_risk(wallet_size, risk_percentage)),
+            capital: wallet_size,
+            capital_mode: CapitalMode::Compounding,
+            allocator: None,
+            sizing: SizingMethod::Notional,
             band_percentage,
             tma_period,
             grid_spacing_percentage,
             max_grid_levels,
-            price_history_length: 50,
+            book_skew_threshold: None,
+            max_adx: None,
+            regime_policy: None,
+            zone_filter: TransitionFilter::default(),
+            signal_cooldown: None,
+            quantity_profile: QuantityProfile::Flat,
+            grid_budget: None,
+            order_kind: OrderKind::Limit,
+        }
+    }
+
+    /// Delay grid buys while the L2 depth imbalance is below `-threshold` (heavily ask-skewed)
+    pub fn with_book_skew_filter(mut self, threshold: Decimal) -> Self {
+        self.book_skew_threshold = Some(threshold);
+        self
+    }
+
+    /// Stop trading the grid while ADX is above `max_adx` (strong trend in either direction)
+    pub fn with_adx_filter(mut self, max_adx: Decimal) -> Self {
+        self.max_adx = Some(max_adx);
+        self
+    }
+
+    /// Adapt the grid to the detected market regime (pause entries, widen spacing or trend-follow)
+    pub fn with_regime_policy(mut self, policy: RegimePolicy) -> Self {
+        self.regime_policy = Some(policy);
+        self
+    }
+
+    /// Require band crossings to clear a hysteresis margin and be confirmed before the zone changes
+    pub fn with_zone_filter(mut self, filter: TransitionFilter) -> Self {
+        self.zone_filter = filter;
+        self
+    }
+
+    /// Ignore new grid signals on an instrument for `cooldown` after it last signalled
+    pub fn with_signal_cooldown(mut self, cooldown: Duration) -> Self {
+        self.signal_cooldown = Some(cooldown);
+        self
+    }
+
+    /// How order quantity is derived for each signal (notional by default)
+    #[allow(dead_code)]
+    pub fn with_sizing(mut self, sizing: SizingMethod) -> Self {
+        self.sizing = sizing;
+        self
+    }
+
+    /// Whether order size follows equity (compounding, the default) or stays at the starting capital
+    #[allow(dead_code)]
+    pub fn with_capital_mode(mut self, capital_mode: CapitalMode) -> Self {
+        self.capital_mode = capital_mode;
+        self
+    }
+
+    /// Enter with market orders instead of resting limits, eg/ for the mock exchange which only fills market orders
+    pub fn with_market_orders(mut self) -> Self {
+        self.order_kind = OrderKind::Market;
+        self
+    }
+
+    /// How quantity varies across grid levels (flat by default)
+    pub fn with_quantity_profile(mut self, profile: QuantityProfile) -> Self {
+        self.quantity_profile = profile;
+        self
+    }
+
+    /// Cap the value of all levels on one side of the grid at `budget_percentage` of the wallet,
+    /// scaling every level down when the quantity profile would exceed it
+    pub fn with_grid_budget(mut self, budget_percentage: Decimal) -> Self {
+        self.grid_budget = Some(budget_percentage);
+        self
+    }
+
+    /// Quantity profile multiplier of a level, and the price and multiplier of every level on its side of the grid
+    fn level_weights(&self, grid_state: &InstrumentGridState, data: &AlgorithmData, level: Decimal, level_type: &GridLevelType) -> (Decimal, Vec<(Decimal, Decimal)>) {
+        // Nearest the grid centre first
+        let levels: Vec<Decimal> = match level_type {
+            GridLevelType::Buy => grid_state.buy_levels.iter().rev().copied().collect(),
+            GridLevelType::Sell => grid_state.sell_levels.iter().copied().collect(),
+        };
+        let multipliers = self.quantity_profile.multipliers(&levels, grid_state.grid_spacing, &data.volume_profile);
+        let weight = levels.iter()
+            .position(|candidate| *candidate == level)
+            .map(|index| multipliers[index])
+            .unwrap_or(dec!(1));
+        (weight, levels.into_iter().zip(multipliers).collect())
+    }
+
+    /// Order quantity for a signal: the sizer's base quantity scaled by the level's weight,
+    /// then scaled down if the whole side of the grid, each level sized at its own price,
+    /// would exceed the grid budget
+    fn level_quantity(&self, signal: &GridSignal, sizing: &SizingModel) -> Decimal {
+        let position_sizer = self.position_sizer.lock().unwrap();
+        let mut quantity = position_sizer.calculate_quantity_with(signal.price, sizing) * signal.level_weight;
+
+        if let Some(budget_percentage) = self.grid_budget {
+            let budget = position_sizer.wallet_size() * budget_percentage;
+            let grid_value: Decimal = signal.side_levels.iter()
+                .map(|(level, weight)| position_sizer.calculate_quantity_with(*level, sizing) * *level * *weight)
+                .sum();
+            if grid_value > budget && grid_value > dec!(0) {
+                quantity *= budget / grid_value;
+            }
+        }
+        quantity.round_dp(8)
+    }
+
+    /// Give each instrument a share of equity and reject buys that would exceed what is left of it
+    pub fn with_capital_allocator(mut self, allocator: CapitalAllocator) -> Self {
+        self.allocator = Some(allocator);
+        self
+    }
+
+    /// Indicators that must be warmed up before the grid trades an instrument
+    fn required_indicators(&self) -> Vec<Indicator> {
+        let mut required = vec![Indicator::Sma];
+        if self.book_skew_threshold.is_some() {
+            required.push(Indicator::OrderBook);
+        }
+        if self.max_adx.is_some() {
+            required.push(Indicator::Adx);
+        }
+        if self.regime_policy.is_some() {
+            required.push(Indicator::Regime);
+        }
+        required.extend(self.sizing.required_indicator());
+        required
+    }
+
+    /// Check whether ADX reports a trend too strong for a grid
+    fn is_trend_too_strong(&self, data: &AlgorithmData) -> bool {
+        let Some(max_adx) = self.max_adx else { return false; };
+        data.adx.value().is_some_and(|value| value.adx > max_adx)
+    }
+
+    /// Action the regime policy prescribes for a regime, trading normally without a policy
+    fn regime_action(&self, regime: Option<MarketRegime>) -> RegimeAction {
+        match (&self.regime_policy, regime) {
+            (Some(policy), Some(regime)) => policy.action(regime),
+            _ => RegimeAction::Trade,
+        }
+    }
+
+    /// Side of the trend-following entry for a regime, none unless the policy follows its trend
+    fn trend_follow_side(&self, regime: Option<MarketRegime>) -> SignalType {
+        if self.regime_action(regime) != RegimeAction::TrendFollow {
+            return SignalType::None;
+        }
+        match regime {
+            Some(MarketRegime::TrendingUp) => SignalType::Buy,
+            Some(MarketRegime::TrendingDown) => SignalType::Sell,
+            _ => SignalType::None,
+        }
+    }
+
+    /// Sides of the exit and entry handing the position between the grid and trend following,
+    /// including a direct flip from one followed trend to the other
+    fn trend_follow_handoff(&self, previous: Option<MarketRegime>, regime: Option<MarketRegime>) -> (SignalType, SignalType) {
+        let (previous_side, side) = (self.trend_follow_side(previous), self.trend_follow_side(regime));
+        if previous_side == side {
+            return (SignalType::None, SignalType::None);
         }
+        let exit = match previous_side {
+            SignalType::Buy => SignalType::Sell,
+            SignalType::Sell => SignalType::Buy,
+            SignalType::None => SignalType::None,
+        };
+        (exit, side)
+    }
+
+    /// Check whether the order book is too ask-heavy to buy into
+    fn is_book_ask_skewed(&self, data: &AlgorithmData) -> bool {
+        let Some(threshold) = self.book_skew_threshold else { return false; };
+        data.order_book.depth_imbalance().is_some_and(|imbalance| imbalance < -threshold)
     }
 
     /// Calculate Triangular Moving Average (TMA) from simple moving average
@@ -155,10 +516,23 @@ impl Grid {
     }
 
     /// Determine current grid zone based on price and bands
-    fn determine_grid_zone(&self, price: Decimal, high_band: Decimal, low_band: Decimal) -> GridZone {
-        if price > high_band {
+    ///
+    /// Entering a band zone needs price beyond the band by the hysteresis margin, and
+    /// leaving it needs price back inside the band by the same margin.
+    fn determine_grid_zone(&self, price: Decimal, high_band: Decimal, low_band: Decimal, previous_zone: &GridZone) -> GridZone {
+        let margin = self.zone_filter.hysteresis;
+        let upper = match previous_zone {
+            GridZone::AboveHighBand => high_band * (dec!(1) - margin),
+            _ => high_band * (dec!(1) + margin),
+        };
+        let lower = match previous_zone {
+            GridZone::BelowLowBand => low_band * (dec!(1) + margin),
+            _ => low_band * (dec!(1) - margin),
+        };
+
+        if price > upper {
             GridZone::AboveHighBand
-        } else if price < low_band {
+        } else if price < lower {
             GridZone::BelowLowBand
         } else {
             GridZone::BetweenBands
@@ -194,8 +568,14 @@ impl Grid {
     }
 
     /// Generate grid levels around current price
-    fn generate_grid_levels(&self, current_price: Decimal, tma: Decimal, volatility: Decimal) -> (BTreeSet<Decimal>, BTreeSet<Decimal>) {
-        let grid_spacing = self.calculate_grid_spacing(current_price, volatility);
+    fn generate_grid_levels(
+        &self,
+        current_price: Decimal,
+        _tma: Decimal,
+        volatility: Decimal,
+        spacing_multiplier: Decimal,
+    ) -> (BTreeSet<Decimal>, BTreeSet<Decimal>) {
+        let grid_spacing = self.calculate_grid_spacing(current_price, volatility) * spacing_multiplier;
         let mut buy_levels = BTreeSet::new();
         let mut sell_levels = BTreeSet::new();
 
@@ -216,27 +596,6 @@ impl Grid {
         (buy_levels, sell_levels)
     }
 
-    /// Update price history and maintain size limit
-    fn update_price_history(&self, price_history: &mut Vec<Decimal>, new_price: Decimal) {
-        price_history.push(new_price);
-        if price_history.len() > self.price_history_length {
-            price_history.remove(0);
-        }
-    }
-
-    /// Find price ranges from historical data
-    fn analyze_price_ranges(&self, price_history: &[Decimal]) -> (Decimal, Decimal, Decimal) {
-        if price_history.is_empty() {
-            return (dec!(0), dec!(0), dec!(0));
-        }
-
-        let min_price = price_history.iter().min().copied().unwrap_or(dec!(0));
-        let max_price = price_history.iter().max().copied().unwrap_or(dec!(0));
-        let avg_price = price_history.iter().sum::<Decimal>() / Decimal::from(price_history.len());
-
-        (min_price, max_price, avg_price)
-    }
-
     /// Check if current price crosses any grid levels
     fn check_grid_level_crosses(&self, grid_state: &InstrumentGridState, current_price: Decimal) -> Vec<(Decimal, GridLevelType)> {
         let mut crosses = Vec::new();
@@ -244,19 +603,21 @@ impl Grid {
 
         // Check buy level crosses (price moving down through buy levels)
         for &buy_level in &grid_state.buy_levels {
-            if !grid_state.filled_levels.contains(&buy_level) {
-                if previous_price > buy_level && current_price <= buy_level {
-                    crosses.push((buy_level, GridLevelType::Buy));
-                }
+            if !grid_state.filled_levels.contains(&buy_level)
+                && previous_price > buy_level
+                && current_price <= buy_level
+            {
+                crosses.push((buy_level, GridLevelType::Buy));
             }
         }
 
         // Check sell level crosses (price moving up through sell levels)
         for &sell_level in &grid_state.sell_levels {
-            if !grid_state.filled_levels.contains(&sell_level) {
-                if previous_price < sell_level && current_price >= sell_level {
-                    crosses.push((sell_level, GridLevelType::Sell));
-                }
+            if !grid_state.filled_levels.contains(&sell_level)
+                && previous_price < sell_level
+                && current_price >= sell_level
+            {
+                crosses.push((sell_level, GridLevelType::Sell));
             }
         }
 
@@ -320,39 +681,106 @@ impl Grid {
         // Calculate volatility
         let volatility = self.calculate_volatility(high_band, low_band, tma);
 
-        // Determine current grid zone
-        let current_zone = self.determine_grid_zone(price, high_band, low_band);
-
         // Lock and get/update instrument grid state
         let mut instrument_grids = self.instrument_grids.lock().unwrap();
         let grid_state = instrument_grids.entry(instrument_key.clone()).or_insert_with(|| {
             InstrumentGridState {
                 current_price: price,
-                price_history: vec![price],
                 buy_levels: BTreeSet::new(),
                 sell_levels: BTreeSet::new(),
                 filled_levels: BTreeSet::new(),
+                deferred_buy_levels: BTreeSet::new(),
                 grid_spacing: self.calculate_grid_spacing(price, volatility),
-                last_grid_zone: GridZone::BetweenBands,
+                zone_confirmation: StateConfirmation::new(GridZone::BetweenBands),
                 last_tma_state: TmaState::Sideways,
+                trend_paused: false,
+                last_regime: None,
+                trend_follow_quantity: Decimal::ZERO,
+                spacing_multiplier: dec!(1),
+                last_signal_time: None,
             }
         });
 
-        let previous_zone = grid_state.last_grid_zone.clone();
+        // Determine current grid zone, only moving once the change is confirmed
+        let time = instrument_state.data.last_update.unwrap_or_else(Utc::now);
+        let bar_start = instrument_state.data.bars.current().map(|bar| bar.start);
+        let previous_zone = grid_state.zone_confirmation.confirmed().clone();
+        let observed_zone = self.determine_grid_zone(price, high_band, low_band, &previous_zone);
+        let current_zone = grid_state.zone_confirmation.update(observed_zone, time, bar_start, &self.zone_filter);
         let previous_price = grid_state.current_price;
 
-        // Update price history
-        self.update_price_history(&mut grid_state.price_history, price);
-
-        // Analyze price ranges
-        let (min_price, max_price, avg_price) = self.analyze_price_ranges(&grid_state.price_history);
+        // Price range over the TMA window, which warm-up fills before trading like every other input
+        let (min_price, max_price) = instrument_state.data.sma.range().unwrap_or((price, price));
 
         // Determine TMA state
         let tma_state = self.determine_tma_state(price, tma, previous_price);
 
+        // Adapt the grid when the market regime changes
+        let regime = instrument_state.data.regime.current();
+        let regime_action = self.regime_action(regime);
+        if self.regime_policy.is_some() && regime != grid_state.last_regime {
+            let metrics = instrument_state.data.regime.metrics();
+            println!("[{}] 🔀 REGIME CHANGE: {} | {} -> {} | Action: {:?} | ADX: {:.2} | Band slope: {:.4}% | Vol ratio: {:.2}",
+                     Local::now().format("%d-%m-%y %H:%M:%S"),
+                     instrument_key,
+                     grid_state.last_regime.map(|regime| regime.to_string()).unwrap_or_else(|| "NONE".to_string()),
+                     regime.map(|regime| regime.to_string()).unwrap_or_else(|| "NONE".to_string()),
+                     regime_action,
+                     metrics.map(|metrics| metrics.adx).unwrap_or_default(),
+                     metrics.map(|metrics| metrics.band_slope * dec!(100)).unwrap_or_default(),
+                     metrics.map(|metrics| metrics.volatility_ratio).unwrap_or_default()
+            );
+
+            // Rebuild the grid if the spacing changes
+            let spacing_multiplier = match regime_action {
+                RegimeAction::WidenSpacing(multiplier) => multiplier,
+                _ => dec!(1),
+            };
+            if spacing_multiplier != grid_state.spacing_multiplier {
+                grid_state.spacing_multiplier = spacing_multiplier;
+                grid_state.buy_levels.clear();
+                grid_state.sell_levels.clear();
+                grid_state.filled_levels.clear();
+                grid_state.deferred_buy_levels.clear();
+            }
+
+            // Hand off to, or back from, trend following, exiting only what the entry traded
+            let (exit, entry) = self.trend_follow_handoff(grid_state.last_regime, regime);
+            let mut handoff = Vec::new();
+            if exit != SignalType::None {
+                let quantity = std::mem::take(&mut grid_state.trend_follow_quantity);
+                handoff.push((exit, "REGIME_TREND_FOLLOW_EXIT", TrendFollowLeg::Exit(quantity)));
+            }
+            if entry != SignalType::None {
+                handoff.push((entry, "REGIME_TREND_FOLLOW_ENTRY", TrendFollowLeg::Entry));
+            }
+            for (signal_type, signal_source, leg) in handoff {
+                signals.push(GridSignal {
+                    signal_type,
+                    instrument_key: instrument_key.clone(),
+                    instrument_index: instrument_state.key,
+                    exchange_index: instrument_state.instrument.exchange,
+                    price,
+                    tma,
+                    high_band,
+                    low_band,
+                    volatility,
+                    signal_source: signal_source.to_string(),
+                    grid_level: None,
+                    level_weight: dec!(1),
+                    side_levels: vec![(price, dec!(1))],
+                    trend_follow: Some(leg),
+                    grid_spacing: grid_state.grid_spacing,
+                });
+            }
+
+            grid_state.last_regime = regime;
+        }
+
         // Generate or update grid levels if this is a new instrument or price has moved significantly
         if grid_state.buy_levels.is_empty() || grid_state.sell_levels.is_empty() {
-            let (buy_levels, sell_levels) = self.generate_grid_levels(price, tma, volatility);
+            let (buy_levels, sell_levels) = self.generate_grid_levels(price, tma, volatility, grid_state.spacing_multiplier);
+            grid_state.grid_spacing = self.calculate_grid_spacing(price, volatility) * grid_state.spacing_multiplier;
             grid_state.buy_levels = buy_levels;
             grid_state.sell_levels = sell_levels;
 
@@ -369,17 +797,70 @@ impl Grid {
             );
         }
 
+        // Pause the grid during strong trends, logging when that changes
+        let trend_paused = self.is_trend_too_strong(&instrument_state.data);
+        if trend_paused != grid_state.trend_paused {
+            let adx = instrument_state.data.adx.value().map(|value| value.adx).unwrap_or_default();
+            println!("[{}] {} GRID {}: {} | ADX: {:.2}",
+                     Local::now().format("%d-%m-%y %H:%M:%S"),
+                     if trend_paused { "⏸️ " } else { "▶️ " },
+                     if trend_paused { "PAUSED (strong trend)" } else { "RESUMED" },
+                     instrument_key,
+                     adx
+            );
+            grid_state.trend_paused = trend_paused;
+        }
+
+        // The grid sits out while trend following holds the position or the signal cooldown runs
+        let cooling_down = in_cooldown(grid_state.last_signal_time, time, self.signal_cooldown);
+        if trend_paused || regime_action == RegimeAction::TrendFollow || cooling_down {
+            grid_state.current_price = price;
+            grid_state.last_tma_state = tma_state;
+            if !signals.is_empty() {
+                grid_state.last_signal_time = Some(time);
+            }
+            return signals;
+        }
+
         // Check for grid level crosses
-        let level_crosses = self.check_grid_level_crosses(&grid_state, price);
+        let mut level_crosses = self.check_grid_level_crosses(grid_state, price);
+
+        // Release delayed buys once the book rebalances, dropping those price has already left
+        let ask_skewed = self.is_book_ask_skewed(&instrument_state.data);
+        grid_state.deferred_buy_levels.retain(|level| price <= *level);
+        if !ask_skewed {
+            for level in std::mem::take(&mut grid_state.deferred_buy_levels) {
+                if !level_crosses.iter().any(|(crossed, _)| *crossed == level) {
+                    level_crosses.push((level, GridLevelType::Buy));
+                }
+            }
+        }
 
         // Generate signals for grid level crosses
+        let entries_paused = regime_action == RegimeAction::PauseEntries;
         for (level_price, level_type) in level_crosses {
+            if entries_paused && level_type == GridLevelType::Buy {
+                continue;
+            }
+
+            if ask_skewed && level_type == GridLevelType::Buy {
+                println!("[{}] ⏸️  GRID BUY DELAYED: {} @ {:.6} | Book imbalance: {:.3}",
+                         Local::now().format("%d-%m-%y %H:%M:%S"),
+                         instrument_key,
+                         level_price,
+                         instrument_state.data.order_book.depth_imbalance().unwrap_or_default()
+                );
+                grid_state.deferred_buy_levels.insert(level_price);
+                continue;
+            }
+
             let signal_type = match level_type {
                 GridLevelType::Buy => SignalType::Buy,
                 GridLevelType::Sell => SignalType::Sell,
             };
 
             let signal_source = format!("GRID_LEVEL_{:?}@{:.6}", level_type, level_price);
+            let (level_weight, side_levels) = self.level_weights(grid_state, &instrument_state.data, level_price, &level_type);
 
             signals.push(GridSignal {
                 signal_type,
@@ -393,6 +874,10 @@ impl Grid {
                 volatility,
                 signal_source,
                 grid_level: Some(level_price),
+                level_weight,
+                side_levels,
+                trend_follow: None,
+                grid_spacing: grid_state.grid_spacing,
             });
 
             // Mark this level as filled
@@ -400,44 +885,56 @@ impl Grid {
         }
 
         // Fallback to traditional grid signals if no level crosses
-        if signals.is_empty() {
-            if self.should_generate_grid_signal(&previous_zone, &current_zone, &tma_state) {
-                let signal_type = self.get_grid_signal_type(&previous_zone, &current_zone, &tma_state);
-
-                if !matches!(signal_type, SignalType::None) {
-                    let signal_source = format!("TRADITIONAL_{:?}->{:?}", previous_zone, current_zone);
-
-                    signals.push(GridSignal {
-                        signal_type,
-                        instrument_key: instrument_key.clone(),
-                        instrument_index: instrument_state.key,
-                        exchange_index: instrument_state.instrument.exchange,
-                        price,
-                        tma,
-                        high_band,
-                        low_band,
-                        volatility,
-                        signal_source,
-                        grid_level: None,
-                    });
-                }
+        if signals.iter().all(|signal| signal.grid_level.is_none()) && self.should_generate_grid_signal(&previous_zone, &current_zone, &tma_state) {
+            let signal_type = self.get_grid_signal_type(&previous_zone, &current_zone, &tma_state);
+
+            // Band-bounce buys are transition based and can't be deferred, so skip them outright
+            let blocked = (ask_skewed || entries_paused) && matches!(signal_type, SignalType::Buy);
+
+            if !matches!(signal_type, SignalType::None) && !blocked {
+                let signal_source = format!("TRADITIONAL_{:?}->{:?}", previous_zone, current_zone);
+
+                signals.push(GridSignal {
+                    signal_type,
+                    instrument_key: instrument_key.clone(),
+                    instrument_index: instrument_state.key,
+                    exchange_index: instrument_state.instrument.exchange,
+                    price,
+                    tma,
+                    high_band,
+                    low_band,
+                    volatility,
+                    signal_source,
+                    grid_level: None,
+                    level_weight: dec!(1),
+                    side_levels: vec![(price, dec!(1))],
+                    trend_follow: None,
+                    grid_spacing: grid_state.grid_spacing,
+                });
             }
         }
 
         // Update grid state
         grid_state.current_price = price;
-        grid_state.last_grid_zone = current_zone;
         grid_state.last_tma_state = tma_state;
+        if !signals.is_empty() {
+            grid_state.last_signal_time = Some(time);
+        }
 
         signals
     }
 
-    fn create_buy_order(&self, signal: &GridSignal) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
-        let quantity = self.position_sizer.calculate_quantity(signal.price);
-        let position_value = self.position_sizer.calculate_position_value(signal.price);
+    /// Sizing model for a signal, risking against one grid step below entry
+    fn sizing_model(&self, signal: &GridSignal, data: &AlgorithmData) -> SizingModel {
+        let stop_distance = Some(signal.grid_spacing);
+        self.sizing.model(signal.price, stop_distance, data)
+    }
+
+    fn create_buy_order(&self, signal: &GridSignal, quantity: Decimal) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
+        let position_value = quantity * signal.price;
 
         let level_info = if let Some(level) = signal.grid_level {
-            format!("GridLevel@{:.6}", level)
+            format!("GridLevel@{:.6} x{:.2}", level, signal.level_weight)
         } else {
             "Market".to_string()
         };
@@ -454,7 +951,7 @@ impl Grid {
                  signal.volatility,
                  signal.signal_source,
                  level_info,
-                 self.position_sizer.risk_percentage() * dec!(100)
+                 self.position_sizer.lock().unwrap().risk_percentage() * dec!(100)
         );
 
         OrderRequestOpen {
@@ -468,18 +965,17 @@ impl Grid {
                 side: Side::Buy,
                 price: signal.price,
                 quantity,
-                kind: OrderKind::Limit,
-                time_in_force: TimeInForce::GoodUntilEndOfDay,
+                kind: self.order_kind,
+                time_in_force: time_in_force(self.order_kind),
             },
         }
     }
 
-    fn create_sell_order(&self, signal: &GridSignal) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
-        let quantity = self.position_sizer.calculate_quantity(signal.price);
-        let position_value = self.position_sizer.calculate_position_value(signal.price);
+    fn create_sell_order(&self, signal: &GridSignal, quantity: Decimal) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
+        let position_value = quantity * signal.price;
 
         let level_info = if let Some(level) = signal.grid_level {
-            format!("GridLevel@{:.6}", level)
+            format!("GridLevel@{:.6} x{:.2}", level, signal.level_weight)
         } else {
             "Market".to_string()
         };
@@ -496,7 +992,7 @@ impl Grid {
                  signal.volatility,
                  signal.signal_source,
                  level_info,
-                 self.position_sizer.risk_percentage() * dec!(100)
+                 self.position_sizer.lock().unwrap().risk_percentage() * dec!(100)
         );
 
         OrderRequestOpen {
@@ -510,19 +1006,38 @@ impl Grid {
                 side: Side::Sell,
                 price: signal.price,
                 quantity,
-                kind: OrderKind::Limit,
-                time_in_force: TimeInForce::GoodUntilEndOfDay,
+                kind: self.order_kind,
+                time_in_force: time_in_force(self.order_kind),
             },
         }
     }
 }
 
+/// Quantity a trend-follow exit on `closing_side` trades: what its entry traded, never selling
+/// more than the long position holds
+fn exit_quantity(position: Option<&Position<QuoteAsset, InstrumentIndex>>, closing_side: &SignalType, entered: Decimal) -> Decimal {
+    match closing_side {
+        SignalType::Buy => entered,
+        SignalType::Sell => position
+            .filter(|position| position.side == Side::Buy)
+            .map(|position| position.quantity_abs.min(entered))
+            .unwrap_or_default(),
+        SignalType::None => Decimal::ZERO,
+    }
+}
+
 impl Default for Grid {
     fn default() -> Self {
         Self::new(dec!(1000))
     }
 }
 
+impl StrategyData for Grid {
+    fn algorithm_data(&self) -> AlgorithmData {
+        AlgorithmData::new_with_periods(14, self.tma_period)
+    }
+}
+
 impl AlgoStrategy for Grid {
     type State = EngineState<DefaultGlobalData, AlgorithmData>;
 
@@ -535,28 +1050,61 @@ impl AlgoStrategy for Grid {
     ) {
         let mut buy_orders = Vec::new();
         let mut sell_orders = Vec::new();
+        let required_indicators = self.required_indicators();
+        let allocation_weights = self.allocator.as_ref()
+            .map(|allocator| allocator.instrument_weights(state))
+            .unwrap_or_default();
 
         // Process all instruments and generate grid signals
         for instrument_state in state.instruments.instruments(&InstrumentFilter::None) {
+            // Don't trade on partially warmed up indicators
+            if !instrument_state.data.pending_indicators(&required_indicators).is_empty() {
+                continue;
+            }
+
             let signals = self.process_instrument_signal(instrument_state);
+            let mut remaining_budget = self.allocator.as_ref().map(|allocator| {
+                allocator.remaining_budget(state, instrument_state, &allocation_weights, self.capital, self.capital_mode, &Grid::ID)
+            });
 
             for signal in signals {
-                match signal.signal_type {
+                self.position_sizer.lock().unwrap()
+                    .refresh_wallet_size(state, instrument_state.instrument.underlying.quote, self.capital, self.capital_mode);
+                let quantity = if let Some(TrendFollowLeg::Exit(entered)) = signal.trend_follow {
+                    exit_quantity(instrument_state.position.current.as_ref(), &signal.signal_type, entered)
+                } else {
+                    let sizing = self.sizing_model(&signal, &instrument_state.data);
+                    self.level_quantity(&signal, &sizing)
+                };
+                // No position left to close, or a level sized down to nothing
+                if quantity.is_zero() {
+                    continue;
+                }
+                let placed = match signal.signal_type {
                     SignalType::Buy => {
-                        buy_orders.push(self.create_buy_order(&signal));
+                        let order = self.create_buy_order(&signal, quantity);
+                        let placed = reserve_budget(&order, &signal.instrument_key, &mut remaining_budget);
+                        if placed {
+                            buy_orders.push(order);
+                        }
+                        placed
                     },
                     SignalType::Sell => {
-                        sell_orders.push(self.create_sell_order(&signal));
+                        sell_orders.push(self.create_sell_order(&signal, quantity));
+                        true
                     },
-                    SignalType::None => {
-                        // No action needed
-                    }
+                    SignalType::None => false,
+                };
+                if placed && signal.trend_follow == Some(TrendFollowLeg::Entry)
+                    && let Some(grid_state) = self.instrument_grids.lock().unwrap().get_mut(&signal.instrument_key)
+                {
+                    grid_state.trend_follow_quantity = quantity;
                 }
             }
         }
 
         // Combine buy and sell orders
-        let all_orders = buy_orders.into_iter().chain(sell_orders.into_iter());
+        let all_orders = buy_orders.into_iter().chain(sell_orders);
 
         (std::iter::empty(), all_orders)
     }
@@ -599,4 +1147,123 @@ impl<Clock, State, ExecutionTxs, Risk> OnTradingDisabled<Clock, State, Execution
         _: &mut Engine<Clock, State, ExecutionTxs, Self, Risk>,
     ) -> Self::OnTradingDisabled {
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels() -> Vec<Decimal> {
+        vec![dec!(99), dec!(98), dec!(97), dec!(96)]
+    }
+
+    #[test]
+    fn test_pyramiding_profiles() {
+        let profile = VolumeProfile::default();
+
+        let linear = QuantityProfile::Linear { step: dec!(0.5) }.multipliers(&levels(), dec!(1), &profile);
+        assert_eq!(linear, vec![dec!(1), dec!(1.5), dec!(2), dec!(2.5)]);
+
+        let martingale = QuantityProfile::Martingale { multiplier: dec!(2) }.multipliers(&levels(), dec!(1), &profile);
+        assert_eq!(martingale, vec![dec!(1), dec!(2), dec!(4), dec!(8)]);
+    }
+
+    #[test]
+    fn test_volume_weighted_profile() {
+        // 0.1 wide buckets from a first price of 100
+        let mut profile = VolumeProfile::new(dec!(0.001));
+        assert_eq!(QuantityProfile::VolumeWeighted.multipliers(&levels(), dec!(1), &profile), vec![dec!(1); 4]);
+
+        profile.update(dec!(100), dec!(0));
+        profile.update(dec!(99), dec!(3));
+        profile.update(dec!(98), dec!(2));
+        profile.update(dec!(97), dec!(1));
+
+        // Average of 1.5 per level, nothing traded around 96 so it gets the floor
+        let weights = QuantityProfile::VolumeWeighted.multipliers(&levels(), dec!(1), &profile);
+        assert_eq!(weights, vec![dec!(2), dec!(4) / dec!(3), dec!(2) / dec!(3), MIN_VOLUME_WEIGHT]);
+    }
+
+    #[test]
+    fn test_grid_budget_scales_levels() {
+        // $100 at risk over a 100 stop sizes one unit at every level, 10% of a $10,000 wallet for one side of the grid
+        let grid = Grid::with_params(dec!(10000), dec!(0.05), 14, dec!(0.01), dec!(0.01), 4)
+            .with_quantity_profile(QuantityProfile::Martingale { multiplier: dec!(2) })
+            .with_grid_budget(dec!(0.1));
+        let sizing = SizingModel::FixedFractional { stop_distance: dec!(100) };
+        let signal = GridSignal {
+            signal_type: SignalType::Buy,
+            instrument_key: "BTCUSDT".to_string(),
+            instrument_index: InstrumentIndex(0),
+            exchange_index: ExchangeIndex(0),
+            price: dec!(70),
+            tma: dec!(100),
+            high_band: dec!(105),
+            low_band: dec!(95),
+            volatility: dec!(5),
+            signal_source: "TEST".to_string(),
+            grid_level: Some(dec!(70)),
+            level_weight: dec!(8),
+            side_levels: vec![(dec!(100), dec!(1)), (dec!(90), dec!(2)), (dec!(80), dec!(4)), (dec!(70), dec!(8))],
+            trend_follow: None,
+            grid_spacing: dec!(1),
+        };
+
+        // The side would need $100 + $180 + $320 + $560 = $1,160, so every level is scaled by 1000 / 1160
+        let quantity = grid.level_quantity(&signal, &sizing);
+        assert_eq!(quantity, (dec!(8) * dec!(1000) / dec!(1160)).round_dp(8));
+
+        let unscaled = GridSignal { side_levels: vec![(dec!(100), dec!(1)), (dec!(90), dec!(2))], level_weight: dec!(2), ..signal };
+        assert_eq!(grid.level_quantity(&unscaled, &sizing), dec!(2));
+    }
+
+    #[test]
+    fn test_volatility_sizing_waits_for_its_indicator() {
+        use crate::algorithm::position::VolatilitySource;
+
+        let grid = Grid::with_params(dec!(10000), dec!(0.05), 14, dec!(0.01), dec!(0.01), 4);
+        assert!(!grid.required_indicators().contains(&Indicator::Atr));
+
+        let grid = grid.with_sizing(SizingMethod::VolatilityTarget { target_volatility: dec!(0.02), source: VolatilitySource::Atr });
+        assert!(grid.required_indicators().contains(&Indicator::Atr));
+
+        let grid = grid.with_sizing(SizingMethod::VolatilityTarget { target_volatility: dec!(0.02), source: VolatilitySource::Realized });
+        assert!(grid.required_indicators().contains(&Indicator::RealizedVolatility));
+    }
+
+    #[test]
+    fn test_trend_follow_exit_leaves_grid_inventory() {
+        use barter_execution::trade::AssetFees;
+
+        let time = Utc::now();
+        let fees = AssetFees::quote_fees(dec!(0));
+        // 0.27 of grid inventory on top of a 0.1 trend-follow entry
+        let long = Position::new(InstrumentIndex(0), Side::Buy, dec!(100), dec!(0.37), dec!(0.5), dec!(0), dec!(0), fees.clone(), fees, time, time, Vec::new());
+
+        assert_eq!(exit_quantity(Some(&long), &SignalType::Sell, dec!(0.1)), dec!(0.1));
+        // Never sells more than is held, nor without a position
+        assert_eq!(exit_quantity(Some(&long), &SignalType::Sell, dec!(0.5)), dec!(0.37));
+        assert_eq!(exit_quantity(None, &SignalType::Sell, dec!(0.1)), dec!(0));
+        // Buys back what a short entry sold
+        assert_eq!(exit_quantity(Some(&long), &SignalType::Buy, dec!(0.2)), dec!(0.2));
+    }
+
+    #[test]
+    fn test_trend_flip_hands_off_between_sides() {
+        let grid = Grid::new(dec!(1000)).with_regime_policy(RegimePolicy {
+            trending_down: RegimeAction::TrendFollow,
+            ..RegimePolicy::default()
+        });
+        let (up, down) = (Some(MarketRegime::TrendingUp), Some(MarketRegime::TrendingDown));
+
+        // Straight from one followed trend to the other exits the long and enters the short
+        assert_eq!(grid.trend_follow_handoff(up, down), (SignalType::Sell, SignalType::Sell));
+        assert_eq!(grid.trend_follow_handoff(down, up), (SignalType::Buy, SignalType::Buy));
+        assert_eq!(grid.trend_follow_handoff(up, Some(MarketRegime::Ranging)), (SignalType::Sell, SignalType::None));
+        assert_eq!(grid.trend_follow_handoff(None, up), (SignalType::None, SignalType::Buy));
+        assert_eq!(grid.trend_follow_handoff(up, up), (SignalType::None, SignalType::None));
+
+        // Under the default policy a down trend only pauses entries, so there is nothing to enter
+        let default_policy = Grid::new(dec!(1000)).with_regime_policy(RegimePolicy::default());
+        assert_eq!(default_policy.trend_follow_handoff(up, down), (SignalType::Sell, SignalType::None));
+    }
+}
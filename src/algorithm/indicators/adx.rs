@@ -0,0 +1,143 @@
+use crate::algorithm::indicators::bar::Bar;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdxValue {
+    pub adx: Decimal,
+    pub plus_di: Decimal,
+    pub minus_di: Decimal,
+}
+
+/// Average Directional Index with the +DI/-DI lines, using Wilder's smoothing
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct ADX {
+    period: usize,
+    previous_bar: Option<Bar>,
+    smoothed_tr: Decimal,
+    smoothed_plus_dm: Decimal,
+    smoothed_minus_dm: Decimal,
+    smoothing_count: usize,
+    dx_sum: Decimal,
+    dx_count: usize,
+    current_value: Option<AdxValue>,
+}
+
+impl ADX {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            previous_bar: None,
+            smoothed_tr: dec!(0),
+            smoothed_plus_dm: dec!(0),
+            smoothed_minus_dm: dec!(0),
+            smoothing_count: 0,
+            dx_sum: dec!(0),
+            dx_count: 0,
+            current_value: None,
+        }
+    }
+
+    pub fn update(&mut self, bar: &Bar) {
+        let Some(previous) = self.previous_bar.replace(*bar) else { return; };
+
+        let true_range = (bar.high - bar.low)
+            .max((bar.high - previous.close).abs())
+            .max((bar.low - previous.close).abs());
+        let up_move = bar.high - previous.high;
+        let down_move = previous.low - bar.low;
+        let plus_dm = if up_move > down_move && up_move > dec!(0) { up_move } else { dec!(0) };
+        let minus_dm = if down_move > up_move && down_move > dec!(0) { down_move } else { dec!(0) };
+
+        // Wilder smoothing: plain sums for the first period, then decay by 1/period
+        let period = Decimal::from(self.period);
+        if self.smoothing_count < self.period {
+            self.smoothed_tr += true_range;
+            self.smoothed_plus_dm += plus_dm;
+            self.smoothed_minus_dm += minus_dm;
+            self.smoothing_count += 1;
+            if self.smoothing_count < self.period {
+                return;
+            }
+        } else {
+            self.smoothed_tr = self.smoothed_tr - self.smoothed_tr / period + true_range;
+            self.smoothed_plus_dm = self.smoothed_plus_dm - self.smoothed_plus_dm / period + plus_dm;
+            self.smoothed_minus_dm = self.smoothed_minus_dm - self.smoothed_minus_dm / period + minus_dm;
+        }
+
+        if self.smoothed_tr == dec!(0) {
+            return;
+        }
+
+        let plus_di = self.smoothed_plus_dm / self.smoothed_tr * dec!(100);
+        let minus_di = self.smoothed_minus_dm / self.smoothed_tr * dec!(100);
+        let di_sum = plus_di + minus_di;
+        let dx = if di_sum == dec!(0) {
+            dec!(0)
+        } else {
+            (plus_di - minus_di).abs() / di_sum * dec!(100)
+        };
+
+        // ADX starts as the average of the first `period` DX values, then is Wilder smoothed
+        let adx = match self.current_value {
+            Some(previous) => (previous.adx * (period - dec!(1)) + dx) / period,
+            None => {
+                self.dx_sum += dx;
+                self.dx_count += 1;
+                if self.dx_count < self.period {
+                    return;
+                }
+                self.dx_sum / period
+            }
+        };
+
+        self.current_value = Some(AdxValue { adx, plus_di, minus_di });
+    }
+
+    pub fn value(&self) -> Option<AdxValue> {
+        self.current_value
+    }
+}
+
+impl Default for ADX {
+    fn default() -> Self {
+        Self::new(14)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn bar(high: Decimal, low: Decimal) -> Bar {
+        Bar { open: low, high, low, close: high, volume: dec!(1), start: Utc::now() }
+    }
+
+    #[test]
+    fn test_adx_strong_uptrend() {
+        let mut adx = ADX::new(3);
+
+        // Every bar makes a higher high and a higher low
+        for step in 0..10 {
+            let low = Decimal::from(100 + step * 2);
+            adx.update(&bar(low + dec!(1), low));
+        }
+
+        let value = adx.value().unwrap();
+        assert_eq!(value.minus_di, dec!(0));
+        assert!(value.plus_di > dec!(0));
+        assert_eq!(value.adx, dec!(100));
+    }
+
+    #[test]
+    fn test_adx_needs_warmup() {
+        let mut adx = ADX::new(3);
+        for step in 0..4 {
+            adx.update(&bar(Decimal::from(101 + step), Decimal::from(100 + step)));
+        }
+        // 3 bars of smoothing + 3 DX values are needed after the first bar
+        assert!(adx.value().is_none());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::algorithm::indicators::bar::Bar;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Average True Range using Wilder's smoothing
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct ATR {
+    period: usize,
+    previous_close: Option<Decimal>,
+    true_range_sum: Decimal,
+    true_range_count: usize,
+    current_value: Option<Decimal>,
+}
+
+impl ATR {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            previous_close: None,
+            true_range_sum: dec!(0),
+            true_range_count: 0,
+            current_value: None,
+        }
+    }
+
+    pub fn update(&mut self, bar: &Bar) {
+        let Some(previous_close) = self.previous_close.replace(bar.close) else { return; };
+
+        let true_range = (bar.high - bar.low)
+            .max((bar.high - previous_close).abs())
+            .max((bar.low - previous_close).abs());
+
+        // Seed with the mean of the first period, then decay by 1/period
+        match self.current_value {
+            Some(atr) => {
+                let period = Decimal::from(self.period);
+                self.current_value = Some((atr * (period - dec!(1)) + true_range) / period);
+            }
+            None => {
+                self.true_range_sum += true_range;
+                self.true_range_count += 1;
+                if self.true_range_count >= self.period {
+                    self.current_value = Some(self.true_range_sum / Decimal::from(self.period));
+                }
+            }
+        }
+    }
+
+    pub fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+}
+
+impl Default for ATR {
+    fn default() -> Self {
+        Self::new(14)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn bar(high: Decimal, low: Decimal, close: Decimal) -> Bar {
+        Bar { open: close, high, low, close, volume: dec!(1), start: Utc::now() }
+    }
+
+    #[test]
+    fn test_atr_seed_and_smoothing() {
+        let mut atr = ATR::new(2);
+
+        atr.update(&bar(dec!(101), dec!(99), dec!(100)));
+        atr.update(&bar(dec!(102), dec!(100), dec!(101)));
+        assert_eq!(atr.value(), None);
+
+        // Gap up: true range is high - previous close = 4
+        atr.update(&bar(dec!(105), dec!(103), dec!(104)));
+        assert_eq!(atr.value(), Some(dec!(3)));
+
+        atr.update(&bar(dec!(105), dec!(104), dec!(104)));
+        assert_eq!(atr.value(), Some(dec!(2)));
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use rust_decimal::Decimal;
+use std::time::Duration;
+
+/// OHLCV bar built from trades
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    pub start: DateTime<Utc>,
+}
+
+/// Aggregates trades into fixed-interval bars aligned to the UTC epoch
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    interval: Duration,
+    current: Option<Bar>,
+}
+
+impl BarAggregator {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            current: None,
+        }
+    }
+
+    /// Adds a trade, returning the previous bar once a trade opens a new interval
+    pub fn update(&mut self, price: Decimal, volume: Decimal, timestamp: DateTime<Utc>) -> Option<Bar> {
+        let start = self.bar_start(timestamp);
+
+        match &mut self.current {
+            Some(bar) if bar.start == start => {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += volume;
+                None
+            }
+            // Late trades for an already closed bar are folded into the open one
+            Some(bar) if start < bar.start => {
+                bar.volume += volume;
+                None
+            }
+            _ => self.current.replace(Bar {
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume,
+                start,
+            }),
+        }
+    }
+
+    /// The bar currently being built
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+
+    fn bar_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let interval = TimeDelta::from_std(self.interval).unwrap_or(TimeDelta::MAX);
+        timestamp.duration_trunc(interval).unwrap_or(timestamp)
+    }
+}
+
+impl Default for BarAggregator {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60)) // 1 minute bars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_bar_aggregation() {
+        let mut bars = BarAggregator::new(Duration::from_secs(60));
+        let start = Utc.with_ymd_and_hms(2024, 12, 20, 12, 0, 0).unwrap();
+
+        assert_eq!(bars.update(dec!(100), dec!(1), start + TimeDelta::seconds(5)), None);
+        assert_eq!(bars.update(dec!(105), dec!(2), start + TimeDelta::seconds(20)), None);
+        assert_eq!(bars.update(dec!(98), dec!(1), start + TimeDelta::seconds(40)), None);
+
+        let closed = bars.update(dec!(101), dec!(1), start + TimeDelta::seconds(61)).unwrap();
+        assert_eq!(closed, Bar {
+            open: dec!(100),
+            high: dec!(105),
+            low: dec!(98),
+            close: dec!(98),
+            volume: dec!(4),
+            start,
+        });
+        assert_eq!(bars.current().unwrap().open, dec!(101));
+    }
+}
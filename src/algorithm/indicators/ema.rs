@@ -0,0 +1,46 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Exponential moving average, seeded with the SMA of the first `period` values
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct EMA {
+    period: usize,
+    multiplier: Decimal,
+    seed_sum: Decimal,
+    seed_count: usize,
+    current_value: Option<Decimal>,
+}
+
+impl EMA {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            multiplier: dec!(2) / Decimal::from(period + 1),
+            seed_sum: dec!(0),
+            seed_count: 0,
+            current_value: None,
+        }
+    }
+
+    pub fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        match self.current_value {
+            Some(previous) => {
+                self.current_value = Some((value - previous) * self.multiplier + previous);
+            }
+            None => {
+                self.seed_sum += value;
+                self.seed_count += 1;
+                if self.seed_count >= self.period {
+                    self.current_value = Some(self.seed_sum / Decimal::from(self.period));
+                }
+            }
+        }
+        self.current_value
+    }
+
+    #[allow(dead_code)]
+    pub fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+}
@@ -0,0 +1,215 @@
+use barter_instrument::Side;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// A print whose size exceeded the configured percentile of recent trade sizes
+#[derive(Debug, Clone, PartialEq)]
+pub struct LargeTrade {
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Trade-flow indicators derived from the aggressor side of public trades
+#[derive(Debug, Clone)]
+pub struct TradeFlowIndicator {
+    cumulative_delta: Decimal,
+    window: Duration,
+    window_trades: VecDeque<(Side, Decimal, DateTime<Utc>)>,
+    window_buy_volume: Decimal,
+    window_sell_volume: Decimal,
+    size_sample: VecDeque<Decimal>,
+    sample_capacity: usize,
+    large_trade_percentile: Decimal,
+    /// Percentile of the sample as of the last refresh, so trades don't each sort it
+    large_trade_threshold: Option<Decimal>,
+    trades_since_refresh: usize,
+    last_large_trade: Option<LargeTrade>,
+}
+
+impl TradeFlowIndicator {
+    /// Creates a new indicator
+    ///
+    /// # Arguments
+    /// * `window` - Rolling window for the buy/sell aggressor ratio
+    /// * `sample_capacity` - Number of recent trade sizes the percentile is taken over
+    /// * `large_trade_percentile` - Size percentile (0-1) above which a print counts as large
+    pub fn new(window: Duration, sample_capacity: usize, large_trade_percentile: Decimal) -> Self {
+        Self {
+            cumulative_delta: dec!(0),
+            window,
+            window_trades: VecDeque::new(),
+            window_buy_volume: dec!(0),
+            window_sell_volume: dec!(0),
+            size_sample: VecDeque::with_capacity(sample_capacity),
+            sample_capacity,
+            large_trade_percentile,
+            large_trade_threshold: None,
+            trades_since_refresh: 0,
+            last_large_trade: None,
+        }
+    }
+
+    pub fn update(&mut self, side: Side, price: Decimal, size: Decimal, timestamp: DateTime<Utc>) {
+        // Compare against the sample before this trade is part of it
+        if let Some(threshold) = self.large_trade_threshold
+            && size > threshold
+        {
+            self.last_large_trade = Some(LargeTrade { side, price, size, timestamp });
+        }
+
+        self.size_sample.push_back(size);
+        if self.size_sample.len() > self.sample_capacity {
+            self.size_sample.pop_front();
+        }
+        self.refresh_large_trade_threshold();
+
+        match side {
+            Side::Buy => {
+                self.cumulative_delta += size;
+                self.window_buy_volume += size;
+            }
+            Side::Sell => {
+                self.cumulative_delta -= size;
+                self.window_sell_volume += size;
+            }
+        }
+        self.window_trades.push_back((side, size, timestamp));
+
+        // Remove trades that fell out of the rolling window
+        let cutoff_time = timestamp - chrono::Duration::from_std(self.window).unwrap_or_default();
+        while let Some((old_side, old_size, old_timestamp)) = self.window_trades.front() {
+            if *old_timestamp >= cutoff_time {
+                break;
+            }
+            match old_side {
+                Side::Buy => self.window_buy_volume -= *old_size,
+                Side::Sell => self.window_sell_volume -= *old_size,
+            }
+            self.window_trades.pop_front();
+        }
+    }
+
+    /// Buy aggressor volume minus sell aggressor volume since start
+    pub fn cumulative_delta(&self) -> Decimal {
+        self.cumulative_delta
+    }
+
+    /// Share of rolling-window volume that was buyer initiated, in [0, 1]
+    pub fn buy_ratio(&self) -> Option<Decimal> {
+        let total = self.window_buy_volume + self.window_sell_volume;
+        if total == dec!(0) {
+            return None;
+        }
+        Some(self.window_buy_volume / total)
+    }
+
+    /// Share of rolling-window volume that was seller initiated, in [0, 1]
+    pub fn sell_ratio(&self) -> Option<Decimal> {
+        self.buy_ratio().map(|ratio| dec!(1) - ratio)
+    }
+
+    /// Takes the trade size at the configured percentile once the sample is full, refreshing it
+    /// every tenth of the sample's capacity in trades
+    fn refresh_large_trade_threshold(&mut self) {
+        if self.size_sample.len() < self.sample_capacity || self.sample_capacity == 0 {
+            return;
+        }
+        self.trades_since_refresh += 1;
+        if self.large_trade_threshold.is_some() && self.trades_since_refresh < (self.sample_capacity / 10).max(1) {
+            return;
+        }
+        self.trades_since_refresh = 0;
+
+        let mut sizes: Vec<Decimal> = self.size_sample.iter().copied().collect();
+        sizes.sort();
+
+        let rank = (Decimal::from(sizes.len() - 1) * self.large_trade_percentile).floor();
+        let index = usize::try_from(rank).unwrap_or(sizes.len() - 1).min(sizes.len() - 1);
+        self.large_trade_threshold = Some(sizes[index]);
+    }
+
+    #[allow(dead_code)]
+    pub fn last_large_trade(&self) -> Option<&LargeTrade> {
+        self.last_large_trade.as_ref()
+    }
+
+    #[allow(dead_code)]
+    pub fn reset_cumulative_delta(&mut self) {
+        self.cumulative_delta = dec!(0);
+    }
+}
+
+impl Default for TradeFlowIndicator {
+    fn default() -> Self {
+        // 1 minute aggressor window, top 1% of the last 500 prints counts as large
+        Self::new(Duration::from_secs(60), 500, dec!(0.99))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cumulative_delta_and_ratio() {
+        let mut flow = TradeFlowIndicator::new(Duration::from_secs(60), 10, dec!(0.9));
+        let now = Utc::now();
+
+        flow.update(Side::Buy, dec!(100), dec!(3), now);
+        flow.update(Side::Sell, dec!(100), dec!(1), now);
+
+        assert_eq!(flow.cumulative_delta(), dec!(2));
+        assert_eq!(flow.buy_ratio(), Some(dec!(0.75)));
+        assert_eq!(flow.sell_ratio(), Some(dec!(0.25)));
+
+        // Ratio is rolling, the delta is not
+        flow.update(Side::Sell, dec!(100), dec!(1), now + chrono::Duration::seconds(61));
+        assert_eq!(flow.buy_ratio(), Some(dec!(0)));
+        assert_eq!(flow.cumulative_delta(), dec!(1));
+    }
+
+    #[test]
+    fn test_large_trade_detection() {
+        let mut flow = TradeFlowIndicator::new(Duration::from_secs(60), 10, dec!(0.9));
+        let now = Utc::now();
+
+        for size in 1..=10 {
+            flow.update(Side::Buy, dec!(100), Decimal::from(size), now);
+        }
+        assert!(flow.last_large_trade().is_none());
+
+        // 90th percentile of 1..=10 is 9, so a 50 lot sell stands out
+        assert_eq!(flow.large_trade_threshold, Some(dec!(9)));
+        flow.update(Side::Sell, dec!(99), dec!(50), now);
+
+        let large = flow.last_large_trade().unwrap();
+        assert_eq!(large.side, Side::Sell);
+        assert_eq!(large.size, dec!(50));
+    }
+
+    #[test]
+    fn test_large_trade_threshold_refreshes_every_tenth_of_the_sample() {
+        let mut flow = TradeFlowIndicator::new(Duration::from_secs(60), 100, dec!(0.5));
+        let now = Utc::now();
+
+        for _ in 0..100 {
+            flow.update(Side::Buy, dec!(100), dec!(1), now);
+        }
+        assert_eq!(flow.large_trade_threshold, Some(dec!(1)));
+
+        // 51 fives make 5 the median, but the threshold last refreshed at 50 and waits for 60
+        for _ in 0..51 {
+            flow.update(Side::Buy, dec!(100), dec!(5), now);
+        }
+        assert_eq!(flow.large_trade_threshold, Some(dec!(1)));
+        for _ in 0..9 {
+            flow.update(Side::Buy, dec!(100), dec!(5), now);
+        }
+        assert_eq!(flow.large_trade_threshold, Some(dec!(5)));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::algorithm::indicators::ema::EMA;
+use rust_decimal::Decimal;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdValue {
+    pub macd: Decimal,
+    pub signal: Decimal,
+    pub histogram: Decimal,
+}
+
+/// Moving Average Convergence Divergence over bar closes
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct MACD {
+    fast: EMA,
+    slow: EMA,
+    signal: EMA,
+    current_value: Option<MacdValue>,
+}
+
+impl MACD {
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize) -> Self {
+        Self {
+            fast: EMA::new(fast_period),
+            slow: EMA::new(slow_period),
+            signal: EMA::new(signal_period),
+            current_value: None,
+        }
+    }
+
+    pub fn update(&mut self, close: Decimal) {
+        let fast = self.fast.update(close);
+        let slow = self.slow.update(close);
+
+        let (Some(fast), Some(slow)) = (fast, slow) else { return; };
+        let macd = fast - slow;
+
+        if let Some(signal) = self.signal.update(macd) {
+            self.current_value = Some(MacdValue {
+                macd,
+                signal,
+                histogram: macd - signal,
+            });
+        }
+    }
+
+    pub fn value(&self) -> Option<MacdValue> {
+        self.current_value
+    }
+}
+
+impl Default for MACD {
+    fn default() -> Self {
+        Self::new(12, 26, 9)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_macd_flat_then_rising() {
+        let mut macd = MACD::new(2, 4, 2);
+
+        for _ in 0..5 {
+            macd.update(dec!(100));
+        }
+        let flat = macd.value().unwrap();
+        assert_eq!(flat.macd, dec!(0));
+        assert_eq!(flat.histogram, dec!(0));
+
+        // Fast EMA reacts first, pushing MACD above its signal line
+        macd.update(dec!(110));
+        let rising = macd.value().unwrap();
+        assert!(rising.macd > dec!(0));
+        assert!(rising.histogram > dec!(0));
+    }
+}
@@ -1,5 +1,18 @@
 pub mod rsi;
 pub mod vwap;
 pub mod sma;
+pub mod orderbook;
+pub mod flow;
+pub mod bar;
+pub mod ema;
+pub mod macd;
+pub mod stochastic;
+pub mod adx;
+pub mod atr;
+pub mod volatility;
+pub mod volume_profile;
 
-pub use vwap::VwapIndicator;
\ No newline at end of file
+pub use vwap::VwapIndicator;
+pub use orderbook::OrderBookIndicator;
+pub use flow::TradeFlowIndicator;
+pub use bar::BarAggregator;
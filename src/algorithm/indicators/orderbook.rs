@@ -0,0 +1,152 @@
+use barter_data::books::{Level, OrderBook};
+use barter_data::subscription::book::OrderBookEvent;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Microstructure indicators computed from a locally maintained L2 order book
+#[derive(Debug, Clone)]
+pub struct OrderBookIndicator {
+    book: OrderBook,
+    depth: usize,
+}
+
+impl OrderBookIndicator {
+    /// Creates a new indicator looking at the top `depth` levels on each side
+    pub fn new(depth: usize) -> Self {
+        Self {
+            book: OrderBook::default(),
+            depth,
+        }
+    }
+
+    pub fn update(&mut self, event: &OrderBookEvent) {
+        self.book.update(event);
+    }
+
+    /// Best ask minus best bid
+    pub fn spread(&self) -> Option<Decimal> {
+        let best_bid = self.book.bids().best()?;
+        let best_ask = self.book.asks().best()?;
+        Some(best_ask.price - best_bid.price)
+    }
+
+    /// Spread relative to the mid price, in basis points
+    #[allow(dead_code)]
+    pub fn spread_bps(&self) -> Option<Decimal> {
+        let spread = self.spread()?;
+        let mid = self.book.mid_price()?;
+        if mid == dec!(0) {
+            return None;
+        }
+        Some(spread / mid * dec!(10000))
+    }
+
+    /// (bid volume - ask volume) / total volume over the top N levels, in [-1, 1]
+    ///
+    /// Negative values mean the book is ask-skewed (more resting supply than demand).
+    pub fn depth_imbalance(&self) -> Option<Decimal> {
+        let bid_volume = Self::volume(self.top_bids(), |_| dec!(1));
+        let ask_volume = Self::volume(self.top_asks(), |_| dec!(1));
+        Self::imbalance(bid_volume, ask_volume)
+    }
+
+    /// Best bid/ask prices weighted by the opposite side's size
+    #[allow(dead_code)]
+    pub fn microprice(&self) -> Option<Decimal> {
+        self.book.bids().best()?;
+        self.book.asks().best()?;
+        self.book.volume_weighed_mid_price()
+    }
+
+    /// Depth imbalance where each level is weighted by 1 / (level + 1), so liquidity
+    /// closest to the touch dominates
+    #[allow(dead_code)]
+    pub fn book_pressure(&self) -> Option<Decimal> {
+        let weight = |level: usize| dec!(1) / Decimal::from(level + 1);
+        let bid_pressure = Self::volume(self.top_bids(), weight);
+        let ask_pressure = Self::volume(self.top_asks(), weight);
+        Self::imbalance(bid_pressure, ask_pressure)
+    }
+
+    #[allow(dead_code)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn top_bids(&self) -> &[Level] {
+        let levels = self.book.bids().levels();
+        &levels[..levels.len().min(self.depth)]
+    }
+
+    fn top_asks(&self) -> &[Level] {
+        let levels = self.book.asks().levels();
+        &levels[..levels.len().min(self.depth)]
+    }
+
+    fn volume(levels: &[Level], weight: impl Fn(usize) -> Decimal) -> Decimal {
+        levels
+            .iter()
+            .enumerate()
+            .map(|(index, level)| level.amount * weight(index))
+            .sum()
+    }
+
+    fn imbalance(bid: Decimal, ask: Decimal) -> Option<Decimal> {
+        let total = bid + ask;
+        if total == dec!(0) {
+            return None;
+        }
+        Some((bid - ask) / total)
+    }
+}
+
+impl Default for OrderBookIndicator {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indicator(bids: Vec<(Decimal, Decimal)>, asks: Vec<(Decimal, Decimal)>) -> OrderBookIndicator {
+        let mut indicator = OrderBookIndicator::new(2);
+        indicator.update(&OrderBookEvent::Snapshot(OrderBook::new(0, None, bids, asks)));
+        indicator
+    }
+
+    #[test]
+    fn test_spread_and_microprice() {
+        let indicator = indicator(
+            vec![(dec!(99), dec!(3)), (dec!(98), dec!(5))],
+            vec![(dec!(101), dec!(1)), (dec!(102), dec!(5))],
+        );
+
+        assert_eq!(indicator.spread(), Some(dec!(2)));
+        assert_eq!(indicator.spread_bps(), Some(dec!(200)));
+        // (99 * 1 + 101 * 3) / (3 + 1)
+        assert_eq!(indicator.microprice(), Some(dec!(100.5)));
+    }
+
+    #[test]
+    fn test_depth_imbalance_uses_top_levels_only() {
+        let indicator = indicator(
+            vec![(dec!(99), dec!(1)), (dec!(98), dec!(1)), (dec!(97), dec!(100))],
+            vec![(dec!(101), dec!(3)), (dec!(102), dec!(3))],
+        );
+
+        // Top 2 levels: bids 2, asks 6 -> (2 - 6) / 8
+        assert_eq!(indicator.depth_imbalance(), Some(dec!(-0.5)));
+        // Weighted: bids 1 + 0.5 = 1.5, asks 3 + 1.5 = 4.5 -> -3 / 6
+        assert_eq!(indicator.book_pressure(), Some(dec!(-0.5)));
+    }
+
+    #[test]
+    fn test_empty_book() {
+        let indicator = OrderBookIndicator::default();
+        assert_eq!(indicator.spread(), None);
+        assert_eq!(indicator.depth_imbalance(), None);
+        assert_eq!(indicator.microprice(), None);
+    }
+}
@@ -1,6 +1,7 @@
 use rust_decimal::Decimal;
 use std::collections::VecDeque;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub struct SMA {
     period: usize,
@@ -40,9 +41,9 @@ impl SMA {
         self.prices.len() >= self.period
     }
 
-    pub fn reset(&mut self) {
-        self.prices.clear();
-        self.current_value = None;
+    /// Lowest and highest price in the window
+    pub fn range(&self) -> Option<(Decimal, Decimal)> {
+        Some((*self.prices.iter().min()?, *self.prices.iter().max()?))
     }
 }
 
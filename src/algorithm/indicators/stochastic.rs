@@ -0,0 +1,101 @@
+use crate::algorithm::indicators::bar::Bar;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StochasticValue {
+    pub k: Decimal,
+    pub d: Decimal,
+}
+
+/// Stochastic oscillator: %K over the last `k_period` bars, %D as its `d_period` SMA
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    k_period: usize,
+    d_period: usize,
+    bars: VecDeque<Bar>,
+    k_values: VecDeque<Decimal>,
+    current_value: Option<StochasticValue>,
+}
+
+impl Stochastic {
+    pub fn new(k_period: usize, d_period: usize) -> Self {
+        Self {
+            k_period,
+            d_period,
+            bars: VecDeque::with_capacity(k_period),
+            k_values: VecDeque::with_capacity(d_period),
+            current_value: None,
+        }
+    }
+
+    pub fn update(&mut self, bar: &Bar) {
+        self.bars.push_back(*bar);
+        if self.bars.len() > self.k_period {
+            self.bars.pop_front();
+        }
+        if self.bars.len() < self.k_period {
+            return;
+        }
+
+        let highest = self.bars.iter().map(|bar| bar.high).max().unwrap_or(bar.high);
+        let lowest = self.bars.iter().map(|bar| bar.low).min().unwrap_or(bar.low);
+        let range = highest - lowest;
+
+        // A flat range has no position within it, treat it as the midpoint
+        let k = if range == dec!(0) {
+            dec!(50)
+        } else {
+            (bar.close - lowest) / range * dec!(100)
+        };
+
+        self.k_values.push_back(k);
+        if self.k_values.len() > self.d_period {
+            self.k_values.pop_front();
+        }
+        if self.k_values.len() < self.d_period {
+            return;
+        }
+
+        let d = self.k_values.iter().sum::<Decimal>() / Decimal::from(self.d_period);
+        self.current_value = Some(StochasticValue { k, d });
+    }
+
+    pub fn value(&self) -> Option<StochasticValue> {
+        self.current_value
+    }
+}
+
+impl Default for Stochastic {
+    fn default() -> Self {
+        Self::new(14, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn bar(high: Decimal, low: Decimal, close: Decimal) -> Bar {
+        Bar { open: close, high, low, close, volume: dec!(1), start: Utc::now() }
+    }
+
+    #[test]
+    fn test_stochastic() {
+        let mut stochastic = Stochastic::new(3, 2);
+
+        stochastic.update(&bar(dec!(110), dec!(90), dec!(100)));
+        stochastic.update(&bar(dec!(120), dec!(100), dec!(110)));
+        assert!(stochastic.value().is_none());
+
+        // Range 90-120, close 120 -> %K 100
+        stochastic.update(&bar(dec!(120), dec!(105), dec!(120)));
+        assert!(stochastic.value().is_none());
+
+        // Range 100-130, close 115 -> %K 50, %D (100 + 50) / 2
+        stochastic.update(&bar(dec!(130), dec!(110), dec!(115)));
+        assert_eq!(stochastic.value(), Some(StochasticValue { k: dec!(50), d: dec!(75) }));
+    }
+}
@@ -0,0 +1,69 @@
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+use std::collections::VecDeque;
+
+/// Standard deviation of bar close-to-close returns over a rolling window
+#[derive(Debug, Clone)]
+pub struct RealizedVolatility {
+    window: usize,
+    previous_close: Option<Decimal>,
+    returns: VecDeque<Decimal>,
+}
+
+impl RealizedVolatility {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            previous_close: None,
+            returns: VecDeque::with_capacity(window),
+        }
+    }
+
+    pub fn update(&mut self, close: Decimal) {
+        if let Some(previous_close) = self.previous_close.replace(close)
+            && previous_close != dec!(0)
+        {
+            self.returns.push_back((close - previous_close) / previous_close);
+            if self.returns.len() > self.window {
+                self.returns.pop_front();
+            }
+        }
+    }
+
+    /// Per-bar volatility as a fraction of price, once the window is full
+    pub fn value(&self) -> Option<Decimal> {
+        if self.returns.len() < self.window || self.window == 0 {
+            return None;
+        }
+
+        let count = Decimal::from(self.returns.len());
+        let mean = self.returns.iter().sum::<Decimal>() / count;
+        let variance = self.returns.iter().map(|value| (value - mean) * (value - mean)).sum::<Decimal>() / count;
+        variance.sqrt()
+    }
+}
+
+impl Default for RealizedVolatility {
+    fn default() -> Self {
+        Self::new(30)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_realized_volatility() {
+        let mut volatility = RealizedVolatility::new(2);
+
+        volatility.update(dec!(100));
+        volatility.update(dec!(101));
+        assert_eq!(volatility.value(), None);
+
+        // Returns of +1% and -1% around a ~0 mean
+        volatility.update(dec!(99.99));
+        let value = volatility.value().unwrap();
+        assert!(value > dec!(0.0099) && value < dec!(0.0101));
+    }
+}
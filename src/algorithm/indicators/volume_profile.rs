@@ -0,0 +1,85 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::BTreeMap;
+
+/// Traded volume bucketed by price
+#[derive(Debug, Clone)]
+pub struct VolumeProfile {
+    bucket_percentage: Decimal,
+    bucket_width: Option<Decimal>,
+    buckets: BTreeMap<i64, Decimal>,
+}
+
+impl VolumeProfile {
+    /// Creates a new profile whose buckets are `bucket_percentage` of the first traded price wide
+    pub fn new(bucket_percentage: Decimal) -> Self {
+        Self {
+            bucket_percentage,
+            bucket_width: None,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, price: Decimal, volume: Decimal) {
+        if price <= dec!(0) {
+            return;
+        }
+        let bucket_width = *self.bucket_width.get_or_insert(price * self.bucket_percentage);
+        if bucket_width <= dec!(0) {
+            return;
+        }
+        *self.buckets.entry(Self::bucket(price, bucket_width)).or_default() += volume;
+    }
+
+    /// Volume traded in the buckets starting in `low..high`
+    ///
+    /// The range is half-open so adjacent ranges never count the same bucket twice.
+    pub fn volume_between(&self, low: Decimal, high: Decimal) -> Decimal {
+        let Some(bucket_width) = self.bucket_width else { return dec!(0); };
+        if bucket_width <= dec!(0) || high <= low {
+            return dec!(0);
+        }
+        self.buckets
+            .range(Self::first_bucket_from(low, bucket_width)..Self::first_bucket_from(high, bucket_width))
+            .map(|(_, volume)| *volume)
+            .sum()
+    }
+
+    fn bucket(price: Decimal, bucket_width: Decimal) -> i64 {
+        i64::try_from((price / bucket_width).floor()).unwrap_or_default()
+    }
+
+    /// First bucket whose lower edge is at or above `price`
+    fn first_bucket_from(price: Decimal, bucket_width: Decimal) -> i64 {
+        i64::try_from((price / bucket_width).ceil()).unwrap_or_default()
+    }
+}
+
+impl Default for VolumeProfile {
+    fn default() -> Self {
+        // 0.1% wide buckets
+        Self::new(dec!(0.001))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_between() {
+        // 1 wide buckets from a first price of 100
+        let mut profile = VolumeProfile::new(dec!(0.01));
+        profile.update(dec!(100), dec!(2));
+        profile.update(dec!(100.5), dec!(3));
+        profile.update(dec!(98.2), dec!(1));
+
+        assert_eq!(profile.volume_between(dec!(100), dec!(100.9)), dec!(5));
+        assert_eq!(profile.volume_between(dec!(97), dec!(101)), dec!(6));
+        assert_eq!(profile.volume_between(dec!(95), dec!(97)), dec!(0));
+
+        // Adjacent ranges split the volume at their shared boundary
+        assert_eq!(profile.volume_between(dec!(99), dec!(100)), dec!(0));
+        assert_eq!(profile.volume_between(dec!(100), dec!(101)), dec!(5));
+    }
+}
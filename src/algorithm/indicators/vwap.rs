@@ -1,20 +1,88 @@
-use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
+use chrono::{DateTime, Datelike, Days, NaiveTime, Utc};
+use rust_decimal::{Decimal, MathematicalOps};
 use rust_decimal_macros::dec;
 use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
 use std::time::Duration;
+use crate::util::parse_window_length;
+
+/// Controls when the accumulated VWAP sums are cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VwapReset {
+    /// Reset once the duration has elapsed since the previous reset
+    Interval(Duration),
+    /// Reset at every 00:00 UTC
+    Daily,
+    /// Reset at 00:00 UTC every Monday
+    Weekly,
+    /// Accumulate from the anchor onward, never resetting on its own
+    Anchored(DateTime<Utc>),
+    /// Never reset, only the trades inside the trailing window contribute
+    Rolling(Duration),
+}
+
+impl FromStr for VwapReset {
+    type Err = String;
+
+    /// Parses `daily`, `weekly`, `interval:8h`, `rolling:30m` and `anchored:` followed by an RFC 3339 time
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let window = |length: &str| {
+            parse_window_length(length)?
+                .to_std()
+                .map_err(|_| format!("VWAP window '{}' must be positive", length))
+        };
+
+        let (kind, params) = s.split_once(':').unwrap_or((s, ""));
+        match kind.to_lowercase().as_str() {
+            "daily" => Ok(VwapReset::Daily),
+            "weekly" => Ok(VwapReset::Weekly),
+            "interval" => Ok(VwapReset::Interval(window(params)?)),
+            "rolling" => Ok(VwapReset::Rolling(window(params)?)),
+            "anchored" => DateTime::parse_from_rfc3339(params)
+                .map(|anchor| VwapReset::Anchored(anchor.with_timezone(&Utc)))
+                .map_err(|error| format!("invalid VWAP anchor '{}': {}", params, error)),
+            other => Err(format!("unknown VWAP reset '{}', expected daily, weekly, interval, rolling or anchored", other)),
+        }
+    }
+}
+
+impl fmt::Display for VwapReset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VwapReset::Interval(period) => write!(f, "interval:{}s", period.as_secs()),
+            VwapReset::Daily => write!(f, "daily"),
+            VwapReset::Weekly => write!(f, "weekly"),
+            VwapReset::Anchored(anchor) => write!(f, "anchored:{}", anchor.to_rfc3339()),
+            VwapReset::Rolling(window) => write!(f, "rolling:{}s", window.as_secs()),
+        }
+    }
+}
+
+/// VWAP with ±1σ and ±2σ bands derived from the volume-weighted variance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VwapBands {
+    pub vwap: Decimal,
+    pub std_dev: Decimal,
+    pub upper_1: Decimal,
+    pub lower_1: Decimal,
+    pub upper_2: Decimal,
+    pub lower_2: Decimal,
+}
 
 #[derive(Debug, Clone)]
 pub struct VwapIndicator {
     price_volume_sum: Decimal,
+    price_squared_volume_sum: Decimal,
     volume_sum: Decimal,
-    trades: VecDeque<VwapTrade>,
-    reset_period: Duration,
+    trade_count: usize,
+    // Only populated in rolling mode, where old trades must be subtracted again
+    window_trades: VecDeque<VwapTrade>,
+    reset: VwapReset,
     last_reset: Option<DateTime<Utc>>,
     current_vwap: Option<Decimal>,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct VwapTrade {
     price: Decimal,
@@ -23,52 +91,84 @@ struct VwapTrade {
 }
 
 impl VwapIndicator {
-    pub fn new(reset_period: Duration) -> Self {
+    pub fn with_reset(reset: VwapReset) -> Self {
         Self {
             price_volume_sum: dec!(0),
+            price_squared_volume_sum: dec!(0),
             volume_sum: dec!(0),
-            trades: VecDeque::new(),
-            reset_period,
+            trade_count: 0,
+            window_trades: VecDeque::new(),
+            reset,
             last_reset: None,
             current_vwap: None,
         }
     }
 
-    /// Create a daily VWAP (resets every 24 hours)
+    /// Create a daily VWAP (resets at every UTC midnight)
     pub fn daily() -> Self {
-        Self::new(Duration::from_secs(24 * 60 * 60))
+        Self::with_reset(VwapReset::Daily)
     }
 
     /// Create an hourly VWAP (resets every hour)
     pub fn _hourly() -> Self {
-        Self::new(Duration::from_secs(60 * 60))
+        Self::with_reset(VwapReset::Interval(Duration::from_secs(60 * 60)))
     }
 
     /// Create a session VWAP (resets every 8 hours)
     pub fn _session() -> Self {
-        Self::new(Duration::from_secs(8 * 60 * 60))
+        Self::with_reset(VwapReset::Interval(Duration::from_secs(8 * 60 * 60)))
     }
 
     pub fn update(&mut self, price: Decimal, volume: Decimal, timestamp: DateTime<Utc>) {
+        // Anchored VWAP ignores anything printed before the anchor
+        if let VwapReset::Anchored(anchor) = self.reset
+            && timestamp < anchor
+        {
+            return;
+        }
+
         // Check if we need to reset the VWAP
         if self.should_reset(timestamp) {
-            self.reset(timestamp);
+            self.reset(self.session_start_for(timestamp));
         }
 
         // Add the new trade
-        let trade = VwapTrade {
-            price,
-            volume,
-            timestamp,
-        };
-
-        self.trades.push_back(trade);
         self.price_volume_sum += price * volume;
+        self.price_squared_volume_sum += price * price * volume;
         self.volume_sum += volume;
+        self.trade_count += 1;
+
+        if let VwapReset::Rolling(window) = self.reset {
+            self.window_trades.push_back(VwapTrade {
+                price,
+                volume,
+                timestamp,
+            });
+            self.evict_expired(timestamp, window);
+        }
 
         // Calculate VWAP
-        if self.volume_sum > dec!(0) {
-            self.current_vwap = Some(self.price_volume_sum / self.volume_sum);
+        self.current_vwap = if self.volume_sum > dec!(0) {
+            Some(self.price_volume_sum / self.volume_sum)
+        } else {
+            None
+        };
+    }
+
+    /// Subtract trades that fell out of the rolling window from the running sums
+    fn evict_expired(&mut self, timestamp: DateTime<Utc>, window: Duration) {
+        let cutoff_time = timestamp - chrono::Duration::from_std(window).unwrap_or_default();
+
+        while let Some(trade) = self.window_trades.front() {
+            if trade.timestamp >= cutoff_time {
+                break;
+            }
+
+            self.price_volume_sum -= trade.price * trade.volume;
+            self.price_squared_volume_sum -= trade.price * trade.price * trade.volume;
+            self.volume_sum -= trade.volume;
+            self.trade_count -= 1;
+            self.window_trades.pop_front();
         }
     }
 
@@ -76,6 +176,29 @@ impl VwapIndicator {
         self.current_vwap
     }
 
+    /// Volume-weighted standard deviation of trade prices around the VWAP
+    pub fn std_dev(&self) -> Option<Decimal> {
+        let vwap = self.current_vwap?;
+        let variance = self.price_squared_volume_sum / self.volume_sum - vwap * vwap;
+
+        // Rounding can push a flat market's variance marginally below zero
+        variance.max(dec!(0)).sqrt()
+    }
+
+    pub fn bands(&self) -> Option<VwapBands> {
+        let vwap = self.current_vwap?;
+        let std_dev = self.std_dev()?;
+
+        Some(VwapBands {
+            vwap,
+            std_dev,
+            upper_1: vwap + std_dev,
+            lower_1: vwap - std_dev,
+            upper_2: vwap + std_dev * dec!(2),
+            lower_2: vwap - std_dev * dec!(2),
+        })
+    }
+
     #[allow(dead_code)]
     pub fn total_volume(&self) -> Decimal {
         self.volume_sum
@@ -83,40 +206,62 @@ impl VwapIndicator {
 
     #[allow(dead_code)]
     pub fn trade_count(&self) -> usize {
-        self.trades.len()
+        self.trade_count
     }
 
     fn should_reset(&self, timestamp: DateTime<Utc>) -> bool {
-        if let Some(last_reset) = self.last_reset {
-            timestamp.signed_duration_since(last_reset).to_std().unwrap_or_default() >= self.reset_period
-        } else {
-            true // First update
+        let Some(last_reset) = self.last_reset else {
+            return true; // First update
+        };
+
+        match self.reset {
+            VwapReset::Interval(period) => {
+                timestamp.signed_duration_since(last_reset).to_std().unwrap_or_default() >= period
+            }
+            VwapReset::Daily | VwapReset::Weekly => self.session_start_for(timestamp) > last_reset,
+            VwapReset::Anchored(_) | VwapReset::Rolling(_) => false,
+        }
+    }
+
+    /// Start of the accumulation period the timestamp falls into
+    fn session_start_for(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        match self.reset {
+            VwapReset::Interval(_) | VwapReset::Rolling(_) => timestamp,
+            VwapReset::Daily => timestamp.date_naive().and_time(NaiveTime::MIN).and_utc(),
+            VwapReset::Weekly => {
+                let date = timestamp.date_naive();
+                let days_since_monday = u64::from(date.weekday().num_days_from_monday());
+                (date - Days::new(days_since_monday)).and_time(NaiveTime::MIN).and_utc()
+            }
+            VwapReset::Anchored(anchor) => anchor,
         }
     }
 
-    fn reset(&mut self, timestamp: DateTime<Utc>) {
+    fn reset(&mut self, session_start: DateTime<Utc>) {
         self.price_volume_sum = dec!(0);
+        self.price_squared_volume_sum = dec!(0);
         self.volume_sum = dec!(0);
-        self.trades.clear();
+        self.trade_count = 0;
+        self.window_trades.clear();
         self.current_vwap = None;
-        self.last_reset = Some(timestamp);
+        self.last_reset = Some(session_start);
     }
 
     #[allow(dead_code)]
     pub fn set_reset_period(&mut self, period: Duration) {
-        self.reset_period = period;
+        self.reset = VwapReset::Interval(period);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
     use rust_decimal_macros::dec;
 
     #[test]
     fn test_vwap_calculation() {
-        let mut vwap = VwapIndicator::new(Duration::from_secs(3600));
+        let mut vwap = VwapIndicator::with_reset(VwapReset::Interval(Duration::from_secs(3600)));
         let now = Utc::now();
 
         // Add some trades
@@ -130,11 +275,14 @@ mod tests {
         assert_eq!(vwap.value(), Some(expected_vwap));
         assert_eq!(vwap.total_volume(), dec!(60));
         assert_eq!(vwap.trade_count(), 3);
+
+        // Cumulative mode only keeps running sums
+        assert!(vwap.window_trades.is_empty());
     }
 
     #[test]
     fn test_vwap_reset() {
-        let mut vwap = VwapIndicator::new(Duration::from_secs(1));
+        let mut vwap = VwapIndicator::with_reset(VwapReset::Interval(Duration::from_secs(1)));
         let now = Utc::now();
 
         vwap.update(dec!(100), dec!(10), now);
@@ -149,4 +297,85 @@ mod tests {
         assert_eq!(vwap.total_volume(), dec!(5));
         assert_eq!(vwap.trade_count(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_vwap_bands() {
+        let mut vwap = VwapIndicator::daily();
+        let now = Utc.with_ymd_and_hms(2024, 12, 20, 12, 0, 0).unwrap();
+
+        // Equal volume at 90 and 110: VWAP = 100, variance = 100, σ = 10
+        vwap.update(dec!(90), dec!(1), now);
+        vwap.update(dec!(110), dec!(1), now);
+
+        let bands = vwap.bands().unwrap();
+        assert_eq!(bands.vwap, dec!(100));
+        assert_eq!(bands.std_dev, dec!(10));
+        assert_eq!(bands.upper_1, dec!(110));
+        assert_eq!(bands.lower_1, dec!(90));
+        assert_eq!(bands.upper_2, dec!(120));
+        assert_eq!(bands.lower_2, dec!(80));
+    }
+
+    #[test]
+    fn test_vwap_daily_resets_at_utc_midnight() {
+        let mut vwap = VwapIndicator::daily();
+        let late = Utc.with_ymd_and_hms(2024, 12, 20, 23, 59, 0).unwrap();
+        let after_midnight = Utc.with_ymd_and_hms(2024, 12, 21, 0, 1, 0).unwrap();
+
+        vwap.update(dec!(100), dec!(10), late);
+        vwap.update(dec!(200), dec!(5), after_midnight);
+
+        // Only two minutes apart, but on different UTC days
+        assert_eq!(vwap.value(), Some(dec!(200)));
+        assert_eq!(vwap.last_reset, Some(Utc.with_ymd_and_hms(2024, 12, 21, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_vwap_weekly_resets_on_monday() {
+        let mut vwap = VwapIndicator::with_reset(VwapReset::Weekly);
+        let friday = Utc.with_ymd_and_hms(2024, 12, 20, 12, 0, 0).unwrap();
+        let sunday = Utc.with_ymd_and_hms(2024, 12, 22, 12, 0, 0).unwrap();
+        let monday = Utc.with_ymd_and_hms(2024, 12, 23, 0, 0, 1).unwrap();
+
+        vwap.update(dec!(100), dec!(10), friday);
+        vwap.update(dec!(200), dec!(10), sunday);
+        assert_eq!(vwap.value(), Some(dec!(150)));
+
+        vwap.update(dec!(300), dec!(10), monday);
+        assert_eq!(vwap.value(), Some(dec!(300)));
+    }
+
+    #[test]
+    fn test_anchored_vwap() {
+        let anchor = Utc.with_ymd_and_hms(2024, 12, 20, 12, 0, 0).unwrap();
+        let mut vwap = VwapIndicator::with_reset("anchored:2024-12-20T12:00:00Z".parse().unwrap());
+
+        // Trades before the anchor are ignored
+        vwap.update(dec!(50), dec!(10), anchor - chrono::Duration::seconds(1));
+        assert_eq!(vwap.value(), None);
+
+        vwap.update(dec!(100), dec!(10), anchor);
+        vwap.update(dec!(200), dec!(10), anchor + chrono::Duration::days(3));
+        assert_eq!(vwap.value(), Some(dec!(150)));
+    }
+
+    #[test]
+    fn test_rolling_vwap_evicts_old_trades() {
+        let mut vwap = VwapIndicator::with_reset("rolling:60s".parse().unwrap());
+        let start = Utc.with_ymd_and_hms(2024, 12, 20, 12, 0, 0).unwrap();
+
+        vwap.update(dec!(100), dec!(10), start);
+        vwap.update(dec!(200), dec!(10), start + chrono::Duration::seconds(30));
+        assert_eq!(vwap.value(), Some(dec!(150)));
+
+        // The first trade is now older than the 60s window
+        vwap.update(dec!(300), dec!(10), start + chrono::Duration::seconds(61));
+        assert_eq!(vwap.value(), Some(dec!(250)));
+        assert_eq!(vwap.total_volume(), dec!(20));
+        assert_eq!(vwap.trade_count(), 2);
+        assert_eq!(vwap.window_trades.len(), 2);
+
+        // Bands only reflect the trades still inside the window
+        assert_eq!(vwap.std_dev(), Some(dec!(50)));
+    }
+}
@@ -1,5 +1,12 @@
 pub mod data;
 pub mod vwap;
-mod indicators;
+pub mod indicators;
 mod position;
-pub mod grid;
\ No newline at end of file
+mod order;
+pub mod grid;
+pub mod regime;
+pub mod exit;
+pub mod equity;
+pub mod allocation;
+pub mod transition;
+pub mod warmup;
\ No newline at end of file
@@ -0,0 +1,10 @@
+use barter_execution::order::{OrderKind, TimeInForce};
+
+/// Time in force for a strategy's entry orders: limit orders rest for the day,
+/// market orders fill immediately or not at all
+pub fn time_in_force(kind: OrderKind) -> TimeInForce {
+    match kind {
+        OrderKind::Market => TimeInForce::ImmediateOrCancel,
+        _ => TimeInForce::GoodUntilEndOfDay,
+    }
+}
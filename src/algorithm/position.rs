@@ -1,5 +1,85 @@
+use barter::engine::state::EngineState;
+use barter_instrument::asset::AssetIndex;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use crate::algorithm::data::{AlgorithmData, Indicator};
+use crate::algorithm::equity::{quote_equity, CapitalMode};
+
+/// How the size of a single order is derived, resolved per signal
+#[derive(Debug, Clone, PartialEq)]
+pub enum SizingModel {
+    /// Spend `wallet_size * risk_percentage` on the asset
+    Notional,
+    /// Lose `wallet_size * risk_percentage` if price moves `stop_distance` (in price units) against the position
+    FixedFractional { stop_distance: Decimal },
+    /// Scale notional so the position's volatility matches `target_volatility` of the wallet,
+    /// with `volatility` the asset's per-bar volatility as a fraction of price
+    VolatilityTarget { target_volatility: Decimal, volatility: Decimal },
+    /// Kelly fraction of the wallet for the given edge, capped at `cap`
+    Kelly { win_rate: Decimal, payoff_ratio: Decimal, cap: Decimal },
+}
+
+/// Where volatility-targeted sizing takes its volatility estimate from
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolatilitySource {
+    /// ATR relative to price
+    Atr,
+    /// Standard deviation of bar returns
+    Realized,
+}
+
+/// Sizing a strategy is configured with, turned into a [`SizingModel`] for each signal
+/// from the signal's stop distance and the instrument's indicators
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SizingMethod {
+    Notional,
+    StopRisk,
+    VolatilityTarget { target_volatility: Decimal, source: VolatilitySource },
+    Kelly { win_rate: Decimal, payoff_ratio: Decimal, cap: Decimal },
+}
+
+impl SizingMethod {
+    /// Resolve the model for a signal, falling back to notional sizing when the
+    /// stop distance or volatility it needs isn't available
+    pub fn model(&self, price: Decimal, stop_distance: Option<Decimal>, data: &AlgorithmData) -> SizingModel {
+        match self {
+            SizingMethod::Notional => SizingModel::Notional,
+            SizingMethod::StopRisk => match stop_distance {
+                Some(stop_distance) if stop_distance > dec!(0) => SizingModel::FixedFractional { stop_distance },
+                _ => SizingModel::Notional,
+            },
+            SizingMethod::VolatilityTarget { target_volatility, source } => {
+                let volatility = match source {
+                    VolatilitySource::Atr => data.atr.value().filter(|_| price > dec!(0)).map(|atr| atr / price),
+                    VolatilitySource::Realized => data.realized_volatility.value(),
+                };
+                match volatility {
+                    Some(volatility) if volatility > dec!(0) => SizingModel::VolatilityTarget {
+                        target_volatility: *target_volatility,
+                        volatility,
+                    },
+                    _ => SizingModel::Notional,
+                }
+            }
+            SizingMethod::Kelly { win_rate, payoff_ratio, cap } => SizingModel::Kelly {
+                win_rate: *win_rate,
+                payoff_ratio: *payoff_ratio,
+                cap: *cap,
+            },
+        }
+    }
+
+    /// Indicator the volatility estimate comes from, which must be warmed up before sizing off it
+    pub fn required_indicator(&self) -> Option<Indicator> {
+        match self {
+            SizingMethod::VolatilityTarget { source: VolatilitySource::Atr, .. } => Some(Indicator::Atr),
+            SizingMethod::VolatilityTarget { source: VolatilitySource::Realized, .. } => Some(Indicator::RealizedVolatility),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct PositionSizer {
@@ -31,6 +111,7 @@ impl PositionSizer {
     ///
     /// # Returns
     /// * `Decimal` - The exact quantity to purchase
+    #[allow(dead_code)]
     pub fn calculate_quantity(&self, price: Decimal) -> Decimal {
         if price <= Decimal::ZERO {
             return Decimal::ZERO;
@@ -46,6 +127,48 @@ impl PositionSizer {
         quantity.round_dp(8)
     }
 
+    /// Calculates the quantity for a sizing model, never committing more than the wallet
+    ///
+    /// # Arguments
+    /// * `price` - Current price of the asset
+    /// * `model` - Sizing model picked for the signal
+    ///
+    /// # Returns
+    /// * `Decimal` - The quantity to trade, rounded to 8 decimal places
+    pub fn calculate_quantity_with(&self, price: Decimal, model: &SizingModel) -> Decimal {
+        if price <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        let quantity = match model {
+            SizingModel::Notional => self.risk_amount() / price,
+            SizingModel::FixedFractional { stop_distance } => {
+                if *stop_distance <= Decimal::ZERO {
+                    return Decimal::ZERO;
+                }
+                self.risk_amount() / stop_distance
+            }
+            SizingModel::VolatilityTarget { target_volatility, volatility } => {
+                if *volatility <= Decimal::ZERO {
+                    return Decimal::ZERO;
+                }
+                self.wallet_size * target_volatility / volatility / price
+            }
+            SizingModel::Kelly { win_rate, payoff_ratio, cap } => {
+                if *payoff_ratio <= Decimal::ZERO {
+                    return Decimal::ZERO;
+                }
+                // f* = p - (1 - p) / b
+                let kelly = win_rate - (dec!(1) - win_rate) / payoff_ratio;
+                let fraction = kelly.max(Decimal::ZERO).min(*cap);
+                self.wallet_size * fraction / price
+            }
+        };
+
+        // No leverage: cap at what the whole wallet buys
+        quantity.min(self.wallet_size / price).round_dp(8)
+    }
+
     /// Calculates the dollar value of the position
     ///
     /// # Arguments
@@ -53,6 +176,7 @@ impl PositionSizer {
     ///
     /// # Returns
     /// * `Decimal` - The dollar value of the calculated position
+    #[allow(dead_code)]
     pub fn calculate_position_value(&self, price: Decimal) -> Decimal {
         let quantity = self.calculate_quantity(price);
         quantity * price
@@ -63,7 +187,21 @@ impl PositionSizer {
         self.wallet_size = new_wallet_size;
     }
 
+    /// Size from the `quote` asset equity in the latest engine balances,
+    /// keeping the previous wallet size until a balance is known
+    pub fn refresh_wallet_size<GlobalData>(
+        &mut self,
+        state: &EngineState<GlobalData, AlgorithmData>,
+        quote: AssetIndex,
+        capital: Decimal,
+        capital_mode: CapitalMode,
+    ) {
+        let Some(equity) = quote_equity(state, quote) else { return; };
+        self.update_wallet_size(capital_mode.wallet_size(capital, equity));
+    }
+
     /// Updates the risk percentage
+    #[cfg(test)]
     pub fn update_risk_percentage(&mut self, new_risk_percentage: Decimal) {
         self.risk_percentage = new_risk_percentage;
     }
@@ -132,6 +270,41 @@ mod tests {
         assert_eq!(quantity, dec!(0));
     }
 
+    #[test]
+    fn test_fixed_fractional_sizing() {
+        let sizer = PositionSizer::with_risk(dec!(10000), dec!(0.01)); // $100 at risk
+
+        // $100 risk over a $5 stop is 20 units, whatever the price
+        let quantity = sizer.calculate_quantity_with(dec!(100), &SizingModel::FixedFractional { stop_distance: dec!(5) });
+        assert_eq!(quantity, dec!(20));
+
+        // A tight stop would need more than the wallet, so it's capped
+        let quantity = sizer.calculate_quantity_with(dec!(100), &SizingModel::FixedFractional { stop_distance: dec!(0.01) });
+        assert_eq!(quantity, dec!(100));
+    }
+
+    #[test]
+    fn test_volatility_target_sizing() {
+        let sizer = PositionSizer::new(dec!(10000));
+
+        // 1% target over a 2% volatile asset is half the wallet
+        let model = SizingModel::VolatilityTarget { target_volatility: dec!(0.01), volatility: dec!(0.02) };
+        assert_eq!(sizer.calculate_quantity_with(dec!(50), &model), dec!(100));
+    }
+
+    #[test]
+    fn test_kelly_sizing() {
+        let sizer = PositionSizer::new(dec!(10000));
+
+        // 60% win rate at 1:1 is a 20% Kelly fraction, capped at 10%
+        let model = SizingModel::Kelly { win_rate: dec!(0.6), payoff_ratio: dec!(1), cap: dec!(0.1) };
+        assert_eq!(sizer.calculate_quantity_with(dec!(100), &model), dec!(10));
+
+        // Negative edge sizes to zero
+        let model = SizingModel::Kelly { win_rate: dec!(0.4), payoff_ratio: dec!(1), cap: dec!(0.1) };
+        assert_eq!(sizer.calculate_quantity_with(dec!(100), &model), dec!(0));
+    }
+
     #[test]
     fn test_update_methods() {
         let mut sizer = PositionSizer::new(dec!(50000));
@@ -0,0 +1,245 @@
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+use std::collections::VecDeque;
+use std::fmt;
+use crate::algorithm::indicators::adx::AdxValue;
+use crate::algorithm::indicators::bar::Bar;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketRegime {
+    TrendingUp,
+    TrendingDown,
+    Ranging,
+    HighVolatility,
+}
+
+impl fmt::Display for MarketRegime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MarketRegime::TrendingUp => "TRENDING_UP",
+            MarketRegime::TrendingDown => "TRENDING_DOWN",
+            MarketRegime::Ranging => "RANGING",
+            MarketRegime::HighVolatility => "HIGH_VOLATILITY",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Inputs behind the latest classification, kept for logging
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegimeMetrics {
+    pub adx: Decimal,
+    pub band_slope: Decimal,
+    pub volatility_ratio: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegimeConfig {
+    /// ADX at or above which the market counts as trending
+    pub trend_adx: Decimal,
+    /// Number of bars the band midline slope is measured over
+    pub slope_lookback: usize,
+    /// Minimum relative midline change over the lookback for a trend (0.002 = 0.2%)
+    pub slope_threshold: Decimal,
+    /// Bars in the short (current) volatility window
+    pub short_volatility_window: usize,
+    /// Bars in the long (baseline) volatility window
+    pub long_volatility_window: usize,
+    /// Short / long volatility ratio at or above which the market is high-vol
+    pub high_volatility_ratio: Decimal,
+}
+
+impl Default for RegimeConfig {
+    fn default() -> Self {
+        Self {
+            trend_adx: dec!(25),
+            slope_lookback: 10,
+            slope_threshold: dec!(0.002),
+            short_volatility_window: 15,
+            long_volatility_window: 60,
+            high_volatility_ratio: dec!(2),
+        }
+    }
+}
+
+/// Classifies the market regime from trend strength, volatility and band slope on each bar close
+#[derive(Debug, Clone)]
+pub struct RegimeDetector {
+    config: RegimeConfig,
+    midlines: VecDeque<Decimal>,
+    returns: VecDeque<Decimal>,
+    last_close: Option<Decimal>,
+    current: Option<MarketRegime>,
+    metrics: Option<RegimeMetrics>,
+}
+
+impl RegimeDetector {
+    pub fn new(config: RegimeConfig) -> Self {
+        Self {
+            config,
+            midlines: VecDeque::new(),
+            returns: VecDeque::new(),
+            last_close: None,
+            current: None,
+            metrics: None,
+        }
+    }
+
+    /// Updates the classification with a closed bar, the current ADX and the band midline
+    pub fn update(&mut self, bar: &Bar, adx: Option<AdxValue>, midline: Option<Decimal>) {
+        if let Some(last_close) = self.last_close.replace(bar.close)
+            && last_close != dec!(0)
+        {
+            self.returns.push_back((bar.close - last_close) / last_close);
+            if self.returns.len() > self.config.long_volatility_window {
+                self.returns.pop_front();
+            }
+        }
+
+        if let Some(midline) = midline {
+            self.midlines.push_back(midline);
+            if self.midlines.len() > self.config.slope_lookback + 1 {
+                self.midlines.pop_front();
+            }
+        }
+
+        let (Some(adx), Some(band_slope), Some(volatility_ratio)) =
+            (adx, self.band_slope(), self.volatility_ratio())
+        else {
+            return;
+        };
+
+        let trending = adx.adx >= self.config.trend_adx;
+        let regime = if volatility_ratio >= self.config.high_volatility_ratio {
+            MarketRegime::HighVolatility
+        } else if trending && band_slope >= self.config.slope_threshold && adx.plus_di > adx.minus_di {
+            MarketRegime::TrendingUp
+        } else if trending && band_slope <= -self.config.slope_threshold && adx.minus_di > adx.plus_di {
+            MarketRegime::TrendingDown
+        } else {
+            MarketRegime::Ranging
+        };
+
+        self.current = Some(regime);
+        self.metrics = Some(RegimeMetrics { adx: adx.adx, band_slope, volatility_ratio });
+    }
+
+    pub fn current(&self) -> Option<MarketRegime> {
+        self.current
+    }
+
+    pub fn metrics(&self) -> Option<RegimeMetrics> {
+        self.metrics
+    }
+
+    /// Relative change of the band midline over the lookback
+    fn band_slope(&self) -> Option<Decimal> {
+        if self.midlines.len() <= self.config.slope_lookback {
+            return None;
+        }
+        let oldest = *self.midlines.front()?;
+        let newest = *self.midlines.back()?;
+        if oldest == dec!(0) {
+            return None;
+        }
+        Some((newest - oldest) / oldest)
+    }
+
+    /// Volatility of recent returns relative to the longer baseline
+    fn volatility_ratio(&self) -> Option<Decimal> {
+        if self.returns.len() < self.config.short_volatility_window {
+            return None;
+        }
+
+        let recent = self.returns.iter().rev().take(self.config.short_volatility_window);
+        let short_volatility = Self::std_dev(recent.copied())?;
+        let long_volatility = Self::std_dev(self.returns.iter().copied())?;
+
+        if long_volatility == dec!(0) {
+            return Some(if short_volatility == dec!(0) { dec!(1) } else { self.config.high_volatility_ratio });
+        }
+        Some(short_volatility / long_volatility)
+    }
+
+    fn std_dev(values: impl Iterator<Item = Decimal> + Clone) -> Option<Decimal> {
+        let count = Decimal::from(values.clone().count());
+        if count == dec!(0) {
+            return None;
+        }
+        let mean = values.clone().sum::<Decimal>() / count;
+        let variance = values.map(|value| (value - mean) * (value - mean)).sum::<Decimal>() / count;
+        variance.sqrt()
+    }
+}
+
+impl Default for RegimeDetector {
+    fn default() -> Self {
+        Self::new(RegimeConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn config() -> RegimeConfig {
+        RegimeConfig {
+            trend_adx: dec!(25),
+            slope_lookback: 3,
+            slope_threshold: dec!(0.002),
+            short_volatility_window: 3,
+            long_volatility_window: 9,
+            high_volatility_ratio: dec!(2),
+        }
+    }
+
+    fn bar(close: Decimal) -> Bar {
+        Bar { open: close, high: close, low: close, close, volume: dec!(1), start: Utc::now() }
+    }
+
+    fn adx(adx: Decimal, plus_di: Decimal, minus_di: Decimal) -> Option<AdxValue> {
+        Some(AdxValue { adx, plus_di, minus_di })
+    }
+
+    #[test]
+    fn test_trending_up() {
+        let mut detector = RegimeDetector::new(config());
+
+        // Steady 1% per bar climb with a rising midline and strong +DI
+        let mut close = dec!(100);
+        for _ in 0..6 {
+            close *= dec!(1.01);
+            detector.update(&bar(close), adx(dec!(40), dec!(30), dec!(5)), Some(close));
+        }
+
+        assert_eq!(detector.current(), Some(MarketRegime::TrendingUp));
+    }
+
+    #[test]
+    fn test_ranging_when_adx_is_weak() {
+        let mut detector = RegimeDetector::new(config());
+
+        let mut close = dec!(100);
+        for _ in 0..6 {
+            close *= dec!(1.01);
+            detector.update(&bar(close), adx(dec!(15), dec!(30), dec!(5)), Some(close));
+        }
+
+        assert_eq!(detector.current(), Some(MarketRegime::Ranging));
+    }
+
+    #[test]
+    fn test_high_volatility() {
+        let mut detector = RegimeDetector::new(RegimeConfig { long_volatility_window: 30, ..config() });
+
+        // A long calm stretch of ±1% moves, then large swings
+        let calm = (0..28).map(|bar| if bar % 2 == 0 { 100 } else { 101 });
+        for close in calm.chain([110, 95, 112]) {
+            detector.update(&bar(Decimal::from(close)), adx(dec!(15), dec!(20), dec!(20)), Some(dec!(100)));
+        }
+
+        assert_eq!(detector.current(), Some(MarketRegime::HighVolatility));
+        assert!(detector.metrics().unwrap().volatility_ratio >= dec!(2));
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::time::Duration;
+
+/// What has to happen before an observed state change counts as a transition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Confirmation {
+    /// Accept the change on the tick it is first seen
+    Immediate,
+    /// The new state must be observed on this many consecutive ticks
+    Ticks(usize),
+    /// The new state must still hold after the bar it appeared in has closed
+    #[allow(dead_code)]
+    BarClose,
+}
+
+/// Damping applied to strategy state transitions so price chopping around a boundary
+/// doesn't fire a signal on every tick
+#[derive(Debug, Clone)]
+pub struct TransitionFilter {
+    /// Relative margin price must move past a boundary by to change state (0.001 = 0.1%)
+    pub hysteresis: Decimal,
+    /// Minimum time a new state must persist before it is accepted
+    pub min_dwell: Duration,
+    pub confirmation: Confirmation,
+}
+
+impl TransitionFilter {
+    pub fn new(hysteresis: Decimal, min_dwell: Duration, confirmation: Confirmation) -> Self {
+        Self { hysteresis, min_dwell, confirmation }
+    }
+}
+
+impl Default for TransitionFilter {
+    /// No damping, every observed change is a transition
+    fn default() -> Self {
+        Self::new(dec!(0), Duration::ZERO, Confirmation::Immediate)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Candidate<S> {
+    state: S,
+    ticks: usize,
+    first_seen: DateTime<Utc>,
+    bar_start: Option<DateTime<Utc>>,
+}
+
+/// Tracks the confirmed state of an instrument and the candidate state waiting to replace it
+#[derive(Debug, Clone)]
+pub struct StateConfirmation<S> {
+    confirmed: S,
+    candidate: Option<Candidate<S>>,
+}
+
+impl<S: Clone + PartialEq> StateConfirmation<S> {
+    pub fn new(initial: S) -> Self {
+        Self { confirmed: initial, candidate: None }
+    }
+
+    pub fn confirmed(&self) -> &S {
+        &self.confirmed
+    }
+
+    /// Feeds the state observed on this tick and returns the confirmed state
+    ///
+    /// # Arguments
+    /// * `observed` - State derived from the latest tick
+    /// * `time` - Time of the latest tick
+    /// * `bar_start` - Start of the bar currently being built, used for bar-close confirmation
+    /// * `filter` - Dwell and confirmation requirements
+    pub fn update(
+        &mut self,
+        observed: S,
+        time: DateTime<Utc>,
+        bar_start: Option<DateTime<Utc>>,
+        filter: &TransitionFilter,
+    ) -> S {
+        if observed == self.confirmed {
+            self.candidate = None;
+            return self.confirmed.clone();
+        }
+
+        let candidate = match self.candidate.take() {
+            Some(mut candidate) if candidate.state == observed => {
+                candidate.ticks += 1;
+                candidate
+            }
+            _ => Candidate { state: observed, ticks: 1, first_seen: time, bar_start },
+        };
+
+        let dwelled = (time - candidate.first_seen).to_std().unwrap_or_default() >= filter.min_dwell;
+        let confirmed = match filter.confirmation {
+            Confirmation::Immediate => true,
+            Confirmation::Ticks(ticks) => candidate.ticks >= ticks,
+            Confirmation::BarClose => match (candidate.bar_start, bar_start) {
+                (Some(first), Some(current)) => current > first,
+                _ => false,
+            },
+        };
+
+        if dwelled && confirmed {
+            self.confirmed = candidate.state;
+        } else {
+            self.candidate = Some(candidate);
+        }
+        self.confirmed.clone()
+    }
+}
+
+/// Whether a per-instrument cooldown started at `last_signal` is still running at `time`
+pub fn in_cooldown(last_signal: Option<DateTime<Utc>>, time: DateTime<Utc>, cooldown: Option<Duration>) -> bool {
+    match (last_signal, cooldown) {
+        (Some(last_signal), Some(cooldown)) => (time - last_signal).to_std().unwrap_or_default() < cooldown,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_confirmation() {
+        let filter = TransitionFilter::new(dec!(0), Duration::ZERO, Confirmation::Ticks(3));
+        let mut confirmation = StateConfirmation::new(0);
+        let now = Utc::now();
+
+        assert_eq!(confirmation.update(1, now, None, &filter), 0);
+        assert_eq!(confirmation.update(1, now, None, &filter), 0);
+        // Flipping back resets the count
+        assert_eq!(confirmation.update(0, now, None, &filter), 0);
+        assert_eq!(confirmation.update(1, now, None, &filter), 0);
+        assert_eq!(confirmation.update(1, now, None, &filter), 0);
+        assert_eq!(confirmation.update(1, now, None, &filter), 1);
+    }
+
+    #[test]
+    fn test_dwell_and_bar_close_confirmation() {
+        let filter = TransitionFilter::new(dec!(0), Duration::from_secs(30), Confirmation::BarClose);
+        let mut confirmation = StateConfirmation::new(0);
+        let bar = Utc::now();
+
+        assert_eq!(confirmation.update(1, bar, Some(bar), &filter), 0);
+        // Dwelled long enough but the bar hasn't closed
+        assert_eq!(confirmation.update(1, bar + chrono::Duration::seconds(40), Some(bar), &filter), 0);
+        let next_bar = bar + chrono::Duration::seconds(60);
+        assert_eq!(confirmation.update(1, next_bar, Some(next_bar), &filter), 1);
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let now = Utc::now();
+        let cooldown = Some(Duration::from_secs(10));
+
+        assert!(!in_cooldown(None, now, cooldown));
+        assert!(in_cooldown(Some(now), now + chrono::Duration::seconds(5), cooldown));
+        assert!(!in_cooldown(Some(now), now + chrono::Duration::seconds(10), cooldown));
+        assert!(!in_cooldown(Some(now), now, None));
+    }
+}
@@ -3,6 +3,7 @@ use barter::engine::state::EngineState;
 use barter::engine::state::global::DefaultGlobalData;
 use barter::engine::state::instrument::data::InstrumentDataState;
 use barter::engine::state::instrument::filter::InstrumentFilter;
+use barter::engine::state::order::Orders;
 use barter::strategy::algo::AlgoStrategy;
 use barter::strategy::close_positions::ClosePositionsStrategy;
 use barter::strategy::on_disconnect::OnDisconnectStrategy;
@@ -17,27 +18,95 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use smol_str::SmolStr;
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Mutex;
+use std::time::Duration;
 use barter_instrument::Side;
-use chrono::Local;
-use crate::algorithm::data::AlgorithmData;
-use crate::algorithm::position::PositionSizer;
+use chrono::{DateTime, Local, Utc};
+use crate::algorithm::allocation::{reserve_budget, CapitalAllocator};
+use crate::algorithm::data::{AlgorithmData, Indicator, StrategyData};
+use crate::algorithm::equity::CapitalMode;
+use crate::algorithm::exit::ExitRules;
+use crate::algorithm::indicators::vwap::{VwapBands, VwapReset};
+use crate::algorithm::order::time_in_force;
+use crate::algorithm::position::{PositionSizer, SizingMethod, SizingModel};
+use crate::algorithm::transition::{in_cooldown, StateConfirmation, TransitionFilter};
 
 #[derive(Debug, Clone, PartialEq)]
 enum RsiState {
     Neutral,
-    Overbought,  // RSI > 80
-    Oversold,    // RSI < 20
+    Overbought,  // RSI > overbought threshold (80 by default)
+    Oversold,    // RSI < oversold threshold (20 by default)
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, PartialEq)]
 enum VwapState {
     AboveVwap,    // Price > VWAP
     BelowVwap,    // Price < VWAP
-    AtVwap,       // Price ≈ VWAP (within 0.1% threshold)
+    AtVwap,       // Price ≈ VWAP (within the deadband, 0.1% by default)
 }
 
-#[derive(Debug, Clone)]
+/// How RSI and VWAP signals are merged into the signal that is traded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignalCombination {
+    /// Trade RSI extremes only
+    RsiOnly,
+    /// Trade VWAP crosses only
+    VwapOnly,
+    /// Trade only when RSI and VWAP fire the same side on the same tick
+    RequireAgreement,
+    /// Trade either signal, RSI wins when they disagree
+    RsiPrecedence,
+    /// Each signal votes +weight (buy) or -weight (sell), trade when |score| >= min_score
+    WeightedVote {
+        rsi_weight: Decimal,
+        vwap_weight: Decimal,
+        min_score: Decimal,
+    },
+}
+
+impl FromStr for SignalCombination {
+    type Err = String;
+
+    /// Parses `rsi`, `vwap`, `agreement`, `precedence` and `vote:1,1,1` (RSI weight, VWAP weight, min score)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, params) = s.split_once(':').unwrap_or((s, ""));
+        match kind.to_lowercase().as_str() {
+            "rsi" => Ok(SignalCombination::RsiOnly),
+            "vwap" => Ok(SignalCombination::VwapOnly),
+            "agreement" => Ok(SignalCombination::RequireAgreement),
+            "precedence" => Ok(SignalCombination::RsiPrecedence),
+            "vote" => {
+                let weights = params.split(',')
+                    .map(|value| value.trim().parse::<Decimal>().ok().filter(|value| *value >= Decimal::ZERO))
+                    .collect::<Option<Vec<_>>>();
+                match weights.as_deref() {
+                    Some(&[rsi_weight, vwap_weight, min_score]) => Ok(SignalCombination::WeightedVote { rsi_weight, vwap_weight, min_score }),
+                    _ => Err(format!("invalid vote weights '{}', expected RSI_WEIGHT,VWAP_WEIGHT,MIN_SCORE", params)),
+                }
+            }
+            other => Err(format!("unknown signal combination '{}', expected rsi, vwap, agreement, precedence or vote", other)),
+        }
+    }
+}
+
+impl fmt::Display for SignalCombination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignalCombination::RsiOnly => write!(f, "rsi"),
+            SignalCombination::VwapOnly => write!(f, "vwap"),
+            SignalCombination::RequireAgreement => write!(f, "agreement"),
+            SignalCombination::RsiPrecedence => write!(f, "precedence"),
+            SignalCombination::WeightedVote { rsi_weight, vwap_weight, min_score } => {
+                write!(f, "vote:{},{},{}", rsi_weight, vwap_weight, min_score)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 enum SignalType {
     Buy,
     Sell,
@@ -54,30 +123,68 @@ struct TradingSignal {
     vwap: Decimal,
     rsi: Decimal,
     previous_rsi: Decimal,
-    signal_source: String, // "RSI" or "VWAP" or "COMBINED"
+    cumulative_delta: Decimal,
+    signal_source: String, // "RSI", "VWAP", "COMBINED", "RSI_PRECEDENCE" or "WEIGHTED_VOTE"
+}
+
+/// Best price seen over the life of the current engine position
+#[derive(Debug, Clone)]
+struct TrackedPosition {
+    time_enter: DateTime<Utc>,
+    best_price: Decimal,
 }
 
 pub struct Vwap {
     last_rsi_state: Mutex<HashMap<String, RsiState>>,
     last_rsi_value: Mutex<HashMap<String, Decimal>>,
-    last_vwap_state: Mutex<HashMap<String, VwapState>>,
-    position_sizer: PositionSizer,
+    last_vwap_state: Mutex<HashMap<String, StateConfirmation<VwapState>>>,
+    last_signal_time: Mutex<HashMap<String, DateTime<Utc>>>,
+    tracked_positions: Mutex<HashMap<String, TrackedPosition>>,
+    position_sizer: Mutex<PositionSizer>,
+    capital: Decimal,
+    capital_mode: CapitalMode,
+    allocator: Option<CapitalAllocator>,
+    sizing: SizingMethod,
+    min_flow_ratio: Option<Decimal>,
+    vwap_reset: VwapReset,
+    band_sigma: Option<usize>,
+    vwap_filter: TransitionFilter,
+    signal_cooldown: Option<Duration>,
+    rsi_overbought: Decimal,
+    rsi_oversold: Decimal,
+    vwap_threshold: Decimal,
+    combination: SignalCombination,
+    exit_rules: Option<ExitRules>,
+    order_kind: OrderKind,
 }
 
 impl Vwap {
-    #[allow(dead_code)]
     pub const ID: StrategyId = StrategyId(SmolStr::new_static("vwap"));
 
-    // VWAP deviation threshold (0.1%)
-    const VWAP_THRESHOLD: Decimal = dec!(0.001);
-
     /// Creates a new Vwap strategy with custom wallet size
     pub fn new(wallet_size: Decimal) -> Self {
         Self {
             last_rsi_state: Mutex::new(HashMap::new()),
             last_rsi_value: Mutex::new(HashMap::new()),
             last_vwap_state: Mutex::new(HashMap::new()),
-            position_sizer: PositionSizer::new(wallet_size),
+            last_signal_time: Mutex::new(HashMap::new()),
+            tracked_positions: Mutex::new(HashMap::new()),
+            position_sizer: Mutex::new(PositionSizer::new(wallet_size)),
+            capital: wallet_size,
+            capital_mode: CapitalMode::Compounding,
+            allocator: None,
+            sizing: SizingMethod::Notional,
+            min_flow_ratio: None,
+            vwap_reset: VwapReset::Daily,
+            band_sigma: None,
+            vwap_filter: TransitionFilter::default(),
+            signal_cooldown: None,
+            rsi_overbought: dec!(80),
+            rsi_oversold: dec!(20),
+            vwap_threshold: dec!(0.001), // 0.1% deadband around VWAP
+            combination: SignalCombination::RsiPrecedence,
+            exit_rules: None,
+            order_kind: OrderKind::Limit,
         }
     }
 
@@ -87,24 +194,167 @@ impl Vwap {
             last_rsi_state: Mutex::new(HashMap::new()),
             last_rsi_value: Mutex::new(HashMap::new()),
             last_vwap_state: Mutex::new(HashMap::new()),
-            position_sizer: PositionSizer::with_risk(wallet_size, risk_percentage),
+            last_signal_time: Mutex::new(HashMap::new()),
+            tracked_positions: Mutex::new(HashMap::new()),
+            position_sizer: Mutex::new(PositionSizer::with_risk(wallet_size, risk_percentage)),
+            capital: wallet_size,
+            capital_mode: CapitalMode::Compounding,
+            allocator: None,
+            sizing: SizingMethod::Notional,
+            min_flow_ratio: None,
+            vwap_reset: VwapReset::Daily,
+            band_sigma: None,
+            vwap_filter: TransitionFilter::default(),
+            signal_cooldown: None,
+            rsi_overbought: dec!(80),
+            rsi_oversold: dec!(20),
+            vwap_threshold: dec!(0.001), // 0.1% deadband around VWAP
+            combination: SignalCombination::RsiPrecedence,
+            exit_rules: None,
+            order_kind: OrderKind::Limit,
+        }
+    }
+
+    /// Only act on VWAP crosses when at least `min_ratio` of recent aggressor volume
+    /// was on the side of the signal (e.g. 0.55 = 55% of volume bought for a buy cross)
+    pub fn with_flow_confirmation(mut self, min_ratio: Decimal) -> Self {
+        self.min_flow_ratio = Some(min_ratio);
+        self
+    }
+
+    /// When each instrument's VWAP starts accumulating afresh, eg/ at UTC midnight (the default),
+    /// over a rolling window or from an anchor such as a session open or swing low
+    pub fn with_vwap_reset(mut self, reset: VwapReset) -> Self {
+        self.vwap_reset = reset;
+        self
+    }
+
+    /// Ignore VWAP crosses that leave price stretched beyond the ±1σ or ±2σ band on the side of
+    /// the signal, eg/ a buy cross already above the upper band
+    pub fn with_band_filter(mut self, sigma: usize) -> Self {
+        self.band_sigma = Some(sigma);
+        self
+    }
+
+    /// Require VWAP crosses to clear a hysteresis margin and be confirmed before the state changes
+    pub fn with_vwap_filter(mut self, filter: TransitionFilter) -> Self {
+        self.vwap_filter = filter;
+        self
+    }
+
+    /// Ignore new signals on an instrument for `cooldown` after it last signalled
+    pub fn with_signal_cooldown(mut self, cooldown: Duration) -> Self {
+        self.signal_cooldown = Some(cooldown);
+        self
+    }
+
+    /// RSI levels above / below which the market counts as overbought / oversold
+    pub fn with_rsi_thresholds(mut self, overbought: Decimal, oversold: Decimal) -> Self {
+        self.rsi_overbought = overbought;
+        self.rsi_oversold = oversold;
+        self
+    }
+
+    /// Relative distance from VWAP within which price counts as at VWAP (0.001 = 0.1%)
+    pub fn with_vwap_deadband(mut self, threshold: Decimal) -> Self {
+        self.vwap_threshold = threshold;
+        self
+    }
+
+    /// How RSI and VWAP signals are merged
+    pub fn with_combination(mut self, combination: SignalCombination) -> Self {
+        self.combination = combination;
+        self
+    }
+
+    /// Close open positions with stop-loss, take-profit, trailing stop and time stop rules
+    #[allow(dead_code)]
+    pub fn with_exit_rules(mut self, exit_rules: ExitRules) -> Self {
+        self.exit_rules = Some(exit_rules);
+        self
+    }
+
+    /// How order quantity is derived for each signal (notional by default)
+    #[allow(dead_code)]
+    pub fn with_sizing(mut self, sizing: SizingMethod) -> Self {
+        self.sizing = sizing;
+        self
+    }
+
+    /// Whether order size follows equity (compounding, the default) or stays at the starting capital
+    #[allow(dead_code)]
+    pub fn with_capital_mode(mut self, capital_mode: CapitalMode) -> Self {
+        self.capital_mode = capital_mode;
+        self
+    }
+
+    /// Enter with market orders instead of resting limits, eg/ for the mock exchange which only fills market orders
+    pub fn with_market_orders(mut self) -> Self {
+        self.order_kind = OrderKind::Market;
+        self
+    }
+
+    /// Give each instrument a share of equity and reject buys that would exceed what is left of it
+    #[allow(dead_code)]
+    pub fn with_capital_allocator(mut self, allocator: CapitalAllocator) -> Self {
+        self.allocator = Some(allocator);
+        self
+    }
+
+    /// Indicators that must be warmed up before the strategy trades an instrument
+    fn required_indicators(&self) -> Vec<Indicator> {
+        let mut required = vec![Indicator::Rsi, Indicator::Vwap];
+        if self.min_flow_ratio.is_some() {
+            required.push(Indicator::TradeFlow);
+        }
+        required.extend(self.sizing.required_indicator());
+        required
+    }
+
+    fn is_band_stretched(&self, signal_type: &SignalType, price: Decimal, bands: Option<VwapBands>) -> bool {
+        let (Some(sigma), Some(bands)) = (self.band_sigma, bands) else { return false; };
+        let (lower, upper) = if sigma >= 2 { (bands.lower_2, bands.upper_2) } else { (bands.lower_1, bands.upper_1) };
+
+        match signal_type {
+            SignalType::Buy => price > upper,
+            SignalType::Sell => price < lower,
+            SignalType::None => false,
         }
     }
 
-    fn determine_rsi_state(rsi: Decimal) -> RsiState {
-        if rsi > dec!(80) {
+    fn is_flow_confirmed(&self, signal_type: &SignalType, data: &AlgorithmData) -> bool {
+        let Some(min_ratio) = self.min_flow_ratio else { return true; };
+
+        let ratio = match signal_type {
+            SignalType::Buy => data.trade_flow.buy_ratio(),
+            SignalType::Sell => data.trade_flow.sell_ratio(),
+            SignalType::None => return false,
+        };
+
+        ratio.is_some_and(|ratio| ratio >= min_ratio)
+    }
+
+    fn determine_rsi_state(&self, rsi: Decimal) -> RsiState {
+        if rsi > self.rsi_overbought {
             RsiState::Overbought
-        } else if rsi < dec!(20) {
+        } else if rsi < self.rsi_oversold {
             RsiState::Oversold
         } else {
             RsiState::Neutral
         }
     }
 
-    fn determine_vwap_state(price: Decimal, vwap: Decimal) -> VwapState {
+    /// Classify price against VWAP, widening the `AtVwap` deadband by the hysteresis margin
+    /// while at VWAP and narrowing it while above or below, so small wobbles keep the state
+    fn determine_vwap_state(&self, price: Decimal, vwap: Decimal, previous_state: &VwapState) -> VwapState {
         let deviation = (price - vwap).abs() / vwap;
+        let hysteresis = self.vwap_filter.hysteresis;
+        let threshold = match previous_state {
+            VwapState::AtVwap => self.vwap_threshold + hysteresis,
+            _ => (self.vwap_threshold - hysteresis).max(dec!(0)),
+        };
 
-        if deviation <= Self::VWAP_THRESHOLD {
+        if deviation <= threshold {
             VwapState::AtVwap
         } else if price > vwap {
             VwapState::AboveVwap
@@ -113,6 +363,42 @@ impl Vwap {
         }
     }
 
+    /// Merge the RSI and VWAP signals of a tick according to the combination policy,
+    /// returning the traded side and the rule that fired
+    fn combine_signals(&self, rsi_signal: Option<SignalType>, vwap_signal: Option<SignalType>) -> Option<(SignalType, &'static str)> {
+        match &self.combination {
+            SignalCombination::RsiOnly => rsi_signal.map(|signal_type| (signal_type, "RSI")),
+            SignalCombination::VwapOnly => vwap_signal.map(|signal_type| (signal_type, "VWAP")),
+            SignalCombination::RequireAgreement => match (rsi_signal, vwap_signal) {
+                (Some(rsi_type), Some(vwap_type)) if rsi_type == vwap_type => Some((rsi_type, "COMBINED")),
+                _ => None,
+            },
+            SignalCombination::RsiPrecedence => match (rsi_signal, vwap_signal) {
+                (Some(rsi_type), Some(vwap_type)) if rsi_type == vwap_type => Some((rsi_type, "COMBINED")),
+                (Some(rsi_type), Some(_)) => Some((rsi_type, "RSI_PRECEDENCE")),
+                (Some(rsi_type), None) => Some((rsi_type, "RSI")),
+                (None, Some(vwap_type)) => Some((vwap_type, "VWAP")),
+                (None, None) => None,
+            },
+            SignalCombination::WeightedVote { rsi_weight, vwap_weight, min_score } => {
+                let vote = |signal: &Option<SignalType>, weight: Decimal| match signal {
+                    Some(SignalType::Buy) => weight,
+                    Some(SignalType::Sell) => -weight,
+                    _ => dec!(0),
+                };
+                let score = vote(&rsi_signal, *rsi_weight) + vote(&vwap_signal, *vwap_weight);
+
+                if score > dec!(0) && score >= *min_score {
+                    Some((SignalType::Buy, "WEIGHTED_VOTE"))
+                } else if score < dec!(0) && -score >= *min_score {
+                    Some((SignalType::Sell, "WEIGHTED_VOTE"))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
     fn should_generate_rsi_signal(previous_state: &RsiState, current_state: &RsiState) -> bool {
         matches!(
             (previous_state, current_state),
@@ -159,8 +445,9 @@ impl Vwap {
         let instrument_key = instrument_state.instrument.name_exchange.name().to_string();
 
         // Determine current states
-        let current_rsi_state = Self::determine_rsi_state(rsi);
-        let current_vwap_state = Self::determine_vwap_state(price, vwap);
+        let current_rsi_state = self.determine_rsi_state(rsi);
+        let time = instrument_state.data.last_update.unwrap_or_else(Utc::now);
+        let bar_start = instrument_state.data.bars.current().map(|bar| bar.start);
 
         // Lock and get previous states
         let mut last_rsi_states = self.last_rsi_state.lock().unwrap();
@@ -169,18 +456,30 @@ impl Vwap {
 
         let previous_rsi_state = last_rsi_states.get(&instrument_key).cloned().unwrap_or(RsiState::Neutral);
         let previous_rsi = last_rsi_values.get(&instrument_key).cloned().unwrap_or(dec!(50));
-        let previous_vwap_state = last_vwap_states.get(&instrument_key).cloned().unwrap_or(VwapState::AtVwap);
+        let vwap_confirmation = last_vwap_states
+            .entry(instrument_key.clone())
+            .or_insert_with(|| StateConfirmation::new(VwapState::AtVwap));
+        let previous_vwap_state = vwap_confirmation.confirmed().clone();
+
+        // VWAP state only moves once the change is confirmed
+        let observed_vwap_state = self.determine_vwap_state(price, vwap, &previous_vwap_state);
+        let current_vwap_state = vwap_confirmation.update(observed_vwap_state, time, bar_start, &self.vwap_filter);
 
         // Update state tracking
         last_rsi_states.insert(instrument_key.clone(), current_rsi_state.clone());
         last_rsi_values.insert(instrument_key.clone(), rsi);
-        last_vwap_states.insert(instrument_key.clone(), current_vwap_state.clone());
+
+        // Sit out the cooldown after the last signal, state tracking above keeps running
+        let mut last_signal_times = self.last_signal_time.lock().unwrap();
+        if in_cooldown(last_signal_times.get(&instrument_key).copied(), time, self.signal_cooldown) {
+            return None;
+        }
 
         // Check for RSI signals
         let rsi_signal = if Self::should_generate_rsi_signal(&previous_rsi_state, &current_rsi_state) {
             match current_rsi_state {
-                RsiState::Overbought => Some((SignalType::Sell, "RSI")),
-                RsiState::Oversold => Some((SignalType::Buy, "RSI")),
+                RsiState::Overbought => Some(SignalType::Sell),
+                RsiState::Oversold => Some(SignalType::Buy),
                 _ => None,
             }
         } else {
@@ -192,29 +491,21 @@ impl Vwap {
             let signal_type = Self::get_vwap_signal_type(&previous_vwap_state, &current_vwap_state);
             match signal_type {
                 SignalType::None => None,
-                _ => Some((signal_type, "VWAP")),
+                // Crosses need aggressor flow on the same side when confirmation is enabled
+                _ if !self.is_flow_confirmed(&signal_type, &instrument_state.data) => None,
+                _ if self.is_band_stretched(&signal_type, price, instrument_state.data.vwap.bands()) => None,
+                _ => Some(signal_type),
             }
         } else {
             None
         };
 
-        // Prioritize signals: RSI signals take precedence over VWAP signals for stronger conviction
-        let final_signal = match (rsi_signal, vwap_signal) {
-            (Some((rsi_type, _)), Some((vwap_type, _))) => {
-                // Both signals present - check if they agree
-                if std::mem::discriminant(&rsi_type) == std::mem::discriminant(&vwap_type) {
-                    Some((rsi_type, "COMBINED")) // Both agree, stronger signal
-                } else {
-                    Some((rsi_type, "RSI")) // RSI takes precedence when they disagree
-                }
-            },
-            (Some((signal_type, source)), None) => Some((signal_type, source)),
-            (None, Some((signal_type, source))) => Some((signal_type, source)),
-            (None, None) => None,
-        };
+        // Merge the two signals according to the configured policy
+        let final_signal = self.combine_signals(rsi_signal, vwap_signal);
 
         // Return signal if we have one
         if let Some((signal_type, source)) = final_signal {
+            last_signal_times.insert(instrument_key.clone(), time);
             Some(TradingSignal {
                 signal_type,
                 instrument_key,
@@ -224,6 +515,7 @@ impl Vwap {
                 vwap,
                 rsi,
                 previous_rsi,
+                cumulative_delta: instrument_state.data.trade_flow.cumulative_delta(),
                 signal_source: source.to_string(),
             })
         } else {
@@ -231,21 +523,108 @@ impl Vwap {
         }
     }
 
-    fn create_buy_order(&self, signal: &TradingSignal) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
-        // Use position sizer to calculate exact quantity based on wallet size and 0.5% risk
-        let quantity = self.position_sizer.calculate_quantity(signal.price);
-        let position_value = self.position_sizer.calculate_position_value(signal.price);
+    /// Evaluate the exit rules against the engine position, producing a market order closing it
+    fn check_exit(
+        &self,
+        instrument_state: &barter::engine::state::instrument::InstrumentState<AlgorithmData>,
+    ) -> Option<OrderRequestOpen<ExchangeIndex, InstrumentIndex>> {
+        let exit_rules = self.exit_rules.as_ref()?;
+        let instrument_key = instrument_state.instrument.name_exchange.name().to_string();
+        let mut tracked_positions = self.tracked_positions.lock().unwrap();
+
+        let Some(position) = &instrument_state.position.current else {
+            tracked_positions.remove(&instrument_key);
+            return None;
+        };
+        let price = instrument_state.data.price()?;
+        let time = instrument_state.data.last_update.unwrap_or_else(Utc::now);
+
+        // Start tracking afresh whenever the engine opens a new position
+        let tracked = tracked_positions
+            .entry(instrument_key.clone())
+            .or_insert_with(|| TrackedPosition { time_enter: position.time_enter, best_price: price });
+        if tracked.time_enter != position.time_enter {
+            *tracked = TrackedPosition { time_enter: position.time_enter, best_price: price };
+        }
+        tracked.best_price = match position.side {
+            Side::Buy => tracked.best_price.max(price),
+            Side::Sell => tracked.best_price.min(price),
+        };
+
+        let closing_side = match position.side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+
+        // Don't stack exits while one is still executing
+        if exit_in_flight(&instrument_state.orders, closing_side) {
+            return None;
+        }
+
+        let held = (time - position.time_enter).to_std().unwrap_or_default();
+        let reason = exit_rules.evaluate(position.side, position.price_entry_average, tracked.best_price, price, held)?;
+
+        let pnl_percentage = if position.price_entry_average == dec!(0) {
+            dec!(0)
+        } else {
+            match position.side {
+                Side::Buy => (price - position.price_entry_average) / position.price_entry_average,
+                Side::Sell => (position.price_entry_average - price) / position.price_entry_average,
+            }
+        };
+
+        println!("[{}] 🛑 EXIT {}: {} @ {:.6} | Side: {:?} | Quantity: {:.8} | Entry: {:.6} | Best: {:.6} | PnL: {:.2}% | Held: {}s",
+                 Local::now().format("%d-%m-%y %H:%M:%S"),
+                 reason,
+                 instrument_key,
+                 price,
+                 position.side,
+                 position.quantity_abs,
+                 position.price_entry_average,
+                 tracked.best_price,
+                 pnl_percentage * dec!(100),
+                 held.as_secs()
+        );
+
+        Some(OrderRequestOpen {
+            key: OrderKey {
+                exchange: instrument_state.instrument.exchange,
+                instrument: instrument_state.key,
+                strategy: Vwap::ID,
+                cid: Default::default(),
+            },
+            state: RequestOpen {
+                side: closing_side,
+                price,
+                quantity: position.quantity_abs,
+                kind: OrderKind::Market,
+                time_in_force: TimeInForce::ImmediateOrCancel,
+            },
+        })
+    }
+
+    /// Sizing model for a signal, risking against the exit rules' stop-loss
+    fn sizing_model(&self, signal: &TradingSignal, data: &AlgorithmData) -> SizingModel {
+        let stop_distance = self.exit_rules.as_ref().and_then(|rules| rules.stop_loss()).map(|stop_loss| signal.price * stop_loss);
+        self.sizing.model(signal.price, stop_distance, data)
+    }
+
+    fn create_buy_order(&self, signal: &TradingSignal, sizing: &SizingModel) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
+        // Use position sizer to calculate exact quantity from the signal's sizing model
+        let quantity = self.position_sizer.lock().unwrap().calculate_quantity_with(signal.price, sizing);
+        let position_value = quantity * signal.price;
 
-        println!("[{}] 🟢 BUY ORDER: {} @ {:.6} | Quantity: {:.8} | Position Value: ${:.2} | Risk: {:.2}% | VWAP: {:.3} | RSI: {:.2} -> {:.2} | Source: {} [{}]",
+        println!("[{}] 🟢 BUY ORDER: {} @ {:.6} | Quantity: {:.8} | Position Value: ${:.2} | Risk: {:.2}% | VWAP: {:.3} | RSI: {:.2} -> {:.2} | CVD: {:.4} | Source: {} [{}]",
                  Local::now().format("%d-%m-%y %H:%M:%S"),
                  signal.instrument_key,
                  signal.price,
                  quantity,
                  position_value,
-                 self.position_sizer.risk_percentage() * dec!(100),
+                 self.position_sizer.lock().unwrap().risk_percentage() * dec!(100),
                  signal.vwap,
                  signal.previous_rsi,
                  signal.rsi,
+                 signal.cumulative_delta,
                  signal.signal_source,
                  if signal.price > signal.vwap { "ABOVE_VWAP" } else { "BELOW_VWAP" }
         );
@@ -261,27 +640,28 @@ impl Vwap {
                 side: Side::Buy,
                 price: signal.price,
                 quantity,
-                kind: OrderKind::Limit,
-                time_in_force: TimeInForce::GoodUntilEndOfDay,
+                kind: self.order_kind,
+                time_in_force: time_in_force(self.order_kind),
             },
         }
     }
 
-    fn create_sell_order(&self, signal: &TradingSignal) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
-        // Use position sizer to calculate exact quantity based on wallet size and 0.5% risk
-        let quantity = self.position_sizer.calculate_quantity(signal.price);
-        let position_value = self.position_sizer.calculate_position_value(signal.price);
+    fn create_sell_order(&self, signal: &TradingSignal, sizing: &SizingModel) -> OrderRequestOpen<ExchangeIndex, InstrumentIndex> {
+        // Use position sizer to calculate exact quantity from the signal's sizing model
+        let quantity = self.position_sizer.lock().unwrap().calculate_quantity_with(signal.price, sizing);
+        let position_value = quantity * signal.price;
 
-        println!("[{}] 🔴 SELL ORDER: {} @ {:.6} | Quantity: {:.8} | Position Value: ${:.2} | Risk: {:.2}% | VWAP: {:.3} | RSI: {:.2} -> {:.2} | Source: {} [{}]",
+        println!("[{}] 🔴 SELL ORDER: {} @ {:.6} | Quantity: {:.8} | Position Value: ${:.2} | Risk: {:.2}% | VWAP: {:.3} | RSI: {:.2} -> {:.2} | CVD: {:.4} | Source: {} [{}]",
                  Local::now().format("%d-%m-%y %H:%M:%S"),
                  signal.instrument_key,
                  signal.price,
                  quantity,
                  position_value,
-                 self.position_sizer.risk_percentage() * dec!(100),
+                 self.position_sizer.lock().unwrap().risk_percentage() * dec!(100),
                  signal.vwap,
                  signal.previous_rsi,
                  signal.rsi,
+                 signal.cumulative_delta,
                  signal.signal_source,
                  if signal.price > signal.vwap { "ABOVE_VWAP" } else { "BELOW_VWAP" }
         );
@@ -297,8 +677,8 @@ impl Vwap {
                 side: Side::Sell,
                 price: signal.price,
                 quantity,
-                kind: OrderKind::Limit,
-                time_in_force: TimeInForce::GoodUntilEndOfDay,
+                kind: self.order_kind,
+                time_in_force: time_in_force(self.order_kind),
             },
         }
     }
@@ -310,6 +690,12 @@ impl Default for Vwap {
     }
 }
 
+impl StrategyData for Vwap {
+    fn algorithm_data(&self) -> AlgorithmData {
+        AlgorithmData::new_with_vwap(14, self.vwap_reset)
+    }
+}
+
 impl AlgoStrategy for Vwap {
     type State = EngineState<DefaultGlobalData, AlgorithmData>;
 
@@ -322,16 +708,44 @@ impl AlgoStrategy for Vwap {
     ) {
         let mut buy_orders = Vec::new();
         let mut sell_orders = Vec::new();
+        let mut exit_orders = Vec::new();
+        let mut cancel_orders = Vec::new();
+        let required_indicators = self.required_indicators();
+        let allocation_weights = self.allocator.as_ref()
+            .map(|allocator| allocator.instrument_weights(state))
+            .unwrap_or_default();
 
         // Process all instruments and generate signals
         for instrument_state in state.instruments.instruments(&InstrumentFilter::None) {
+            // Exits only need a price, so they run even while indicators warm up
+            if let Some(exit_order) = self.check_exit(instrument_state) {
+                // The exit takes over from closing orders left resting, like a take-profit limit
+                cancel_orders.extend(resting_closing_orders(&instrument_state.orders, exit_order.state.side));
+                exit_orders.push(exit_order);
+                continue;
+            }
+
+            // Don't trade on partially warmed up indicators
+            if !instrument_state.data.pending_indicators(&required_indicators).is_empty() {
+                continue;
+            }
+
             if let Some(signal) = self.process_instrument_signal(instrument_state) {
+                let mut remaining_budget = self.allocator.as_ref().map(|allocator| {
+                    allocator.remaining_budget(state, instrument_state, &allocation_weights, self.capital, self.capital_mode, &Vwap::ID)
+                });
+                self.position_sizer.lock().unwrap()
+                    .refresh_wallet_size(state, instrument_state.instrument.underlying.quote, self.capital, self.capital_mode);
+                let sizing = self.sizing_model(&signal, &instrument_state.data);
                 match signal.signal_type {
                     SignalType::Buy => {
-                        buy_orders.push(self.create_buy_order(&signal));
+                        let order = self.create_buy_order(&signal, &sizing);
+                        if reserve_budget(&order, &signal.instrument_key, &mut remaining_budget) {
+                            buy_orders.push(order);
+                        }
                     },
                     SignalType::Sell => {
-                        sell_orders.push(self.create_sell_order(&signal));
+                        sell_orders.push(self.create_sell_order(&signal, &sizing));
                     },
                     SignalType::None => {
                         // No action needed
@@ -340,13 +754,27 @@ impl AlgoStrategy for Vwap {
             }
         }
 
-        // Combine buy and sell orders
-        let all_orders = buy_orders.into_iter().chain(sell_orders.into_iter());
+        // Combine exit, buy and sell orders
+        let all_orders = exit_orders.into_iter().chain(buy_orders).chain(sell_orders);
 
-        (std::iter::empty(), all_orders)
+        (cancel_orders, all_orders)
     }
 }
 
+/// Whether one of the strategy's market exits on `closing_side` is still executing
+fn exit_in_flight(orders: &Orders<ExchangeIndex, InstrumentIndex>, closing_side: Side) -> bool {
+    orders.0.values()
+        .any(|order| order.key.strategy == Vwap::ID && order.side == closing_side && order.kind == OrderKind::Market)
+}
+
+/// Cancels for the strategy's resting orders on `closing_side`, skipping any already being cancelled
+fn resting_closing_orders(orders: &Orders<ExchangeIndex, InstrumentIndex>, closing_side: Side) -> Vec<OrderRequestCancel<ExchangeIndex, InstrumentIndex>> {
+    orders.0.values()
+        .filter(|order| order.key.strategy == Vwap::ID && order.side == closing_side && order.kind != OrderKind::Market)
+        .filter_map(|order| order.to_request_cancel())
+        .collect()
+}
+
 impl ClosePositionsStrategy for Vwap {
     type State = EngineState<DefaultGlobalData, AlgorithmData>;
 
@@ -384,4 +812,104 @@ impl<Clock, State, ExecutionTxs, Risk> OnTradingDisabled<Clock, State, Execution
         _: &mut Engine<Clock, State, ExecutionTxs, Self, Risk>,
     ) -> Self::OnTradingDisabled {
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsi_precedence() {
+        let vwap = Vwap::default();
+
+        assert_eq!(vwap.combine_signals(Some(SignalType::Buy), Some(SignalType::Buy)), Some((SignalType::Buy, "COMBINED")));
+        assert_eq!(vwap.combine_signals(Some(SignalType::Sell), Some(SignalType::Buy)), Some((SignalType::Sell, "RSI_PRECEDENCE")));
+        assert_eq!(vwap.combine_signals(None, Some(SignalType::Buy)), Some((SignalType::Buy, "VWAP")));
+    }
+
+    #[test]
+    fn test_single_source_and_agreement_policies() {
+        let rsi_only = Vwap::default().with_combination(SignalCombination::RsiOnly);
+        assert_eq!(rsi_only.combine_signals(None, Some(SignalType::Buy)), None);
+
+        let vwap_only = Vwap::default().with_combination(SignalCombination::VwapOnly);
+        assert_eq!(vwap_only.combine_signals(Some(SignalType::Sell), Some(SignalType::Buy)), Some((SignalType::Buy, "VWAP")));
+
+        let agreement = Vwap::default().with_combination(SignalCombination::RequireAgreement);
+        assert_eq!(agreement.combine_signals(Some(SignalType::Buy), None), None);
+        assert_eq!(agreement.combine_signals(Some(SignalType::Buy), Some(SignalType::Sell)), None);
+    }
+
+    #[test]
+    fn test_weighted_vote() {
+        let vwap = Vwap::default().with_combination(SignalCombination::WeightedVote {
+            rsi_weight: dec!(2),
+            vwap_weight: dec!(1),
+            min_score: dec!(1.5),
+        });
+
+        // RSI alone clears the bar, VWAP alone doesn't, opposing votes net out to 1
+        assert_eq!(vwap.combine_signals(Some(SignalType::Sell), None), Some((SignalType::Sell, "WEIGHTED_VOTE")));
+        assert_eq!(vwap.combine_signals(None, Some(SignalType::Buy)), None);
+        assert_eq!(vwap.combine_signals(Some(SignalType::Buy), Some(SignalType::Sell)), None);
+    }
+
+    #[test]
+    fn test_configurable_thresholds() {
+        let vwap = Vwap::default().with_rsi_thresholds(dec!(70), dec!(30)).with_vwap_deadband(dec!(0.01));
+
+        assert_eq!(vwap.determine_rsi_state(dec!(75)), RsiState::Overbought);
+        assert_eq!(vwap.determine_rsi_state(dec!(25)), RsiState::Oversold);
+        // 0.5% away is inside a 1% deadband
+        assert_eq!(vwap.determine_vwap_state(dec!(100.5), dec!(100), &VwapState::AtVwap), VwapState::AtVwap);
+    }
+
+    #[test]
+    fn test_band_filter_skips_stretched_crosses() {
+        let bands = VwapBands {
+            vwap: dec!(100),
+            std_dev: dec!(10),
+            upper_1: dec!(110),
+            lower_1: dec!(90),
+            upper_2: dec!(120),
+            lower_2: dec!(80),
+        };
+
+        assert!(!Vwap::default().is_band_stretched(&SignalType::Buy, dec!(125), Some(bands)));
+
+        let one_sigma = Vwap::default().with_band_filter(1);
+        assert!(one_sigma.is_band_stretched(&SignalType::Buy, dec!(115), Some(bands)));
+        assert!(!one_sigma.is_band_stretched(&SignalType::Sell, dec!(115), Some(bands)));
+        assert!(!one_sigma.is_band_stretched(&SignalType::Buy, dec!(115), None));
+
+        let two_sigma = Vwap::default().with_band_filter(2);
+        assert!(!two_sigma.is_band_stretched(&SignalType::Buy, dec!(115), Some(bands)));
+        assert!(two_sigma.is_band_stretched(&SignalType::Sell, dec!(75), Some(bands)));
+    }
+
+    #[test]
+    fn test_stop_exit_replaces_resting_closing_orders() {
+        use barter_execution::order::Order;
+        use barter_execution::order::id::{ClientOrderId, OrderId};
+        use barter_execution::order::state::{ActiveOrderState, Open};
+
+        let order = |cid: &str, kind: OrderKind, time_in_force: TimeInForce| {
+            let key = OrderKey { exchange: ExchangeIndex(0), instrument: InstrumentIndex(0), strategy: Vwap::ID, cid: ClientOrderId::new(cid) };
+            let state = ActiveOrderState::Open(Open::new(OrderId::new(cid), Utc::now(), dec!(0)));
+            (key.cid.clone(), Order { key, side: Side::Sell, price: dec!(110), quantity: dec!(1), kind, time_in_force, state })
+        };
+
+        // A take-profit resting above the market doesn't hold a stop back, it gets cancelled
+        let mut orders = Orders::default();
+        orders.0.extend([order("tp", OrderKind::Limit, TimeInForce::GoodUntilEndOfDay)]);
+        assert!(!exit_in_flight(&orders, Side::Sell));
+        let cancels = resting_closing_orders(&orders, Side::Sell);
+        assert_eq!(cancels.len(), 1);
+        assert_eq!(cancels[0].key.cid, ClientOrderId::new("tp"));
+        assert!(resting_closing_orders(&orders, Side::Buy).is_empty());
+
+        // Only a market exit still executing stops another being placed
+        orders.0.extend([order("stop", OrderKind::Market, TimeInForce::ImmediateOrCancel)]);
+        assert!(exit_in_flight(&orders, Side::Sell));
+    }
+}
@@ -0,0 +1,65 @@
+use barter::engine::Processor;
+use barter_data::event::DataKind;
+use barter_data::streams::consumer::MarketStreamEvent;
+use barter_instrument::instrument::InstrumentIndex;
+use std::collections::HashMap;
+use crate::algorithm::data::AlgorithmData;
+
+/// Replays historical market events through a fresh `AlgorithmData` per instrument,
+/// so indicators start live trading with a full window instead of empty
+pub fn warm_up<Events, FnData>(events: Events, init: FnData) -> HashMap<InstrumentIndex, AlgorithmData>
+where
+    Events: IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+    FnData: Fn() -> AlgorithmData,
+{
+    let mut datas = HashMap::new();
+
+    for event in events {
+        let MarketStreamEvent::Item(event) = event else { continue; };
+        datas.entry(event.instrument).or_insert_with(&init).process(&event);
+    }
+
+    datas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::data::Indicator;
+    use crate::algorithm::data::StrategyData;
+    use crate::algorithm::grid::Grid;
+    use crate::historical::load_market_events;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_warm_up_from_recorded_data() {
+        let events = load_market_events("config/data.json").unwrap();
+        let datas = warm_up(events, || AlgorithmData::new_with_periods(3, 5));
+
+        // config/data.json holds BTCUSDT (0) and SOLUSDT (2) trades only
+        assert_eq!(datas.len(), 2);
+
+        let btc = &datas[&InstrumentIndex(0)];
+        assert!(btc.is_ready(Indicator::Sma));
+        assert!(btc.is_ready(Indicator::Vwap));
+        assert!(btc.is_ready(Indicator::TradeFlow));
+        // A few seconds of trades can't close a one minute bar
+        assert!(!btc.is_ready(Indicator::Adx));
+        assert_eq!(btc.pending_indicators(&[Indicator::Sma, Indicator::Macd]), vec![Indicator::Macd]);
+    }
+
+    #[test]
+    fn test_warm_up_with_strategy_periods() {
+        // The grid reads its TMA and price range from an SMA over its own period
+        let warmed_up = |tma_period| {
+            let grid = Grid::with_params(dec!(10000), dec!(0.05), tma_period, dec!(0.01), dec!(0.02), 4);
+            let mut datas = warm_up(load_market_events("config/data.json").unwrap(), || grid.algorithm_data());
+            datas.remove(&InstrumentIndex(0)).unwrap()
+        };
+
+        let short = warmed_up(5);
+        assert!(short.is_ready(Indicator::Sma));
+        assert!(short.sma.range().is_some_and(|(low, high)| low <= high));
+        assert!(!warmed_up(400).is_ready(Indicator::Sma));
+    }
+}
@@ -0,0 +1,435 @@
+use barter::{
+    EngineEvent,
+    engine::{
+        EngineOutput,
+        audit::EngineAudit,
+        clock::HistoricalClock,
+        execution_tx::MultiExchangeTxMap,
+        Engine, Processor,
+        state::{EngineState, builder::EngineStateBuilder, global::DefaultGlobalData, position::PositionExited, trading::TradingState},
+    },
+    execution::{AccountStreamEvent, request::ExecutionRequest},
+    risk::DefaultRiskManager,
+    statistic::{summary::TradingSummary, time::Daily},
+    strategy::{
+        algo::AlgoStrategy, close_positions::ClosePositionsStrategy,
+        on_disconnect::OnDisconnectStrategy, on_trading_disabled::OnTradingDisabled,
+    },
+    system::{
+        builder::{AuditMode, EngineFeedMode, SystemArgs, SystemBuilder},
+        config::ExecutionConfig,
+    },
+};
+use barter_data::event::DataKind;
+use barter_data::streams::consumer::MarketStreamEvent;
+use barter_data::streams::reconnect::Event;
+use barter_instrument::Keyed;
+use barter_instrument::asset::QuoteAsset;
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::InstrumentIndex;
+use barter_integration::channel::{mpsc_unbounded, UnboundedRx};
+use chrono::{DateTime, Local, Utc};
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use crate::algorithm::data::{AlgorithmData, StrategyData};
+use crate::algorithm::warmup::warm_up;
+use crate::simulated_exchange::{FillSimulation, SimulatedExchange};
+use crate::tca::TradeCost;
+
+type BacktestState = EngineState<DefaultGlobalData, AlgorithmData>;
+type BacktestRisk = DefaultRiskManager<BacktestState>;
+
+/// Strategy a backtest runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BacktestStrategy {
+    Grid,
+    Vwap,
+}
+
+impl FromStr for BacktestStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "grid" => Ok(BacktestStrategy::Grid),
+            "vwap" => Ok(BacktestStrategy::Vwap),
+            other => Err(format!("unknown backtest strategy '{}', expected grid or vwap", other)),
+        }
+    }
+}
+
+impl fmt::Display for BacktestStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BacktestStrategy::Grid => "GRID",
+            BacktestStrategy::Vwap => "VWAP",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Replays recorded market events through the engine with a historical clock and the
+/// configured mock executions, as fast as the engine can process them
+///
+/// The mock exchange only fills market orders, so strategies should be built with
+/// `with_market_orders()`.
+///
+/// # Arguments
+/// * `instruments` - Instruments the events are indexed against
+/// * `executions` - Mock execution configs holding the starting balances
+/// * `events` - Recorded market events in time order, pulled lazily as the engine consumes them
+/// * `strategy` - Strategy under test
+/// * `risk_free_return` - Risk-free return used by the trading summary
+pub async fn run_backtest<Strategy>(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>, IntoIter: Send + 'static>,
+    strategy: Strategy,
+    risk_free_return: Decimal,
+) -> Result<TradingSummary<Daily>, Box<dyn std::error::Error>>
+where
+    Strategy: BacktestableStrategy,
+    Disconnect<Strategy>: Debug + Clone + Send + 'static,
+    TradingDisabled<Strategy>: Debug + Clone + Send + 'static,
+{
+    let (summary, _) = run_engine(instruments, executions, events, strategy, risk_free_return, AuditMode::Disabled, HashMap::new()).await?;
+    Ok(summary)
+}
+
+/// A backtest's summary along with every position it closed, in exit order
+#[derive(Debug, Clone)]
+pub struct BacktestResult {
+    pub summary: TradingSummary<Daily>,
+    pub trades: Vec<PositionExited<QuoteAsset>>,
+    /// Transaction costs of every fill, in fill order (only known with simulated fills)
+    pub costs: Vec<TradeCost>,
+}
+
+/// Runs a backtest like `run_backtest`, also collecting the closed positions from the engine audit stream
+pub async fn run_backtest_with_trades<Strategy>(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>, IntoIter: Send + 'static>,
+    strategy: Strategy,
+    risk_free_return: Decimal,
+) -> Result<BacktestResult, Box<dyn std::error::Error>>
+where
+    Strategy: BacktestableStrategy,
+    Disconnect<Strategy>: Debug + Clone + Send + 'static,
+    TradingDisabled<Strategy>: Debug + Clone + Send + 'static,
+{
+    let (summary, trades) = run_engine(instruments, executions, events, strategy, risk_free_return, AuditMode::Enabled, HashMap::new()).await?;
+    Ok(BacktestResult { summary, trades, costs: Vec::new() })
+}
+
+/// Runs a backtest like `run_backtest_with_trades`, first replaying `warm_up_events` through the
+/// strategy's indicators so they start the backtest ready instead of empty
+pub async fn run_backtest_warmed_up<Strategy>(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    warm_up_events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>, IntoIter: Send + 'static>,
+    strategy: Strategy,
+    risk_free_return: Decimal,
+) -> Result<BacktestResult, Box<dyn std::error::Error>>
+where
+    Strategy: BacktestableStrategy,
+    Disconnect<Strategy>: Debug + Clone + Send + 'static,
+    TradingDisabled<Strategy>: Debug + Clone + Send + 'static,
+{
+    let data = strategy.algorithm_data();
+    let warmed_up = warm_up(warm_up_events, || data.clone());
+    let (summary, trades) = run_engine(instruments, executions, events, strategy, risk_free_return, AuditMode::Enabled, warmed_up).await?;
+    Ok(BacktestResult { summary, trades, costs: Vec::new() })
+}
+
+/// Strategies the backtest engine can run
+pub trait BacktestableStrategy:
+    AlgoStrategy<State = BacktestState>
+    + ClosePositionsStrategy<State = BacktestState>
+    + OnDisconnectStrategy<HistoricalClock, BacktestState, MultiExchangeTxMap, BacktestRisk>
+    + OnTradingDisabled<HistoricalClock, BacktestState, MultiExchangeTxMap, BacktestRisk>
+    + StrategyData
+    + Send
+    + 'static
+{
+}
+
+impl<Strategy> BacktestableStrategy for Strategy where
+    Strategy: AlgoStrategy<State = BacktestState>
+        + ClosePositionsStrategy<State = BacktestState>
+        + OnDisconnectStrategy<HistoricalClock, BacktestState, MultiExchangeTxMap, BacktestRisk>
+        + OnTradingDisabled<HistoricalClock, BacktestState, MultiExchangeTxMap, BacktestRisk>
+        + StrategyData
+        + Send
+        + 'static
+{
+}
+
+type Disconnect<Strategy> =
+    <Strategy as OnDisconnectStrategy<HistoricalClock, BacktestState, MultiExchangeTxMap, BacktestRisk>>::OnDisconnect;
+type TradingDisabled<Strategy> =
+    <Strategy as OnTradingDisabled<HistoricalClock, BacktestState, MultiExchangeTxMap, BacktestRisk>>::OnTradingDisabled;
+
+/// Runs the engine over `events`, collecting closed positions from the audit stream when auditing is enabled
+///
+/// Instruments in `warmed_up` start from its indicator data, the rest from the strategy's.
+async fn run_engine<Strategy>(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>, IntoIter: Send + 'static>,
+    strategy: Strategy,
+    risk_free_return: Decimal,
+    audit_mode: AuditMode,
+    warmed_up: HashMap<InstrumentIndex, AlgorithmData>,
+) -> Result<(TradingSummary<Daily>, Vec<PositionExited<QuoteAsset>>), Box<dyn std::error::Error>>
+where
+    Strategy: BacktestableStrategy,
+    Disconnect<Strategy>: Debug + Clone + Send + 'static,
+    TradingDisabled<Strategy>: Debug + Clone + Send + 'static,
+{
+    let (time_first_event, events) = start_clock(events.into_iter())?;
+    let data = strategy.algorithm_data();
+
+    let args = SystemArgs::new(
+        instruments,
+        executions,
+        HistoricalClock::new(time_first_event),
+        strategy,
+        DefaultRiskManager::default(),
+        futures::stream::iter(events),
+        DefaultGlobalData,
+        move |instrument: &Keyed<InstrumentIndex, _>| warmed_up
+            .get(&instrument.key)
+            .cloned()
+            .unwrap_or_else(|| data.clone()),
+    );
+
+    let mut system = SystemBuilder::new(args)
+        // Engine feed in Async mode (Stream input), consumed as fast as it's processed
+        .engine_feed_mode(EngineFeedMode::Stream)
+        .audit_mode(audit_mode)
+        .trading_state(TradingState::Enabled)
+        .build::<EngineEvent, _>()?
+        .init_with_runtime(tokio::runtime::Handle::current())
+        .await?;
+
+    // Drain the audit stream while the engine runs so it never backs up in memory
+    let trades = system.audit.take().map(|audit| tokio::spawn(async move {
+        let mut trades = Vec::new();
+        let mut updates = audit.updates.into_stream();
+        while let Some(tick) = updates.next().await {
+            trades.extend(position_exits(tick.event));
+        }
+        trades
+    }));
+
+    // Shuts down once the engine has processed every market event
+    let (engine, _shutdown_audit) = system.shutdown_after_backtest().await?;
+
+    let trades = match trades {
+        Some(trades) => trades.await?,
+        None => Vec::new(),
+    };
+
+    Ok((engine.trading_summary_generator(risk_free_return).generate(Daily), trades))
+}
+
+/// Finds the first market event to start the clock from, returning the events from the start
+/// again, reconnections before it included
+fn start_clock<Events>(
+    mut events: Events,
+) -> Result<(DateTime<Utc>, impl Iterator<Item = Events::Item>), Box<dyn std::error::Error>>
+where
+    Events: Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+{
+    let mut leading = Vec::new();
+    let first_event = loop {
+        match events.next() {
+            Some(Event::Item(event)) => break event,
+            Some(reconnecting) => leading.push(reconnecting),
+            None => return Err("backtest market data contains no market events".into()),
+        }
+    };
+    let time_first_event = first_event.time_exchange;
+    Ok((time_first_event, leading.into_iter().chain(std::iter::once(Event::Item(first_event))).chain(events)))
+}
+
+/// Runs a backtest against simulated exchanges that fill orders from the recorded order book and
+/// trade prints, collecting the closed positions like `run_backtest_with_trades`
+///
+/// Unlike the mock exchange this works with limit orders. The engine is driven in event time on
+/// the calling thread, so latency is measured against the recorded timestamps rather than the
+/// wall clock, and runs are deterministic for a given `fills` seed.
+pub fn run_backtest_with_fills<Strategy>(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+    strategy: Strategy,
+    risk_free_return: Decimal,
+    fills: &FillSimulation,
+) -> Result<BacktestResult, Box<dyn std::error::Error>>
+where
+    Strategy: BacktestableStrategy,
+{
+    let (time_first_event, events) = start_clock(events.into_iter())?;
+
+    let mut exchanges = executions.iter()
+        .map(|ExecutionConfig::Mock(config)| SimulatedExchange::new(instruments, config, fills))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Route each exchange's engine requests to its simulated exchange
+    let mut requests: Vec<(usize, UnboundedRx<ExecutionRequest>)> = Vec::new();
+    let execution_txs = instruments.exchanges().iter()
+        .map(|exchange| {
+            let simulated = exchanges.iter().position(|simulated| simulated.exchange_id() == exchange.value);
+            let tx = simulated.map(|simulated| {
+                let (tx, rx) = mpsc_unbounded();
+                requests.push((simulated, rx));
+                tx
+            });
+            (exchange.value, tx)
+        })
+        .collect();
+
+    let data = strategy.algorithm_data();
+    let state = EngineStateBuilder::new(instruments, DefaultGlobalData, |_| data.clone())
+        .time_engine_start(time_first_event)
+        .trading_state(TradingState::Enabled)
+        .build();
+    let mut engine = Engine::new(
+        HistoricalClock::new(time_first_event),
+        state,
+        execution_txs,
+        strategy,
+        DefaultRiskManager::default(),
+    );
+
+    let mut trades = Vec::new();
+    for exchange in &exchanges {
+        let snapshot = exchange.account_snapshot(time_first_event);
+        trades.extend(position_exits(engine.process(EngineEvent::Account(AccountStreamEvent::Item(snapshot)))));
+    }
+
+    for event in events {
+        let Event::Item(market) = &event else {
+            trades.extend(position_exits(engine.process(EngineEvent::Market(event))));
+            continue;
+        };
+
+        let time = market.time_exchange;
+        deliver_notifications(&mut engine, &mut exchanges, &mut requests, time, &mut trades);
+        if let Some(exchange) = exchanges.iter_mut().find(|exchange| exchange.exchange_id() == market.exchange) {
+            exchange.on_market(market);
+        }
+
+        trades.extend(position_exits(engine.process(EngineEvent::Market(event))));
+        forward_requests(&mut exchanges, &mut requests, time);
+    }
+
+    // Let requests still in flight reach the exchanges and their notifications the engine
+    deliver_notifications(&mut engine, &mut exchanges, &mut requests, DateTime::<Utc>::MAX_UTC, &mut trades);
+
+    for exchange in &exchanges {
+        println!("[{}] 🧾 SIMULATED FILLS {}: {}", Local::now().format("%d-%m-%y %H:%M:%S"), exchange.exchange_id(), exchange.stats());
+    }
+
+    let mut costs: Vec<TradeCost> = exchanges.iter().flat_map(|exchange| exchange.costs().iter().cloned()).collect();
+    costs.sort_by_key(|cost| cost.time_fill);
+
+    Ok(BacktestResult {
+        summary: engine.trading_summary_generator(risk_free_return).generate(Daily),
+        trades,
+        costs,
+    })
+}
+
+type SimulatedEngine<Strategy> = Engine<HistoricalClock, BacktestState, MultiExchangeTxMap, Strategy, BacktestRisk>;
+
+/// Feeds the engine every exchange notification due by `until`, sending on the requests it makes in response
+fn deliver_notifications<Strategy: BacktestableStrategy>(
+    engine: &mut SimulatedEngine<Strategy>,
+    exchanges: &mut [SimulatedExchange],
+    requests: &mut [(usize, UnboundedRx<ExecutionRequest>)],
+    until: DateTime<Utc>,
+    trades: &mut Vec<PositionExited<QuoteAsset>>,
+) {
+    loop {
+        let mut delivered = false;
+        for index in 0..exchanges.len() {
+            while let Some((time, notification)) = exchanges[index].next_due(until) {
+                delivered = true;
+                trades.extend(position_exits(engine.process(EngineEvent::Account(AccountStreamEvent::Item(notification)))));
+                forward_requests(exchanges, requests, time);
+            }
+        }
+        if !delivered {
+            break;
+        }
+    }
+}
+
+fn forward_requests(
+    exchanges: &mut [SimulatedExchange],
+    requests: &mut [(usize, UnboundedRx<ExecutionRequest>)],
+    time: DateTime<Utc>,
+) {
+    for (exchange, rx) in requests.iter_mut() {
+        while let Ok(request) = rx.rx.try_recv() {
+            exchanges[*exchange].submit(request, time);
+        }
+    }
+}
+
+/// Positions an engine audit reports closed
+fn position_exits<Event, OnTradingDisabled, OnDisconnect>(
+    audit: EngineAudit<Event, EngineOutput<OnTradingDisabled, OnDisconnect>>,
+) -> Vec<PositionExited<QuoteAsset>> {
+    match audit {
+        EngineAudit::Process(process) => process.outputs.into_iter()
+            .filter_map(|output| match output {
+                EngineOutput::PositionExit(position) => Some(position),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter_data::event::MarketEvent;
+    use barter_data::subscription::trade::PublicTrade;
+    use barter_instrument::Side;
+    use barter_instrument::exchange::ExchangeId;
+
+    #[test]
+    fn test_parse_backtest_strategy() {
+        assert_eq!("grid".parse(), Ok(BacktestStrategy::Grid));
+        assert_eq!("VWAP".parse(), Ok(BacktestStrategy::Vwap));
+        assert!("momentum".parse::<BacktestStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_start_clock_keeps_leading_reconnections() {
+        let time = DateTime::<Utc>::from_timestamp(1_742_860_800, 0).unwrap();
+        let trade = Event::Item(MarketEvent {
+            time_exchange: time,
+            time_received: time,
+            exchange: ExchangeId::BinanceSpot,
+            instrument: InstrumentIndex(0),
+            kind: DataKind::Trade(PublicTrade { id: "1".to_string(), price: 100.0, amount: 1.0, side: Side::Buy }),
+        });
+        let events = vec![Event::Reconnecting(ExchangeId::BinanceSpot), trade.clone()];
+
+        // A recorded day file opens with the gap before its first event
+        let (start, events) = start_clock(events.into_iter()).unwrap();
+        assert_eq!(start, time);
+        assert_eq!(events.collect::<Vec<_>>(), vec![Event::Reconnecting(ExchangeId::BinanceSpot), trade]);
+        assert!(start_clock(std::iter::once(Event::Reconnecting(ExchangeId::BinanceSpot))).is_err());
+    }
+}
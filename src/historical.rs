@@ -0,0 +1,295 @@
+use barter_data::error::DataError;
+use barter_data::event::{DataKind, MarketEvent};
+use barter_data::streams::consumer::{MarketStreamEvent, MarketStreamResult};
+use barter_data::streams::reconnect::Event;
+use barter_instrument::exchange::ExchangeId;
+use barter_instrument::instrument::InstrumentIndex;
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// A `MarketStreamResult` borrowing the event it records
+type RecordedEvent<'a> = Event<ExchangeId, Result<&'a MarketEvent<InstrumentIndex, DataKind>, DataError>>;
+
+/// Loads recorded market events from a JSON array file in the `config/data.json` format
+///
+/// Events that were recorded as stream errors are dropped.
+pub fn load_market_events(
+    path: impl AsRef<Path>,
+) -> Result<Vec<MarketStreamEvent<InstrumentIndex, DataKind>>, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let results: Vec<MarketStreamResult<InstrumentIndex, DataKind>> = serde_json::from_reader(reader)?;
+
+    Ok(results.into_iter().filter_map(into_market_stream_event).collect())
+}
+
+/// Converts a recorded stream result into an engine market event, dropping errors
+pub fn into_market_stream_event(
+    event: MarketStreamResult<InstrumentIndex, DataKind>,
+) -> Option<MarketStreamEvent<InstrumentIndex, DataKind>> {
+    match event {
+        Event::Reconnecting(exchange) => Some(Event::Reconnecting(exchange)),
+        Event::Item(Ok(market_event)) => Some(Event::Item(market_event)),
+        Event::Item(Err(error)) => {
+            warn!(%error, "skipping recorded market stream error");
+            None
+        }
+    }
+}
+
+/// Lazily reads recorded market events from a newline-delimited JSON file, one
+/// `MarketStreamResult` per line in the same format as the `config/data.json` array elements
+///
+/// Files ending in `.gz` are gunzipped and files ending in `.zst` are zstd decompressed as they
+/// are read. Blank lines, malformed lines and recorded stream errors are skipped.
+pub struct MarketEventReader {
+    path: PathBuf,
+    lines: Lines<Box<dyn BufRead + Send>>,
+    line_number: usize,
+}
+
+impl MarketEventReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+
+        let reader: Box<dyn BufRead + Send> = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+            Some("zst") | Some("zstd") => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+            _ => Box::new(BufReader::new(file)),
+        };
+
+        Ok(Self { path, lines: reader.lines(), line_number: 0 })
+    }
+}
+
+impl Iterator for MarketEventReader {
+    type Item = MarketStreamEvent<InstrumentIndex, DataKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => {
+                    // A corrupt or truncated compressed file can't be read any further
+                    warn!(%error, path = %self.path.display(), "stopped reading market data file");
+                    return None;
+                }
+            };
+            self.line_number += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<MarketStreamResult<InstrumentIndex, DataKind>>(&line) {
+                Ok(result) => {
+                    if let Some(event) = into_market_stream_event(result) {
+                        return Some(event);
+                    }
+                }
+                Err(error) => {
+                    warn!(%error, path = %self.path.display(), line = self.line_number, "skipping malformed market event");
+                }
+            }
+        }
+    }
+}
+
+/// Writes an event as one line of the newline-delimited format `MarketEventReader` reads
+///
+/// barter-data skips the `side` tag of order book sides when serializing but requires it when
+/// deserializing, so it's written back in for L2 events to round-trip.
+pub fn write_market_event(
+    writer: &mut impl Write,
+    event: &MarketStreamEvent<InstrumentIndex, DataKind>,
+) -> io::Result<()> {
+    match event {
+        Event::Item(market_event) if matches!(market_event.kind, DataKind::OrderBook(_)) => {
+            let mut value = serde_json::to_value(RecordedEvent::Item(Ok(market_event)))?;
+            let book = value.pointer_mut("/Item/Ok/kind/OrderBook")
+                .and_then(|kind| kind.as_object_mut())
+                .and_then(|kind| kind.values_mut().next());
+            if let Some(book) = book {
+                for side in ["bids", "asks"] {
+                    if let Some(side) = book.get_mut(side).and_then(|side| side.as_object_mut()) {
+                        side.insert("side".to_string(), serde_json::Value::Null);
+                    }
+                }
+            }
+            serde_json::to_writer(&mut *writer, &value)?;
+        }
+        Event::Item(market_event) => serde_json::to_writer(&mut *writer, &RecordedEvent::Item(Ok(market_event)))?,
+        Event::Reconnecting(exchange) => serde_json::to_writer(&mut *writer, &RecordedEvent::Reconnecting(*exchange))?,
+    }
+    writer.write_all(b"\n")
+}
+
+/// Merges several market event sources into a single stream ordered by exchange time
+///
+/// Each source must already be in time order (eg/ one file per day or per instrument).
+/// Reconnection events carry no timestamp, so they keep their position relative to the
+/// events of the source they came from. One at the start of a source is placed just before
+/// the source's first event.
+pub struct MergedMarketEvents<Source: Iterator> {
+    sources: Vec<Peekable<Source>>,
+    pending: Vec<Option<MarketStreamEvent<InstrumentIndex, DataKind>>>,
+    last_time: Vec<DateTime<Utc>>,
+    // (time, source index) of the next event of every source that still has events
+    queue: BinaryHeap<Reverse<(DateTime<Utc>, usize)>>,
+}
+
+impl<Source> MergedMarketEvents<Source>
+where
+    Source: Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+{
+    pub fn new(sources: Vec<Source>) -> Self {
+        let mut merged = Self {
+            pending: (0..sources.len()).map(|_| None).collect(),
+            last_time: vec![DateTime::<Utc>::MIN_UTC; sources.len()],
+            queue: BinaryHeap::with_capacity(sources.len()),
+            sources: sources.into_iter().map(Iterator::peekable).collect(),
+        };
+        for index in 0..merged.sources.len() {
+            merged.advance(index);
+        }
+        merged
+    }
+
+    /// Pulls the next event of a source and queues it by time
+    fn advance(&mut self, index: usize) {
+        let Some(event) = self.sources[index].next() else { return; };
+        let time = match &event {
+            Event::Item(market_event) => market_event.time_exchange,
+            Event::Reconnecting(_) if self.last_time[index] == DateTime::<Utc>::MIN_UTC => {
+                match self.sources[index].peek() {
+                    Some(Event::Item(next)) => next.time_exchange,
+                    _ => self.last_time[index],
+                }
+            }
+            Event::Reconnecting(_) => self.last_time[index],
+        };
+        self.last_time[index] = time;
+        self.pending[index] = Some(event);
+        self.queue.push(Reverse((time, index)));
+    }
+}
+
+impl<Source> Iterator for MergedMarketEvents<Source>
+where
+    Source: Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+{
+    type Item = MarketStreamEvent<InstrumentIndex, DataKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, index)) = self.queue.pop()?;
+        let event = self.pending[index].take();
+        self.advance(index);
+        event
+    }
+}
+
+/// Expands a path into the market data files it holds: a file is returned as-is and a
+/// directory yields every `.jsonl`, `.jsonl.gz` and `.jsonl.zst` file in it, sorted by name
+pub fn market_data_files(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let file = entry?.path();
+        let name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if file.is_file() && [".jsonl", ".jsonl.gz", ".jsonl.zst"].iter().any(|suffix| name.ends_with(suffix)) {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Opens newline-delimited market data files and directories of them as one lazily read
+/// stream of events in timestamp order
+pub fn stream_market_events(
+    paths: &[impl AsRef<Path>],
+) -> io::Result<MergedMarketEvents<MarketEventReader>> {
+    let mut readers = Vec::new();
+    for path in paths {
+        for file in market_data_files(path)? {
+            readers.push(MarketEventReader::open(file)?);
+        }
+    }
+    Ok(MergedMarketEvents::new(readers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter_data::event::MarketEvent;
+    use barter_data::subscription::trade::PublicTrade;
+    use barter_instrument::Side;
+    use barter_instrument::exchange::ExchangeId;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    fn line(instrument: usize, second: u32) -> String {
+        let time = DateTime::parse_from_rfc3339(&format!("2024-12-20T19:05:{:02}Z", second)).unwrap().with_timezone(&Utc);
+        let event: MarketStreamResult<InstrumentIndex, DataKind> = Event::Item(Ok(MarketEvent {
+            time_exchange: time,
+            time_received: time,
+            exchange: ExchangeId::BinanceSpot,
+            instrument: InstrumentIndex(instrument),
+            kind: DataKind::Trade(PublicTrade { id: second.to_string(), price: 100.0, amount: 1.0, side: Side::Buy }),
+        }));
+        serde_json::to_string(&event).unwrap()
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("market_data_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn seconds(events: impl Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>) -> Vec<String> {
+        events
+            .map(|event| match event {
+                Event::Item(event) => event.time_exchange.format("%S").to_string(),
+                Event::Reconnecting(_) => "reconnect".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merges_plain_and_compressed_files_in_time_order() {
+        let dir = test_dir("merge");
+
+        let btc = [line(0, 1), String::new(), line(0, 4), "not json".to_string(), line(0, 6)].join("\n");
+        std::fs::write(dir.join("btcusdt.jsonl"), btc).unwrap();
+
+        let mut gz = GzEncoder::new(File::create(dir.join("ethusdt.jsonl.gz")).unwrap(), Compression::default());
+        writeln!(gz, "{}\n{}", line(1, 2), line(1, 5)).unwrap();
+        gz.finish().unwrap();
+
+        let reconnect = serde_json::to_string(&MarketStreamResult::<InstrumentIndex, DataKind>::Reconnecting(ExchangeId::BinanceSpot)).unwrap();
+        let zst = [line(2, 3), reconnect, line(2, 7)].join("\n");
+        std::fs::write(dir.join("solusdt.jsonl.zst"), zstd::encode_all(zst.as_bytes(), 0).unwrap()).unwrap();
+
+        let events = stream_market_events(&[&dir]).unwrap();
+        assert_eq!(
+            seconds(events),
+            vec!["01", "02", "03", "reconnect", "04", "05", "06", "07"]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
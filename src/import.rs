@@ -0,0 +1,317 @@
+use barter_data::event::{DataKind, MarketEvent};
+use barter_data::streams::reconnect::Event;
+use barter_data::subscription::trade::PublicTrade;
+use barter_instrument::Side;
+use barter_instrument::exchange::ExchangeId;
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::InstrumentIndex;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use crate::historical::write_market_event;
+
+/// Binance public-data CSV layouts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinanceCsvKind {
+    /// `agg_trade_id, price, quantity, first_trade_id, last_trade_id, transact_time, is_buyer_maker, ...`
+    AggTrades,
+    /// `open_time, open, high, low, close, volume, close_time, ...`
+    Klines,
+}
+
+impl BinanceCsvKind {
+    /// Detects the layout from a Binance file name, eg/ `BTCUSDT-aggTrades-2024-12-20.csv`
+    /// or `BTCUSDT-1m-2024-12-20.csv`
+    pub fn from_file_name(name: &str) -> Self {
+        if name.contains("aggTrades") {
+            BinanceCsvKind::AggTrades
+        } else {
+            BinanceCsvKind::Klines
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// The symbol doesn't match any instrument in the system config
+    UnknownInstrument(String),
+    Io(io::Error),
+    Csv(csv::Error),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::UnknownInstrument(symbol) => {
+                write!(f, "{} is not one of the configured instruments", symbol)
+            }
+            ImportError::Io(error) => write!(f, "{}", error),
+            ImportError::Csv(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(error: io::Error) -> Self {
+        ImportError::Io(error)
+    }
+}
+
+impl From<csv::Error> for ImportError {
+    fn from(error: csv::Error) -> Self {
+        ImportError::Csv(error)
+    }
+}
+
+/// A CSV row that couldn't be converted
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedRow {
+    pub line: u64,
+    pub reason: String,
+}
+
+/// Outcome of converting one CSV
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub rows: usize,
+    pub events: usize,
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rows -> {} events, {} skipped", self.rows, self.events, self.skipped.len())
+    }
+}
+
+/// Converts a Binance public-data CSV into trade events for `symbol`, written to `writer` in the
+/// newline-delimited format backtests stream
+///
+/// Timestamps may be in seconds, milliseconds, microseconds or nanoseconds. Klines carry no
+/// individual trades, so each one is replayed as four trades (open, the nearer extreme first,
+/// the other extreme, close) splitting its volume evenly across the bar. Header rows are
+/// ignored and malformed rows are skipped and listed in the report.
+///
+/// # Arguments
+/// * `reader` - CSV contents
+/// * `kind` - CSV layout
+/// * `symbol` - Exchange symbol of the rows (eg/ "BTCUSDT"), matched against `instruments`
+/// * `instruments` - Instruments from the system config the events are indexed against
+/// * `writer` - Destination of the converted events
+pub fn import_binance_csv(
+    reader: impl Read,
+    kind: BinanceCsvKind,
+    symbol: &str,
+    instruments: &IndexedInstruments,
+    writer: &mut impl Write,
+) -> Result<ImportReport, ImportError> {
+    let (instrument, exchange) = find_instrument(instruments, symbol)
+        .ok_or_else(|| ImportError::UnknownInstrument(symbol.to_string()))?;
+
+    let mut csv = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+
+    let mut report = ImportReport::default();
+    for (index, record) in csv.records().enumerate() {
+        let record = record?;
+        let line = record.position().map(|position| position.line()).unwrap_or(index as u64 + 1);
+
+        // Newer Binance files start with a header row
+        if index == 0 && record.get(0).is_some_and(|field| field.parse::<f64>().is_err()) {
+            continue;
+        }
+        report.rows += 1;
+
+        let trades = match kind {
+            BinanceCsvKind::AggTrades => parse_agg_trade(&record).map(|trade| vec![trade]),
+            BinanceCsvKind::Klines => parse_kline(&record),
+        };
+        let trades = match trades {
+            Ok(trades) => trades,
+            Err(reason) => {
+                report.skipped.push(SkippedRow { line, reason });
+                continue;
+            }
+        };
+
+        for (time, trade) in trades {
+            let event = Event::Item(MarketEvent {
+                time_exchange: time,
+                time_received: time,
+                exchange,
+                instrument,
+                kind: DataKind::Trade(trade),
+            });
+            write_market_event(writer, &event)?;
+            report.events += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Converts a Binance CSV file, taking the layout and symbol from its name
+pub fn import_binance_file(
+    path: impl AsRef<Path>,
+    instruments: &IndexedInstruments,
+    writer: &mut impl Write,
+) -> Result<ImportReport, ImportError> {
+    let path = path.as_ref();
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let symbol = name.split('-').next().unwrap_or_default();
+    let file = std::fs::File::open(path)?;
+    import_binance_csv(file, BinanceCsvKind::from_file_name(name), symbol, instruments, writer)
+}
+
+/// Index and exchange of the instrument trading as `symbol`, preferring Binance spot
+fn find_instrument(instruments: &IndexedInstruments, symbol: &str) -> Option<(InstrumentIndex, ExchangeId)> {
+    let mut matches = instruments.instruments()
+        .iter()
+        .filter(|instrument| instrument.value.name_exchange.name().eq_ignore_ascii_case(symbol))
+        .map(|instrument| (instrument.key, instrument.value.exchange.value))
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|(_, exchange)| *exchange != ExchangeId::BinanceSpot);
+    matches.into_iter().next()
+}
+
+/// Parses an epoch timestamp, inferring the unit from its magnitude
+pub fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    let value: i64 = raw.trim().parse().ok()?;
+    match value.abs() {
+        v if v >= 100_000_000_000_000_000 => Some(DateTime::from_timestamp_nanos(value)),
+        v if v >= 100_000_000_000_000 => DateTime::from_timestamp_micros(value),
+        v if v >= 100_000_000_000 => DateTime::from_timestamp_millis(value),
+        _ => DateTime::from_timestamp(value, 0),
+    }
+}
+
+fn field<T: std::str::FromStr>(record: &csv::StringRecord, index: usize, name: &str) -> Result<T, String> {
+    let raw = record.get(index).ok_or_else(|| format!("missing {}", name))?;
+    raw.trim().parse().map_err(|_| format!("invalid {} '{}'", name, raw))
+}
+
+fn time_field(record: &csv::StringRecord, index: usize, name: &str) -> Result<DateTime<Utc>, String> {
+    let raw = record.get(index).ok_or_else(|| format!("missing {}", name))?;
+    parse_timestamp(raw).ok_or_else(|| format!("invalid {} '{}'", name, raw))
+}
+
+fn parse_agg_trade(record: &csv::StringRecord) -> Result<(DateTime<Utc>, PublicTrade), String> {
+    let id: u64 = field(record, 0, "agg_trade_id")?;
+    let price: f64 = field(record, 1, "price")?;
+    let amount: f64 = field(record, 2, "quantity")?;
+    let time = time_field(record, 5, "transact_time")?;
+    let buyer_maker = record.get(6).ok_or("missing is_buyer_maker")?.trim().to_lowercase();
+
+    // The buyer resting means the seller was the aggressor
+    let side = match buyer_maker.as_str() {
+        "true" => Side::Sell,
+        "false" => Side::Buy,
+        other => return Err(format!("invalid is_buyer_maker '{}'", other)),
+    };
+    if price <= 0.0 || amount <= 0.0 {
+        return Err("non-positive price or quantity".to_string());
+    }
+
+    Ok((time, PublicTrade { id: id.to_string(), price, amount, side }))
+}
+
+fn parse_kline(record: &csv::StringRecord) -> Result<Vec<(DateTime<Utc>, PublicTrade)>, String> {
+    let open_time = time_field(record, 0, "open_time")?;
+    let open: f64 = field(record, 1, "open")?;
+    let high: f64 = field(record, 2, "high")?;
+    let low: f64 = field(record, 3, "low")?;
+    let close: f64 = field(record, 4, "close")?;
+    let volume: f64 = field(record, 5, "volume")?;
+    let close_time = time_field(record, 6, "close_time")?;
+
+    if low <= 0.0 || high < low || close_time < open_time {
+        return Err("inconsistent kline".to_string());
+    }
+
+    // A rising bar most likely visited its low first, a falling bar its high
+    let path = if close >= open { [open, low, high, close] } else { [open, high, low, close] };
+    let duration = close_time - open_time;
+    let amount = volume / 4.0;
+    let id = open_time.timestamp_millis();
+
+    let mut previous = open;
+    Ok(path.iter()
+        .enumerate()
+        .map(|(index, price)| {
+            let side = if *price >= previous { Side::Buy } else { Side::Sell };
+            previous = *price;
+            let trade = PublicTrade { id: format!("{}-{}", id, index), price: *price, amount, side };
+            let offset = chrono::Duration::microseconds(duration.num_microseconds().unwrap_or_default() * index as i64 / 3);
+            (open_time + offset, trade)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter_data::streams::consumer::MarketStreamResult;
+    use crate::test_util::btc_eth_instruments;
+
+    fn trades(output: &[u8]) -> Vec<(InstrumentIndex, DateTime<Utc>, PublicTrade)> {
+        String::from_utf8_lossy(output)
+            .lines()
+            .map(|line| match serde_json::from_str::<MarketStreamResult<InstrumentIndex, DataKind>>(line).unwrap() {
+                Event::Item(Ok(MarketEvent { instrument, time_exchange, kind: DataKind::Trade(trade), .. })) => (instrument, time_exchange, trade),
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_import_agg_trades() {
+        let csv = "agg_trade_id,price,quantity,first_trade_id,last_trade_id,transact_time,is_buyer_maker,is_best_match\n\
+                   1,96962.51,0.5,10,11,1734721518175,true,true\n\
+                   2,oops,0.5,12,12,1734721518176,false,true\n\
+                   3,96963.00,0.1,13,13,1734721518177000,False,True\n";
+        let mut output = Vec::new();
+        let report = import_binance_csv(csv.as_bytes(), BinanceCsvKind::AggTrades, "ethusdt", &btc_eth_instruments(), &mut output).unwrap();
+
+        assert_eq!((report.rows, report.events), (3, 2));
+        assert_eq!(report.skipped, vec![SkippedRow { line: 3, reason: "invalid price 'oops'".to_string() }]);
+
+        let trades = trades(&output);
+        assert_eq!(trades[0].0, InstrumentIndex(1));
+        assert_eq!(trades[0].2.side, Side::Sell);
+        // Millisecond and microsecond timestamps both parse, landing two milliseconds apart
+        assert_eq!((trades[1].1 - trades[0].1).num_milliseconds(), 2);
+        assert_eq!(trades[1].2.side, Side::Buy);
+    }
+
+    #[test]
+    fn test_klines_synthesize_trades() {
+        let csv = "1734721440000,100,104,99,103,8,1734721499999,0,0,0,0,0\n";
+        let mut output = Vec::new();
+        let report = import_binance_csv(csv.as_bytes(), BinanceCsvKind::Klines, "BTCUSDT", &btc_eth_instruments(), &mut output).unwrap();
+        assert_eq!(report.events, 4);
+
+        let trades = trades(&output);
+        let prices: Vec<f64> = trades.iter().map(|(_, _, trade)| trade.price).collect();
+        assert_eq!(prices, vec![100.0, 99.0, 104.0, 103.0]);
+        assert!(trades.iter().all(|(_, _, trade)| trade.amount == 2.0));
+        assert_eq!(trades[3].1.timestamp_millis(), 1734721499999);
+    }
+
+    #[test]
+    fn test_unknown_instrument() {
+        let result = import_binance_csv("".as_bytes(), BinanceCsvKind::Klines, "DOGEUSDT", &btc_eth_instruments(), &mut Vec::new());
+        assert!(matches!(result, Err(ImportError::UnknownInstrument(_))));
+    }
+
+    #[test]
+    fn test_kind_and_timestamp_parsing() {
+        assert_eq!(BinanceCsvKind::from_file_name("BTCUSDT-aggTrades-2024-12-20.csv"), BinanceCsvKind::AggTrades);
+        // Second timestamps are accepted alongside milliseconds and microseconds
+        assert_eq!(parse_timestamp("1734721518").map(|time| time.timestamp()), Some(1734721518));
+    }
+}
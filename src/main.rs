@@ -1,4 +1,18 @@
 mod algorithm;
+mod backtest;
+mod historical;
+mod import;
+mod monte_carlo;
+mod optimizer;
+mod recorder;
+mod simulated_exchange;
+mod synthetic;
+mod tca;
+#[cfg(test)]
+mod test_util;
+mod util;
+mod valuation;
+mod walk_forward;
 
 use barter::{
     EngineEvent,
@@ -17,26 +31,53 @@ use barter::{
     statistic::time::Daily,
     system::{
         builder::{AuditMode, EngineFeedMode, SystemArgs, SystemBuilder},
-        config::SystemConfig,
+        config::{ExecutionConfig, SystemConfig},
     },
 };
 use barter_data::{
+    event::DataKind,
+    streams::consumer::MarketStreamEvent,
     streams::builder::dynamic::indexed::init_indexed_multi_exchange_market_stream,
     subscription::SubKind,
 };
-use barter_instrument::index::IndexedInstruments;
+use barter_instrument::{Keyed, index::IndexedInstruments, instrument::InstrumentIndex};
 use barter_integration::Terminal;
 use futures::StreamExt;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
-use std::{fs::File, io::BufReader, time::Duration};
+use std::{collections::HashMap, fs::File, io::BufReader, time::Duration};
 use tracing::debug;
-use crate::algorithm::data::AlgorithmData;
-use crate::algorithm::grid::Grid;
+use crate::algorithm::allocation::{AllocationScheme, CapitalAllocator};
+use crate::algorithm::data::{AlgorithmData, Indicator, StrategyData};
+use crate::algorithm::grid::{Grid, RegimePolicy};
 use crate::algorithm::vwap::Vwap;
+use crate::algorithm::transition::{Confirmation, TransitionFilter};
+use crate::algorithm::warmup::warm_up;
+use crate::backtest::{run_backtest, run_backtest_warmed_up, run_backtest_with_fills, run_backtest_with_trades, BacktestStrategy};
+use crate::historical::{load_market_events, stream_market_events, write_market_event};
+use crate::import::import_binance_file;
+use crate::monte_carlo::{MonteCarlo, Resampling, TradeOutcome};
+use crate::optimizer::{run_sweep, write_results, GridParams, ParameterSpace, RankBy, SearchMethod, StrategyParams, SweepResult, VwapParams};
+use crate::recorder::MarketRecorder;
+use crate::synthetic::{Clustering, FlashCrash, Gaps, PriceModel, SyntheticMarket};
+use crate::valuation::Valuation;
+use crate::simulated_exchange::{FillSimulation, LatencyModel, SlippageModel};
+use crate::tca::{write_tca_csv, Liquidity, TcaSummary, TradeCost};
+use crate::util::parse_window_length;
+use crate::walk_forward::{data_range, run_walk_forward, window_events, WalkForward, WalkForwardReport};
+
+/// Market events a backtest run replays
+type BacktestEvents = Box<dyn Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>> + Send>;
 
 const FILE_PATH_SYSTEM_CONFIG: &str = "config/system_config.json";
+const FILE_PATH_BACKTEST_DATA: &str = "config/data.json";
+const DIR_IMPORTED_DATA: &str = "data";
+/// Default exchange time of the first synthetic step, so a seed always reproduces the same events
+const SYNTHETIC_START: &str = "2024-01-01T00:00:00Z";
 const RISK_FREE_RETURN: Decimal = dec!(0.05);
+/// Currency wallets are valued in unless `--reporting` names another
+const REPORTING_CURRENCY: &str = "usdt";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -45,11 +86,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let config = load_config()?;
 
-    // Extract USDT wallet size from config BEFORE destructuring
-    let usdt_wallet_size = extract_usdt_wallet_size(&config);
-    println!("📊 USDT Wallet Size: ${:.2}", usdt_wallet_size);
-
-    // Now destructure the config
+    // Destructure the config
     let SystemConfig {
         instruments,
         executions,
@@ -58,6 +95,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Construct IndexedInstruments
     let instruments = IndexedInstruments::new(instruments);
 
+    // Convert Binance public-data CSVs into replayable market events
+    if let Some(paths) = subcommand_paths("import-binance") {
+        return import_binance_mode(&instruments, &paths);
+    }
+
+    // Generate a seeded synthetic scenario to backtest or write out
+    if subcommand_paths("synthetic").is_some() {
+        return run_synthetic_mode(&instruments, executions).await;
+    }
+
+    // Backtest a grid or random search over the Grid parameters
+    if let Some(paths) = subcommand_paths("sweep") {
+        return run_sweep_mode(&instruments, executions, &paths).await;
+    }
+
+    // Optimize on rolling in-sample windows and validate out of sample
+    if let Some(paths) = subcommand_paths("walk-forward") {
+        return run_walk_forward_mode(&instruments, executions, &paths).await;
+    }
+
+    // Stress a backtest's trade list with resampled and perturbed equity curves
+    if let Some(paths) = subcommand_paths("monte-carlo") {
+        return run_monte_carlo_mode(&instruments, executions, &paths).await;
+    }
+
+    // Replay recorded market data instead of trading live
+    if let Some(paths) = backtest_paths_arg() {
+        return run_backtest_mode(&instruments, executions, &paths).await;
+    }
+
+    // Optionally warm up indicators from recorded market data (JSON, newline-delimited or compressed
+    // files, or directories of them) before trading
+    let grid_params = single_params(grid_params()?, &GRID_KNOB_FLAGS)?;
+    let grid_data = grid_data(&grid_params);
+    let warmed_up = match list_flag::<String>("--warmup")? {
+        Some(paths) => {
+            println!("🔥 Warm-up: replaying events from {}", paths.join(", "));
+            let warmed_up = warm_up(market_events(&paths)?, || grid_data.clone());
+            print_warmup_summary(&instruments, &warmed_up);
+            warmed_up
+        }
+        None => HashMap::new(),
+    };
+
+    // Value the quote asset balances of every execution in the reporting currency,
+    // using warm-up prices for any quote asset that isn't the reporting currency itself
+    let valuation = Valuation::new(&reporting_currency()).with_market_prices(&instruments, &warmed_up);
+    let wallet_size = valuation.wallet_size(&instruments, &executions)?;
+    println!("📊 Wallet Size: {:.2} {}", wallet_size, valuation.reporting().to_uppercase());
+
     // Initialise MarketData Stream
     let market_stream = init_indexed_multi_exchange_market_stream(
         &instruments,
@@ -65,23 +152,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     )
         .await?;
 
+    // Optionally tee the live stream to disk (`--record <dir>`) in the format backtests replay
+    let mut recorder = flag_value("--record")
+        .map(|dir| MarketRecorder::new(dir, &instruments))
+        .transpose()?;
+    let market_stream = market_stream.inspect(move |event| {
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(event);
+        }
+    });
+
     // Construct System Args with dynamic wallet size
     let args = SystemArgs::new(
         &instruments,
         executions,
         LiveClock,
-        Grid::with_params(
-            usdt_wallet_size,
-            dec!(0.05),    // 5% bands
-            14,            // TMA period
-            dec!(0.005),   // 0.5% risk
-            dec!(0.01),    // 1% grid spacing
-            15             // 15 grid levels
-        ),
+        grid_strategy(wallet_size, grid_params),
         DefaultRiskManager::default(),
         market_stream,
-        DefaultGlobalData::default(),
-        |_| AlgorithmData::new(14),
+        DefaultGlobalData,
+        |instrument: &Keyed<InstrumentIndex, _>| warmed_up
+            .get(&instrument.key)
+            .cloned()
+            .unwrap_or_else(|| grid_data.clone()),
     );
 
     // Build & run System:
@@ -141,35 +234,689 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn load_config() -> Result<SystemConfig, Box<dyn std::error::Error>> {
-    let file = File::open(FILE_PATH_SYSTEM_CONFIG)?;
-    let reader = BufReader::new(file);
-    let config = serde_json::from_reader(reader)?;
-    Ok(config)
+/// Grid strategy configuration shared by live trading, backtests and parameter sweeps
+fn grid_strategy(wallet_size: Decimal, params: GridParams) -> Grid {
+    let grid = Grid::with_params(
+        wallet_size,
+        params.band_percentage,
+        params.tma_period,
+        params.risk_percentage,
+        params.grid_spacing_percentage,
+        params.max_grid_levels,
+    )
+    .with_book_skew_filter(dec!(0.6)) // Delay buys when top-of-book is 80/20 ask-heavy
+    .with_adx_filter(dec!(30))        // Stand aside while ADX signals a strong trend
+    .with_regime_policy(RegimePolicy::default()) // Trend-follow uptrends, no buys in downtrends, wider grid in high vol
+    .with_zone_filter(TransitionFilter::new(dec!(0.001), Duration::from_secs(5), Confirmation::Ticks(3))) // 0.1% band hysteresis, 3 ticks over 5s
+    .with_signal_cooldown(Duration::from_secs(30))
+    .with_capital_allocator(CapitalAllocator::new(AllocationScheme::Equal)) // Split equity evenly across instruments
+    .with_quantity_profile(params.quantity_profile);
+    if params.grid_budget > Decimal::ZERO {
+        return grid.with_grid_budget(params.grid_budget);
+    }
+    grid
+}
+
+/// Per-instrument data the grid built from `params` reads, for warming up before it exists
+fn grid_data(params: &GridParams) -> AlgorithmData {
+    grid_strategy(Decimal::ZERO, *params).algorithm_data()
 }
 
-/// Extracts the USDT wallet size from the system configuration
-pub fn extract_usdt_wallet_size(config: &SystemConfig) -> Decimal {
-    // Look through all executions to find USDT balance
-    for execution in &config.executions {
-        match execution {
-            barter::system::config::ExecutionConfig::Mock(mock_config) => {
-                // Search through balances to find USDT
-                for balance in &mock_config.initial_state.balances {
-                    // Convert AssetNameExchange to string and check if it's USDT
-                    let asset_name = balance.asset.name().to_lowercase();
-                    if asset_name == "usdt" {
-                        return balance.balance.total;
-                    }
+/// VWAP strategy configuration used by backtests and parameter sweeps
+fn vwap_strategy(wallet_size: Decimal, params: VwapParams) -> Vwap {
+    let confirmation = match params.vwap_confirm_ticks {
+        0 | 1 => Confirmation::Immediate,
+        ticks => Confirmation::Ticks(ticks),
+    };
+    let mut vwap = Vwap::with_risk(wallet_size, params.risk_percentage)
+        .with_rsi_thresholds(params.rsi_overbought, params.rsi_oversold)
+        .with_vwap_deadband(params.vwap_deadband)
+        .with_vwap_filter(TransitionFilter::new(params.vwap_hysteresis, Duration::ZERO, confirmation))
+        .with_combination(params.combination)
+        .with_vwap_reset(params.vwap_reset);
+    if params.min_flow_ratio > Decimal::ZERO {
+        vwap = vwap.with_flow_confirmation(params.min_flow_ratio);
+    }
+    if params.signal_cooldown_secs > 0 {
+        vwap = vwap.with_signal_cooldown(Duration::from_secs(params.signal_cooldown_secs as u64));
+    }
+    if params.vwap_band_sigma > 0 {
+        vwap = vwap.with_band_filter(params.vwap_band_sigma);
+    }
+    vwap
+}
+
+/// Replays recorded market data through the engine with mock execution and prints the TradingSummary
+///
+/// Usage: `backtest [paths...] [--strategy grid|vwap] [knob values...] [--fills ...] [--tca costs.csv]`,
+/// defaulting to `config/data.json` and the grid. Knob flags are those of `sweep`, with one value
+/// each, and apply to live trading and the other backtesting modes too. `--fills` swaps the mock
+/// exchange for simulated fills, see `fill_simulation`, and reports their transaction costs.
+/// `--reporting btc` values the wallet in another currency than USDT, here and in every other mode,
+/// at the first prices of the replay (of the warm-up when trading live).
+async fn run_backtest_mode(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    paths: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let strategy = strategy_flag()?;
+
+    let events = market_events(paths)?;
+    println!("⏪ Backtest: replaying events from {} with the {} strategy", paths.join(", "), strategy);
+
+    let valuation = replay_valuation(instruments, market_events(paths)?);
+    backtest_strategy(instruments, executions, events, strategy, valuation).await
+}
+
+/// Runs a backtest of `strategy` over `events` and prints the TradingSummary
+async fn backtest_strategy(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    events: impl Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>> + Send + 'static,
+    strategy: BacktestStrategy,
+    valuation: Valuation,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let wallet_size = valuation.wallet_size(instruments, &executions)?;
+    println!("📊 Wallet Size: {:.2} {}", wallet_size, valuation.reporting().to_uppercase());
+
+    let fills = fill_simulation()?;
+    let (trading_summary, costs) = match strategy {
+        BacktestStrategy::Grid => {
+            let grid = grid_strategy(wallet_size, single_params(grid_params()?, &GRID_KNOB_FLAGS)?);
+            match &fills {
+                Some(fills) => {
+                    let result = run_backtest_with_fills(instruments, executions, events, grid, RISK_FREE_RETURN, fills)?;
+                    (result.summary, result.costs)
                 }
+                // The mock exchange only fills market orders
+                None => (run_backtest(instruments, executions, events, grid.with_market_orders(), RISK_FREE_RETURN).await?, Vec::new()),
+            }
+        }
+        BacktestStrategy::Vwap => {
+            let vwap = vwap_strategy(wallet_size, single_params(vwap_params()?, &VWAP_KNOB_FLAGS)?);
+            match &fills {
+                Some(fills) => {
+                    let result = run_backtest_with_fills(instruments, executions, events, vwap, RISK_FREE_RETURN, fills)?;
+                    (result.summary, result.costs)
+                }
+                None => (run_backtest(instruments, executions, events, vwap.with_market_orders(), RISK_FREE_RETURN).await?, Vec::new()),
+            }
+        }
+    };
+
+    trading_summary.print_summary();
+    if fills.is_some() {
+        report_tca(instruments, &costs)?;
+    }
+
+    Ok(())
+}
+
+/// Prints the transaction costs of simulated fills against their signal and arrival prices,
+/// writing every fill to `--tca file.csv` if given
+fn report_tca(instruments: &IndexedInstruments, costs: &[TradeCost]) -> Result<(), Box<dyn std::error::Error>> {
+    println!("💸 Transaction costs (bps, positive = cost):");
+    println!("   all:   {}", TcaSummary::new(costs));
+    println!("   maker: {}", TcaSummary::new(costs.iter().filter(|cost| cost.liquidity == Liquidity::Maker)));
+    println!("   taker: {}", TcaSummary::new(costs.iter().filter(|cost| cost.liquidity == Liquidity::Taker)));
+
+    if let Some(out) = flag_value("--tca") {
+        write_tca_csv(File::create(&out)?, instruments, costs)?;
+        println!("💾 Transaction costs written to {}", out);
+    }
+    Ok(())
+}
+
+/// Opens market data for a backtest run
+///
+/// A single `.json` file is read as a JSON array, anything else as newline-delimited files (or
+/// directories of them, optionally gzip/zstd compressed) streamed lazily in timestamp order.
+fn market_events(
+    paths: &[String],
+) -> Result<BacktestEvents, Box<dyn std::error::Error>> {
+    match paths {
+        [path] if path.ends_with(".json") => Ok(Box::new(load_market_events(path)?.into_iter())),
+        _ => Ok(Box::new(stream_market_events(paths)?)),
+    }
+}
+
+/// Backtests a strategy and runs a Monte Carlo analysis over the trades it closed
+///
+/// Usage: `monte-carlo [paths...] [--strategy grid|vwap] [--simulations 10000] [--resample shuffle|bootstrap]
+/// [--slippage-bps 0] [--fill-noise-bps 0] [--ruin 0.5] [--seed N] [--out monte_carlo.json] [--fills ...]`, where
+/// `--ruin` is the drawdown of the starting wallet that counts as ruin.
+async fn run_monte_carlo_mode(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    paths: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let paths = if paths.is_empty() { vec![FILE_PATH_BACKTEST_DATA.to_string()] } else { paths.to_vec() };
+    let strategy = strategy_flag()?;
+    let float_flag = |flag: &str, default: f64| -> Result<f64, std::num::ParseFloatError> {
+        flag_value(flag).map(|value| value.parse()).transpose().map(|value| value.unwrap_or(default))
+    };
+
+    let wallet_size = replay_valuation(instruments, market_events(&paths)?).wallet_size(instruments, &executions)?;
+    let events = market_events(&paths)?;
+    println!("🎲 Monte Carlo: backtesting {} with the {} strategy", paths.join(", "), strategy);
+
+    let fills = fill_simulation()?;
+    let result = match strategy {
+        BacktestStrategy::Grid => {
+            let grid = grid_strategy(wallet_size, single_params(grid_params()?, &GRID_KNOB_FLAGS)?);
+            match &fills {
+                Some(fills) => run_backtest_with_fills(instruments, executions, events, grid, RISK_FREE_RETURN, fills)?,
+                None => run_backtest_with_trades(instruments, executions, events, grid.with_market_orders(), RISK_FREE_RETURN).await?,
             }
-            // Handle other execution config types if they exist
-            _ => continue,
         }
+        BacktestStrategy::Vwap => {
+            let vwap = vwap_strategy(wallet_size, single_params(vwap_params()?, &VWAP_KNOB_FLAGS)?);
+            match &fills {
+                Some(fills) => run_backtest_with_fills(instruments, executions, events, vwap, RISK_FREE_RETURN, fills)?,
+                None => run_backtest_with_trades(instruments, executions, events, vwap.with_market_orders(), RISK_FREE_RETURN).await?,
+            }
+        }
+    };
+    result.summary.print_summary();
+    if result.trades.is_empty() {
+        return Err("the backtest closed no positions to resample".into());
+    }
+
+    let trades: Vec<TradeOutcome> = result.trades.iter().map(TradeOutcome::from).collect();
+    let monte_carlo = MonteCarlo::new(
+        wallet_size.to_f64().unwrap_or_default(),
+        flag_value("--simulations").map(|simulations| simulations.parse()).transpose()?.unwrap_or(10_000),
+        flag_value("--seed").map(|seed| seed.parse()).transpose()?.unwrap_or(42),
+    )
+    .with_resampling(flag_value("--resample").map(|resampling| resampling.parse::<Resampling>()).transpose()?.unwrap_or(Resampling::Shuffle))
+    .with_slippage(float_flag("--slippage-bps", 0.0)?)
+    .with_fill_noise(float_flag("--fill-noise-bps", 0.0)?)
+    .with_ruin_drawdown(float_flag("--ruin", 0.5)?);
+    let report = monte_carlo.run(&trades);
+
+    println!("🎲 {} simulations of {} trades", report.simulations, report.trades);
+    println!("   Final PnL     | {}", report.final_pnl);
+    println!("   Max drawdown  | {}", report.max_drawdown);
+    println!("   Recovery (trades) | {}", report.time_to_recovery);
+    println!("   P(loss): {:.2}% | Risk of ruin: {:.2}%", report.probability_of_loss * 100.0, report.risk_of_ruin * 100.0);
+
+    let out = flag_value("--out").unwrap_or_else(|| "monte_carlo.json".to_string());
+    serde_json::to_writer_pretty(std::io::BufWriter::new(File::create(&out)?), &report.to_json())?;
+    println!("💾 Monte Carlo report written to {}", out);
+
+    Ok(())
+}
+
+/// Knob flags of the sweep and walk-forward modes, with the knob each one sets
+const GRID_KNOB_FLAGS: [(&str, &str); 6] = [
+    ("--bands", "band_percentage"),
+    ("--tma", "tma_period"),
+    ("--risk", "risk_percentage"),
+    ("--spacing", "grid_spacing_percentage"),
+    ("--levels", "max_grid_levels"),
+    ("--budget", "grid_budget"),
+];
+const VWAP_KNOB_FLAGS: [(&str, &str); 9] = [
+    ("--risk", "risk_percentage"),
+    ("--overbought", "rsi_overbought"),
+    ("--oversold", "rsi_oversold"),
+    ("--deadband", "vwap_deadband"),
+    ("--flow", "min_flow_ratio"),
+    ("--hysteresis", "vwap_hysteresis"),
+    ("--confirm-ticks", "vwap_confirm_ticks"),
+    ("--cooldown", "signal_cooldown_secs"),
+    ("--band-sigma", "vwap_band_sigma"),
+];
+
+/// Backtests a strategy over a parameter grid or random search, running backtests in parallel,
+/// and writes the ranked results
+///
+/// Usage: `sweep [paths...] [--strategy grid|vwap] [knob values...] [--search grid|random] [--samples N]
+/// [--seed N] [--rank sharpe|pnl|drawdown|profit-factor] [--jobs N] [--out results.csv|results.json]`.
+/// Knob values are comma separated: `--bands 0.03,0.05 --tma 10,14 --risk 0.005 --spacing 0.01,0.02
+/// --levels 10,15 --budget 0.2` for the grid and `--risk --overbought --oversold --deadband --flow
+/// --hysteresis --confirm-ticks --cooldown --band-sigma` for VWAP. Knobs left out keep the live
+/// values; random search samples each knob between its smallest and largest value. `--quantity-profile
+/// flat|linear:0.5|martingale:2|volume` for the grid, and `--combination rsi|vwap|agreement|precedence|vote:1,1,1`
+/// and `--vwap-reset daily|weekly|interval:8h|rolling:30m|anchored:<RFC 3339 time>` for VWAP are
+/// settings, the same for every candidate.
+async fn run_sweep_mode(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    paths: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let paths = if paths.is_empty() { vec![FILE_PATH_BACKTEST_DATA.to_string()] } else { paths.to_vec() };
+    let strategy = strategy_flag()?;
+    let (method, rank_by, jobs) = sweep_options()?;
+    let wallet_size = replay_valuation(instruments, market_events(&paths)?).wallet_size(instruments, &executions)?;
+    let out = flag_value("--out").unwrap_or_else(|| "sweep_results.csv".to_string());
+
+    match strategy {
+        BacktestStrategy::Grid => {
+            let candidates = parameter_space(grid_params()?, &GRID_KNOB_FLAGS)?.candidates(method);
+            println!("🔬 Sweep: {} {} candidates over {} with {} parallel backtests", candidates.len(), strategy, paths.join(", "), jobs);
+            let results = run_sweep(candidates, jobs, rank_by, |params| {
+                let (executions, paths) = (executions.clone(), &paths);
+                async move {
+                    let grid = grid_strategy(wallet_size, params).with_market_orders();
+                    run_backtest(instruments, executions, market_events(paths)?, grid, RISK_FREE_RETURN).await
+                }
+            })
+            .await;
+            report_sweep(&results, rank_by, &out)
+        }
+        BacktestStrategy::Vwap => {
+            let candidates = parameter_space(vwap_params()?, &VWAP_KNOB_FLAGS)?.candidates(method);
+            println!("🔬 Sweep: {} {} candidates over {} with {} parallel backtests", candidates.len(), strategy, paths.join(", "), jobs);
+            let results = run_sweep(candidates, jobs, rank_by, |params| {
+                let (executions, paths) = (executions.clone(), &paths);
+                async move {
+                    let vwap = vwap_strategy(wallet_size, params).with_market_orders();
+                    run_backtest(instruments, executions, market_events(paths)?, vwap, RISK_FREE_RETURN).await
+                }
+            })
+            .await;
+            report_sweep(&results, rank_by, &out)
+        }
+    }
+}
+
+/// Prints the top sweep runs and writes every run to `out`
+fn report_sweep<Params: StrategyParams>(
+    results: &[SweepResult<Params>],
+    rank_by: RankBy,
+    out: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🏆 Top runs by {:?}:", rank_by);
+    for (rank, result) in results.iter().take(10).enumerate() {
+        println!("   {}. {} | {}", rank + 1, result.params, result.metrics);
+    }
+
+    write_results(out, results)?;
+    println!("💾 Sweep results written to {}", out);
+    Ok(())
+}
+
+/// Walk-forward optimization: sweeps each rolling in-sample window, trades the best parameters on
+/// the out-of-sample window after it, and reports the stitched out-of-sample performance and how
+/// stable the chosen parameters were
+///
+/// Usage: `walk-forward [paths...] [--in-sample 7d] [--out-of-sample 1d]` plus the `sweep` flags,
+/// writing the report to `--out` (default `walk_forward.json`).
+async fn run_walk_forward_mode(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+    paths: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let paths = if paths.is_empty() { vec![FILE_PATH_BACKTEST_DATA.to_string()] } else { paths.to_vec() };
+    let strategy = strategy_flag()?;
+    let (method, rank_by, jobs) = sweep_options()?;
+    let wallet_size = replay_valuation(instruments, market_events(&paths)?).wallet_size(instruments, &executions)?;
+    let out = flag_value("--out").unwrap_or_else(|| "walk_forward.json".to_string());
+
+    let in_sample = parse_window_length(&flag_value("--in-sample").unwrap_or_else(|| "7d".to_string()))?;
+    let out_of_sample = parse_window_length(&flag_value("--out-of-sample").unwrap_or_else(|| "1d".to_string()))?;
+    let (start, end) = data_range(market_events(&paths)?).ok_or("walk-forward market data contains no market events")?;
+    let windows = WalkForward::new(in_sample, out_of_sample).windows(start, end);
+    if windows.is_empty() {
+        return Err(format!("market data from {} to {} is shorter than one in-sample window", start, end).into());
+    }
+    println!("🪟 Walk-forward: {} {} windows over {}", windows.len(), strategy, paths.join(", "));
+
+    match strategy {
+        BacktestStrategy::Grid => {
+            let candidates = parameter_space(grid_params()?, &GRID_KNOB_FLAGS)?.candidates(method);
+            let report = run_walk_forward(windows, candidates, jobs, rank_by, wallet_size, |params, range, warm_up_range| {
+                let (executions, paths) = (executions.clone(), &paths);
+                async move {
+                    let grid = grid_strategy(wallet_size, params).with_market_orders();
+                    let warm_up_events = warm_up_events(paths, warm_up_range)?;
+                    let events = window_events(market_events(paths)?, range);
+                    run_backtest_warmed_up(instruments, executions, warm_up_events, events, grid, RISK_FREE_RETURN).await
+                }
+            })
+            .await;
+            report_walk_forward(&report, &out)
+        }
+        BacktestStrategy::Vwap => {
+            let candidates = parameter_space(vwap_params()?, &VWAP_KNOB_FLAGS)?.candidates(method);
+            let report = run_walk_forward(windows, candidates, jobs, rank_by, wallet_size, |params, range, warm_up_range| {
+                let (executions, paths) = (executions.clone(), &paths);
+                async move {
+                    let vwap = vwap_strategy(wallet_size, params).with_market_orders();
+                    let warm_up_events = warm_up_events(paths, warm_up_range)?;
+                    let events = window_events(market_events(paths)?, range);
+                    run_backtest_warmed_up(instruments, executions, warm_up_events, events, vwap, RISK_FREE_RETURN).await
+                }
+            })
+            .await;
+            report_walk_forward(&report, &out)
+        }
+    }
+}
+
+/// Events of `range` to warm a walk-forward backtest up on, none without a range
+fn warm_up_events(
+    paths: &[String],
+    range: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
+) -> Result<BacktestEvents, Box<dyn std::error::Error>> {
+    match range {
+        Some(range) => Ok(Box::new(window_events(market_events(paths)?, range))),
+        None => Ok(Box::new(std::iter::empty())),
+    }
+}
+
+/// Prints the stitched out-of-sample metrics and parameter stability, and writes the full report as JSON
+fn report_walk_forward<Params: StrategyParams>(
+    report: &WalkForwardReport<Params>,
+    out: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🧵 Stitched out-of-sample: {}", report.stitched());
+    println!("⚖️  Parameter stability:");
+    for knob in report.stability() {
+        println!("   {}", knob);
+    }
+
+    let writer = std::io::BufWriter::new(File::create(out)?);
+    serde_json::to_writer_pretty(writer, &report.to_json())?;
+    println!("💾 Walk-forward report written to {}", out);
+    Ok(())
+}
+
+/// Reporting currency of `--reporting`, `REPORTING_CURRENCY` by default
+fn reporting_currency() -> String {
+    flag_value("--reporting").unwrap_or_else(|| REPORTING_CURRENCY.to_string())
+}
+
+/// Values balances in the reporting currency at the prices a replay starts from, so quote assets
+/// other than the reporting currency can be valued without a live feed
+fn replay_valuation(
+    instruments: &IndexedInstruments,
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+) -> Valuation {
+    Valuation::new(&reporting_currency()).with_replay_prices(instruments, events)
+}
+
+/// Search method, ranking and parallelism of the sweep and walk-forward modes
+fn sweep_options() -> Result<(SearchMethod, RankBy, usize), Box<dyn std::error::Error>> {
+    let method = match flag_value("--search").as_deref() {
+        None | Some("grid") => SearchMethod::Grid,
+        Some("random") => SearchMethod::Random {
+            samples: flag_value("--samples").map(|samples| samples.parse()).transpose()?.unwrap_or(50),
+            seed: flag_value("--seed").map(|seed| seed.parse()).transpose()?.unwrap_or(42),
+        },
+        Some(other) => return Err(format!("unknown search '{}', expected grid or random", other).into()),
+    };
+    let rank_by = flag_value("--rank")
+        .map(|rank_by| rank_by.parse::<RankBy>())
+        .transpose()?
+        .unwrap_or(RankBy::Sharpe);
+    let jobs = match flag_value("--jobs") {
+        Some(jobs) => jobs.parse()?,
+        None => std::thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1),
+    };
+    Ok((method, rank_by, jobs))
+}
+
+/// Grid parameters the knob flags start from, with the quantity profile of `--quantity-profile`
+fn grid_params() -> Result<GridParams, Box<dyn std::error::Error>> {
+    let mut params = GridParams::default();
+    if let Some(profile) = flag_value("--quantity-profile") {
+        params.quantity_profile = profile.parse()?;
+    }
+    Ok(params)
+}
+
+/// VWAP parameters the knob flags start from, with the signal combination of `--combination`
+/// and the VWAP reset of `--vwap-reset`
+fn vwap_params() -> Result<VwapParams, Box<dyn std::error::Error>> {
+    let mut params = VwapParams::default();
+    if let Some(combination) = flag_value("--combination") {
+        params.combination = combination.parse()?;
+    }
+    if let Some(reset) = flag_value("--vwap-reset") {
+        params.vwap_reset = reset.parse()?;
+    }
+    Ok(params)
+}
+
+/// Parameters of a single run: `defaults` with any knob flag passed on the command line, each
+/// taking one value
+fn single_params<Params: StrategyParams>(
+    defaults: Params,
+    flags: &[(&str, &str)],
+) -> Result<Params, Box<dyn std::error::Error>> {
+    match parameter_space(defaults, flags)?.candidates(SearchMethod::Grid).as_slice() {
+        [params] => Ok(*params),
+        _ => Err("knob flags take a single value outside sweep and walk-forward".into()),
+    }
+}
+
+/// Parameter space around `defaults`, with the values of any knob flag passed on the command line
+fn parameter_space<Params: StrategyParams>(
+    defaults: Params,
+    flags: &[(&str, &str)],
+) -> Result<ParameterSpace<Params>, Box<dyn std::error::Error>> {
+    let mut space = ParameterSpace::from(defaults);
+    for (flag, knob) in flags {
+        if let Some(values) = list_flag::<Decimal>(flag)? {
+            space = space.with_values(knob, values)?;
+        }
+    }
+    Ok(space)
+}
+
+/// Generates a seeded synthetic market and backtests it, or writes it out for later replay
+///
+/// Usage: `synthetic [--model gbm|ou|regime] [--seed N] [--steps N] [--price P] [--start RFC 3339 time]
+/// [--instrument-price BTCUSDT=P,...] [--interval 1s] [--trade-amount A] [--book-levels N] [--spread S]
+/// [--flash-crash] [--gaps] [--clustering] [--out file.jsonl] [--strategy grid|vwap] [--fills ...]`
+async fn run_synthetic_mode(
+    instruments: &IndexedInstruments,
+    executions: Vec<ExecutionConfig>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let model_name = flag_value("--model").unwrap_or_else(|| "gbm".to_string());
+    let price: f64 = flag_value("--price").map(|price| price.parse()).transpose()?.unwrap_or(100.0);
+    let seed: u64 = flag_value("--seed").map(|seed| seed.parse()).transpose()?.unwrap_or(42);
+    let steps: usize = flag_value("--steps").map(|steps| steps.parse()).transpose()?.unwrap_or(86_400);
+    let start = chrono::DateTime::parse_from_rfc3339(&flag_value("--start").unwrap_or_else(|| SYNTHETIC_START.to_string()))?
+        .with_timezone(&chrono::Utc);
+
+    let mut market = SyntheticMarket::new(PriceModel::preset(&model_name, price)?, seed).with_start_price(price);
+    for pair in list_flag::<String>("--instrument-price")?.unwrap_or_default() {
+        let (instrument, price) = pair.split_once('=').ok_or_else(|| format!("invalid --instrument-price '{}', expected NAME=PRICE", pair))?;
+        market = market.with_instrument_price(instrument, price.parse()?);
+    }
+    if let Some(interval) = flag_value("--interval") {
+        market = market.with_interval(parse_window_length(&interval)?.to_std()?);
+    }
+    if let Some(amount) = flag_value("--trade-amount") {
+        let amount: f64 = amount.parse()?;
+        if !(amount.is_finite() && amount > 0.0) {
+            return Err(format!("--trade-amount must be positive, got {}", amount).into());
+        }
+        market = market.with_trade_amount(amount);
+    }
+    if let Some(levels) = flag_value("--book-levels") {
+        market = market.with_book_levels(levels.parse()?);
+    }
+    if let Some(spread) = flag_value("--spread") {
+        let spread: f64 = spread.parse()?;
+        if !(spread.is_finite() && spread >= 0.0) {
+            return Err(format!("--spread must be zero or more, got {}", spread).into());
+        }
+        market = market.with_spread(spread);
+    }
+    if has_flag("--flash-crash") {
+        market = market.with_flash_crashes(FlashCrash { probability: 0.0005, depth: 0.15, recovery_steps: 300 });
+    }
+    if has_flag("--gaps") {
+        market = market.with_gaps(Gaps { probability: 0.0002, max_jump: 0.05, pause: Duration::from_secs(900) });
+    }
+    if has_flag("--clustering") {
+        market = market.with_volatility_clustering(Clustering { reaction: 0.08, persistence: 0.9 });
+    }
+
+    let events = market.events(instruments, start, steps);
+
+    if let Some(out) = flag_value("--out") {
+        let mut writer = std::io::BufWriter::new(File::create(&out)?);
+        let mut written = 0;
+        for event in events {
+            write_market_event(&mut writer, &event)?;
+            written += 1;
+        }
+        std::io::Write::flush(&mut writer)?;
+        println!("🧪 Synthetic: wrote {} {} events (seed {}) to {}", written, model_name, seed, out);
+        return Ok(());
+    }
+
+    let strategy = strategy_flag()?;
+    println!("🧪 Synthetic: backtesting {} steps of {} (seed {}) with the {} strategy", steps, model_name, seed, strategy);
+
+    let valuation = replay_valuation(instruments, market.events(instruments, start, steps));
+    backtest_strategy(instruments, executions, events, strategy, valuation).await
+}
+
+/// Converts Binance kline/aggTrades CSVs into newline-delimited market events a backtest can replay
+///
+/// Usage: `import-binance <csv files...> [--out dir]`, writing `<dir>/<csv name>.jsonl` (default `data/`)
+fn import_binance_mode(instruments: &IndexedInstruments, paths: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let out = flag_value("--out").unwrap_or_else(|| DIR_IMPORTED_DATA.to_string());
+    std::fs::create_dir_all(&out)?;
+
+    for path in paths {
+        let stem = std::path::Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("invalid CSV path {}", path))?;
+        let output = std::path::Path::new(&out).join(format!("{}.jsonl", stem.to_lowercase()));
+
+        let mut writer = std::io::BufWriter::new(File::create(&output)?);
+        let report = import_binance_file(path, instruments, &mut writer)?;
+        std::io::Write::flush(&mut writer)?;
+
+        println!("📥 Imported {} -> {} | {}", path, output.display(), report);
+        for skipped in report.skipped.iter().take(10) {
+            println!("   ⚠️  line {}: {}", skipped.line, skipped.reason);
+        }
+        if report.skipped.len() > 10 {
+            println!("   ... and {} more skipped rows", report.skipped.len() - 10);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the market data paths when started as `backtest [paths...]`
+fn backtest_paths_arg() -> Option<Vec<String>> {
+    let paths = subcommand_paths("backtest")?;
+    if paths.is_empty() {
+        return Some(vec![FILE_PATH_BACKTEST_DATA.to_string()]);
+    }
+    Some(paths)
+}
+
+/// Returns the paths following `subcommand` when it is the first argument, up to the first flag
+fn subcommand_paths(subcommand: &str) -> Option<Vec<String>> {
+    let mut args = std::env::args().skip(1);
+    if args.next()? != subcommand {
+        return None;
+    }
+    Some(args.take_while(|arg| !arg.starts_with("--")).collect())
+}
+
+/// Realistic fill simulation, enabled with `--fills`
+///
+/// Flags: `--latency 100|uniform:50-150|lognormal:80,0.5` (milliseconds each way), `--maker-fee` and
+/// `--taker-fee` as fractions of notional, `--slippage book|fixed:5|spread:0.5|sqrt:0.1,1h` for
+/// marketable orders, and `--fill-seed N`. Latency and fees default to the mock execution config.
+fn fill_simulation() -> Result<Option<FillSimulation>, Box<dyn std::error::Error>> {
+    if !has_flag("--fills") {
+        return Ok(None);
+    }
+
+    let mut fills = FillSimulation::new(flag_value("--fill-seed").map(|seed| seed.parse()).transpose()?.unwrap_or(42));
+    if let Some(latency) = flag_value("--latency") {
+        fills = fills.with_latency(latency.parse::<LatencyModel>()?);
+    }
+    if let Some(slippage) = flag_value("--slippage") {
+        fills = fills.with_slippage(slippage.parse::<SlippageModel>()?);
+    }
+    match (flag_value("--maker-fee"), flag_value("--taker-fee")) {
+        (None, None) => {}
+        (Some(maker), Some(taker)) => fills = fills.with_fees(maker.parse()?, taker.parse()?),
+        _ => return Err("--maker-fee and --taker-fee must be given together".into()),
+    }
+    Ok(Some(fills))
+}
+
+/// Strategy selected with `--strategy`, the grid by default
+fn strategy_flag() -> Result<BacktestStrategy, String> {
+    flag_value("--strategy")
+        .map(|strategy| strategy.parse::<BacktestStrategy>())
+        .transpose()
+        .map(|strategy| strategy.unwrap_or(BacktestStrategy::Grid))
+}
+
+/// Parses the comma separated values following `flag`, if any
+fn list_flag<T>(flag: &str) -> Result<Option<Vec<T>>, Box<dyn std::error::Error>>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + 'static,
+{
+    let Some(values) = flag_value(flag) else { return Ok(None); };
+    let values = values.split(',')
+        .map(|value| value.trim().parse::<T>())
+        .collect::<Result<Vec<T>, _>>()?;
+    Ok(Some(values))
+}
+
+/// Returns whether `flag` was passed on the command line
+fn has_flag(flag: &str) -> bool {
+    std::env::args().skip(1).any(|arg| arg == flag)
+}
+
+/// Returns the value following `flag` on the command line, if any
+fn flag_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// Prints which indicators each instrument still needs live data for after warm-up
+fn print_warmup_summary(instruments: &IndexedInstruments, warmed_up: &HashMap<InstrumentIndex, AlgorithmData>) {
+    const ALL_INDICATORS: [Indicator; 11] = [
+        Indicator::Rsi,
+        Indicator::Sma,
+        Indicator::Vwap,
+        Indicator::OrderBook,
+        Indicator::TradeFlow,
+        Indicator::Macd,
+        Indicator::Stochastic,
+        Indicator::Adx,
+        Indicator::Regime,
+        Indicator::Atr,
+        Indicator::RealizedVolatility,
+    ];
+
+    for instrument in instruments.instruments() {
+        let pending = match warmed_up.get(&instrument.key) {
+            Some(data) => data.pending_indicators(&ALL_INDICATORS),
+            None => ALL_INDICATORS.to_vec(),
+        };
+        println!("   {} | pending: {:?}", instrument.value.name_exchange.name(), pending);
     }
+}
 
-    // Default fallback if USDT not found
-    println!("⚠️  USDT balance not found in config, using default: $10,000");
-    dec!(10000)
+fn load_config() -> Result<SystemConfig, Box<dyn std::error::Error>> {
+    let file = File::open(FILE_PATH_SYSTEM_CONFIG)?;
+    let reader = BufReader::new(file);
+    let config = serde_json::from_reader(reader)?;
+    Ok(config)
 }
 
@@ -0,0 +1,333 @@
+use barter::engine::state::position::PositionExited;
+use barter_instrument::asset::QuoteAsset;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal::prelude::ToPrimitive;
+use std::fmt;
+use std::str::FromStr;
+use crate::util::standard_normal;
+
+/// Percentiles reported for every distribution
+const PERCENTILES: [u8; 7] = [1, 5, 25, 50, 75, 95, 99];
+
+/// A closed trade as the simulation sees it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeOutcome {
+    /// Realised PnL including fees
+    pub pnl: f64,
+    /// Entry notional, the size slippage and fill noise are charged on
+    pub notional: f64,
+}
+
+impl From<&PositionExited<QuoteAsset>> for TradeOutcome {
+    fn from(position: &PositionExited<QuoteAsset>) -> Self {
+        Self {
+            pnl: position.pnl_realised.to_f64().unwrap_or_default(),
+            notional: (position.price_entry_average * position.quantity_abs_max).to_f64().unwrap_or_default(),
+        }
+    }
+}
+
+/// How each simulation reorders the backtest's trades
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resampling {
+    /// Same trades in a random order: same final PnL, different path
+    Shuffle,
+    /// As many trades drawn with replacement: different final PnL and path
+    Bootstrap,
+}
+
+impl FromStr for Resampling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "shuffle" => Ok(Resampling::Shuffle),
+            "bootstrap" => Ok(Resampling::Bootstrap),
+            other => Err(format!("unknown resampling '{}', expected shuffle or bootstrap", other)),
+        }
+    }
+}
+
+/// Monte Carlo robustness analysis of a backtest's trade list
+///
+/// Every simulation reorders the trades, charges extra slippage on entry and exit, and adds
+/// random fill noise, then walks the equity curve from `starting_equity`. Time to recovery is
+/// measured in trades, since reordering discards the original timestamps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarlo {
+    simulations: usize,
+    seed: u64,
+    starting_equity: f64,
+    resampling: Resampling,
+    slippage_bps: f64,
+    fill_noise_bps: f64,
+    ruin_drawdown: f64,
+}
+
+impl MonteCarlo {
+    pub fn new(starting_equity: f64, simulations: usize, seed: u64) -> Self {
+        Self {
+            simulations,
+            seed,
+            starting_equity,
+            resampling: Resampling::Shuffle,
+            slippage_bps: 0.0,
+            fill_noise_bps: 0.0,
+            ruin_drawdown: 0.5, // losing half the account
+        }
+    }
+
+    pub fn with_resampling(mut self, resampling: Resampling) -> Self {
+        self.resampling = resampling;
+        self
+    }
+
+    /// Extra cost per fill in basis points of notional, charged on entry and exit
+    pub fn with_slippage(mut self, bps: f64) -> Self {
+        self.slippage_bps = bps;
+        self
+    }
+
+    /// Standard deviation of the random fill price error per fill, in basis points of notional
+    pub fn with_fill_noise(mut self, bps: f64) -> Self {
+        self.fill_noise_bps = bps;
+        self
+    }
+
+    /// Drawdown from the starting equity that counts as ruin (0.5 = losing half the account)
+    pub fn with_ruin_drawdown(mut self, drawdown: f64) -> Self {
+        self.ruin_drawdown = drawdown;
+        self
+    }
+
+    pub fn run(&self, trades: &[TradeOutcome]) -> MonteCarloReport {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut final_pnls = Vec::with_capacity(self.simulations);
+        let mut max_drawdowns = Vec::with_capacity(self.simulations);
+        let mut recoveries = Vec::with_capacity(self.simulations);
+        let mut ruined = 0;
+
+        let mut path = trades.to_vec();
+        for _ in 0..self.simulations {
+            match self.resampling {
+                Resampling::Shuffle => path.shuffle(&mut rng),
+                Resampling::Bootstrap => {
+                    for trade in path.iter_mut() {
+                        *trade = trades[rng.random_range(0..trades.len())];
+                    }
+                }
+            }
+
+            let simulation = self.simulate(&path, &mut rng);
+            final_pnls.push(simulation.final_pnl);
+            max_drawdowns.push(simulation.max_drawdown);
+            recoveries.push(simulation.longest_recovery as f64);
+            if simulation.ruined {
+                ruined += 1;
+            }
+        }
+
+        let simulations = self.simulations.max(1) as f64;
+        MonteCarloReport {
+            simulations: self.simulations,
+            trades: trades.len(),
+            probability_of_loss: final_pnls.iter().filter(|pnl| **pnl < 0.0).count() as f64 / simulations,
+            risk_of_ruin: ruined as f64 / simulations,
+            final_pnl: Distribution::new(final_pnls),
+            max_drawdown: Distribution::new(max_drawdowns),
+            time_to_recovery: Distribution::new(recoveries),
+        }
+    }
+
+    fn simulate(&self, path: &[TradeOutcome], rng: &mut ChaCha8Rng) -> Simulation {
+        let ruin_equity = self.starting_equity * (1.0 - self.ruin_drawdown);
+        let mut equity = self.starting_equity;
+        let mut peak = equity;
+        let mut simulation = Simulation::default();
+        let mut under_water = 0;
+
+        for trade in path {
+            let notional = trade.notional.abs();
+            let slippage = notional * 2.0 * self.slippage_bps / 10_000.0;
+            // Entry and exit errors are independent, so their sum has sqrt(2) times the deviation
+            let noise = notional * self.fill_noise_bps / 10_000.0 * std::f64::consts::SQRT_2 * standard_normal(rng);
+            equity += trade.pnl - slippage + noise;
+
+            if equity >= peak {
+                peak = equity;
+                under_water = 0;
+            } else {
+                under_water += 1;
+                if peak > 0.0 {
+                    simulation.max_drawdown = simulation.max_drawdown.max((peak - equity) / peak);
+                }
+            }
+            // Drawdowns still open at the end count up to the last trade
+            simulation.longest_recovery = simulation.longest_recovery.max(under_water);
+            if equity <= ruin_equity {
+                simulation.ruined = true;
+            }
+        }
+
+        simulation.final_pnl = equity - self.starting_equity;
+        simulation
+    }
+}
+
+#[derive(Debug, Default)]
+struct Simulation {
+    final_pnl: f64,
+    max_drawdown: f64,
+    longest_recovery: usize,
+    ruined: bool,
+}
+
+/// Summary statistics of one simulated quantity
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Distribution {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    /// `(percentile, value)` for each of `PERCENTILES`, nearest-rank
+    pub percentiles: Vec<(u8, f64)>,
+}
+
+impl Distribution {
+    pub fn new(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(f64::total_cmp);
+
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count;
+
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+            min: values[0],
+            max: values[values.len() - 1],
+            percentiles: PERCENTILES.iter()
+                .map(|percentile| {
+                    let rank = (*percentile as f64 / 100.0 * count).ceil() as usize;
+                    (*percentile, values[rank.clamp(1, values.len()) - 1])
+                })
+                .collect(),
+        }
+    }
+
+    pub fn percentile(&self, percentile: u8) -> Option<f64> {
+        self.percentiles.iter().find(|(p, _)| *p == percentile).map(|(_, value)| *value)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let percentiles: serde_json::Map<String, serde_json::Value> = self.percentiles.iter()
+            .map(|(percentile, value)| (format!("p{}", percentile), serde_json::Value::from(*value)))
+            .collect();
+        serde_json::json!({
+            "mean": self.mean,
+            "std_dev": self.std_dev,
+            "min": self.min,
+            "max": self.max,
+            "percentiles": percentiles,
+        })
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mean {:.4} | p5 {:.4} | p50 {:.4} | p95 {:.4} | worst {:.4}",
+               self.mean,
+               self.percentile(5).unwrap_or_default(),
+               self.percentile(50).unwrap_or_default(),
+               self.percentile(95).unwrap_or_default(),
+               self.min
+        )
+    }
+}
+
+/// Distributions across every simulation
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarloReport {
+    pub simulations: usize,
+    pub trades: usize,
+    pub final_pnl: Distribution,
+    /// Deepest drawdown of each simulation, as a fraction of peak equity
+    pub max_drawdown: Distribution,
+    /// Longest run of trades spent below a previous equity peak
+    pub time_to_recovery: Distribution,
+    /// Share of simulations ending with a loss
+    pub probability_of_loss: f64,
+    /// Share of simulations whose equity fell to the ruin level at any point
+    pub risk_of_ruin: f64,
+}
+
+impl MonteCarloReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "simulations": self.simulations,
+            "trades": self.trades,
+            "final_pnl": self.final_pnl.to_json(),
+            "max_drawdown": self.max_drawdown.to_json(),
+            "time_to_recovery_trades": self.time_to_recovery.to_json(),
+            "probability_of_loss": self.probability_of_loss,
+            "risk_of_ruin": self.risk_of_ruin,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trades(pnls: &[f64]) -> Vec<TradeOutcome> {
+        pnls.iter()
+            .map(|pnl| TradeOutcome { pnl: *pnl, notional: 1_000.0 })
+            .collect()
+    }
+
+    #[test]
+    fn test_distribution_percentiles() {
+        let distribution = Distribution::new((1..=100).rev().map(f64::from).collect());
+        assert_eq!(distribution.min, 1.0);
+        assert_eq!(distribution.max, 100.0);
+        assert_eq!(distribution.mean, 50.5);
+        assert_eq!(distribution.percentile(5), Some(5.0));
+        assert_eq!(distribution.percentile(50), Some(50.0));
+        assert_eq!(distribution.percentile(99), Some(99.0));
+    }
+
+    #[test]
+    fn test_shuffle_keeps_final_pnl_and_varies_drawdown() {
+        let trades = trades(&[100.0, -50.0, 80.0, -120.0, 60.0, -30.0, 90.0, -70.0]);
+        let report = MonteCarlo::new(1_000.0, 500, 7).run(&trades);
+
+        assert_eq!(report.simulations, 500);
+        assert!((report.final_pnl.min - 60.0).abs() < 1e-9 && (report.final_pnl.max - 60.0).abs() < 1e-9);
+        assert!(report.max_drawdown.max > report.max_drawdown.min);
+        assert_eq!(report.probability_of_loss, 0.0);
+        assert_eq!(report, MonteCarlo::new(1_000.0, 500, 7).run(&trades));
+    }
+
+    #[test]
+    fn test_slippage_and_risk_of_ruin() {
+        // 1 bp per fill on 1000 notional costs 0.2 per round trip
+        let trades = trades(&[1.0; 10]);
+        let report = MonteCarlo::new(1_000.0, 10, 1).with_slippage(1.0).run(&trades);
+        assert!((report.final_pnl.mean - 8.0).abs() < 1e-9);
+
+        let losing = self::trades(&[-300.0, -300.0, 50.0]);
+        let report = MonteCarlo::new(1_000.0, 200, 3)
+            .with_resampling(Resampling::Bootstrap)
+            .with_fill_noise(5.0)
+            .with_ruin_drawdown(0.5)
+            .run(&losing);
+        // Ruined whenever the bootstrap draws both or all three losers
+        assert!(report.risk_of_ruin > 0.3 && report.risk_of_ruin < 1.0);
+        assert!(report.time_to_recovery.max >= 2.0);
+    }
+}
@@ -0,0 +1,628 @@
+use barter::statistic::summary::TradingSummary;
+use barter::statistic::time::Daily;
+use chrono::Local;
+use futures::StreamExt;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal_macros::dec;
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use crate::algorithm::grid::QuantityProfile;
+use crate::algorithm::indicators::vwap::VwapReset;
+use crate::algorithm::vwap::SignalCombination;
+
+/// A decimal knob of a strategy and the values it accepts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Knob {
+    pub name: &'static str,
+    /// Smallest and largest accepted value, inclusive
+    pub range: (Decimal, Decimal),
+    /// Whether only whole numbers are accepted, eg/ periods and level counts
+    pub integer: bool,
+}
+
+impl Knob {
+    const fn decimal(name: &'static str, min: Decimal, max: Decimal) -> Self {
+        Self { name, range: (min, max), integer: false }
+    }
+
+    const fn integer(name: &'static str, min: Decimal, max: Decimal) -> Self {
+        Self { name, range: (min, max), integer: true }
+    }
+
+    /// Checks a candidate value is in range, and whole for integer knobs
+    pub fn validate(&self, value: Decimal) -> Result<(), String> {
+        let (min, max) = self.range;
+        if value < min || value > max {
+            return Err(format!("{} must be between {} and {}, got {}", self.name, min, max, value));
+        }
+        if self.integer && !value.fract().is_zero() {
+            return Err(format!("{} must be a whole number, got {}", self.name, value));
+        }
+        Ok(())
+    }
+}
+
+/// Tunable parameters of a strategy, as the decimal knobs a sweep searches over
+///
+/// Settings that are not knobs, eg/ how signals are combined, are fixed for a whole sweep and kept
+/// from the parameters the search starts from.
+pub trait StrategyParams: Copy + fmt::Display {
+    /// Knobs, in the order of `values`
+    const KNOBS: &'static [Knob];
+
+    fn values(&self) -> Vec<Decimal>;
+
+    /// These parameters with knob values in `KNOBS` order, already validated against their knobs
+    fn with_knob_values(self, values: &[Decimal]) -> Self;
+}
+
+/// Tuning knobs of `Grid::with_params`, other than the wallet size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridParams {
+    pub band_percentage: Decimal,
+    pub tma_period: usize,
+    pub risk_percentage: Decimal,
+    pub grid_spacing_percentage: Decimal,
+    pub max_grid_levels: usize,
+    /// Share of the wallet one side of the grid may hold, 0 for no grid budget
+    pub grid_budget: Decimal,
+    /// How quantity varies across grid levels, a setting rather than a knob
+    pub quantity_profile: QuantityProfile,
+}
+
+impl Default for GridParams {
+    fn default() -> Self {
+        Self {
+            band_percentage: dec!(0.05),         // 5% bands
+            tma_period: 14,                      // TMA period
+            risk_percentage: dec!(0.005),        // 0.5% risk
+            grid_spacing_percentage: dec!(0.01), // 1% grid spacing
+            max_grid_levels: 15,                 // 15 grid levels
+            grid_budget: dec!(0),                // No grid budget
+            quantity_profile: QuantityProfile::Flat,
+        }
+    }
+}
+
+impl fmt::Display for GridParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bands {} | TMA {} | risk {} | spacing {} | levels {} | budget {} | {}",
+               self.band_percentage,
+               self.tma_period,
+               self.risk_percentage,
+               self.grid_spacing_percentage,
+               self.max_grid_levels,
+               self.grid_budget,
+               self.quantity_profile
+        )
+    }
+}
+
+impl StrategyParams for GridParams {
+    const KNOBS: &'static [Knob] = &[
+        Knob::decimal("band_percentage", dec!(0.0001), dec!(1)),
+        Knob::integer("tma_period", dec!(1), dec!(1000)),
+        Knob::decimal("risk_percentage", dec!(0.0001), dec!(1)),
+        Knob::decimal("grid_spacing_percentage", dec!(0.0001), dec!(1)),
+        Knob::integer("max_grid_levels", dec!(1), dec!(100)),
+        Knob::decimal("grid_budget", dec!(0), dec!(1)),
+    ];
+
+    fn values(&self) -> Vec<Decimal> {
+        vec![
+            self.band_percentage,
+            Decimal::from(self.tma_period),
+            self.risk_percentage,
+            self.grid_spacing_percentage,
+            Decimal::from(self.max_grid_levels),
+            self.grid_budget,
+        ]
+    }
+
+    fn with_knob_values(self, values: &[Decimal]) -> Self {
+        Self {
+            band_percentage: values[0],
+            tma_period: integer(values[1]),
+            risk_percentage: values[2],
+            grid_spacing_percentage: values[3],
+            max_grid_levels: integer(values[4]),
+            grid_budget: values[5],
+            ..self
+        }
+    }
+}
+
+/// Tuning knobs of `Vwap`, other than the wallet size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VwapParams {
+    pub risk_percentage: Decimal,
+    pub rsi_overbought: Decimal,
+    pub rsi_oversold: Decimal,
+    pub vwap_deadband: Decimal,
+    /// Share of recent volume that must agree with a VWAP cross, 0 for no flow confirmation
+    pub min_flow_ratio: Decimal,
+    /// Relative margin price must clear VWAP by to cross it
+    pub vwap_hysteresis: Decimal,
+    /// Consecutive ticks a VWAP cross must hold for, 1 to accept it straight away
+    pub vwap_confirm_ticks: usize,
+    /// Seconds an instrument ignores new signals after one, 0 for no cooldown
+    pub signal_cooldown_secs: usize,
+    /// Band, in standard deviations, beyond which VWAP crosses are ignored, 0 for no band filter
+    pub vwap_band_sigma: usize,
+    /// When the VWAP starts accumulating afresh, a setting rather than a knob
+    pub vwap_reset: VwapReset,
+    /// How RSI and VWAP signals are merged, a setting rather than a knob
+    pub combination: SignalCombination,
+}
+
+impl Default for VwapParams {
+    fn default() -> Self {
+        Self {
+            risk_percentage: dec!(0.005), // 0.5% risk
+            rsi_overbought: dec!(80),
+            rsi_oversold: dec!(20),
+            vwap_deadband: dec!(0.001),   // 0.1% around VWAP
+            min_flow_ratio: dec!(0),
+            vwap_hysteresis: dec!(0),
+            vwap_confirm_ticks: 1,
+            signal_cooldown_secs: 0,
+            vwap_band_sigma: 0,
+            vwap_reset: VwapReset::Daily,
+            combination: SignalCombination::RsiPrecedence,
+        }
+    }
+}
+
+impl fmt::Display for VwapParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "risk {} | RSI {}/{} | deadband {} | flow {} | hysteresis {} | confirm {} | cooldown {}s | band {}σ | {} | {}",
+               self.risk_percentage,
+               self.rsi_overbought,
+               self.rsi_oversold,
+               self.vwap_deadband,
+               self.min_flow_ratio,
+               self.vwap_hysteresis,
+               self.vwap_confirm_ticks,
+               self.signal_cooldown_secs,
+               self.vwap_band_sigma,
+               self.vwap_reset,
+               self.combination
+        )
+    }
+}
+
+impl StrategyParams for VwapParams {
+    const KNOBS: &'static [Knob] = &[
+        Knob::decimal("risk_percentage", dec!(0.0001), dec!(1)),
+        Knob::decimal("rsi_overbought", dec!(0), dec!(100)),
+        Knob::decimal("rsi_oversold", dec!(0), dec!(100)),
+        Knob::decimal("vwap_deadband", dec!(0), dec!(1)),
+        Knob::decimal("min_flow_ratio", dec!(0), dec!(1)),
+        Knob::decimal("vwap_hysteresis", dec!(0), dec!(0.1)),
+        Knob::integer("vwap_confirm_ticks", dec!(1), dec!(100)),
+        Knob::integer("signal_cooldown_secs", dec!(0), dec!(86400)),
+        Knob::integer("vwap_band_sigma", dec!(0), dec!(2)),
+    ];
+
+    fn values(&self) -> Vec<Decimal> {
+        vec![
+            self.risk_percentage,
+            self.rsi_overbought,
+            self.rsi_oversold,
+            self.vwap_deadband,
+            self.min_flow_ratio,
+            self.vwap_hysteresis,
+            Decimal::from(self.vwap_confirm_ticks),
+            Decimal::from(self.signal_cooldown_secs),
+            Decimal::from(self.vwap_band_sigma),
+        ]
+    }
+
+    fn with_knob_values(self, values: &[Decimal]) -> Self {
+        Self {
+            risk_percentage: values[0],
+            rsi_overbought: values[1],
+            rsi_oversold: values[2],
+            vwap_deadband: values[3],
+            min_flow_ratio: values[4],
+            vwap_hysteresis: values[5],
+            vwap_confirm_ticks: integer(values[6]),
+            signal_cooldown_secs: integer(values[7]),
+            vwap_band_sigma: integer(values[8]),
+            ..self
+        }
+    }
+}
+
+/// Integer knob value, which `Knob::validate` has already checked is whole and non-negative
+fn integer(value: Decimal) -> usize {
+    value.to_usize().unwrap_or_default()
+}
+
+/// Candidate values of every knob, a single value keeps a knob fixed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterSpace<Params> {
+    knobs: Vec<Vec<Decimal>>,
+    /// Parameters the search starts from, which candidates keep every non-knob setting of
+    params: Params,
+}
+
+impl<Params: StrategyParams> From<Params> for ParameterSpace<Params> {
+    fn from(params: Params) -> Self {
+        Self {
+            knobs: params.values().into_iter().map(|value| vec![value]).collect(),
+            params,
+        }
+    }
+}
+
+/// How candidates are drawn from a `ParameterSpace`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchMethod {
+    /// Every combination of the listed values
+    Grid,
+    /// `samples` draws, each knob uniform between its smallest and largest listed value
+    /// (whole numbers only when every listed value is whole)
+    Random { samples: usize, seed: u64 },
+}
+
+impl<Params: StrategyParams> ParameterSpace<Params> {
+    /// Searches `values` for the knob named `knob`, rejecting unknown knobs and out of range values
+    pub fn with_values(mut self, knob: &str, values: Vec<Decimal>) -> Result<Self, String> {
+        let index = Params::KNOBS.iter()
+            .position(|candidate| candidate.name == knob)
+            .ok_or_else(|| format!("unknown knob '{}'", knob))?;
+        for value in &values {
+            Params::KNOBS[index].validate(*value)?;
+        }
+        if !values.is_empty() {
+            self.knobs[index] = values;
+        }
+        Ok(self)
+    }
+
+    pub fn candidates(&self, method: SearchMethod) -> Vec<Params> {
+        match method {
+            SearchMethod::Grid => {
+                let mut combinations: Vec<Vec<Decimal>> = vec![Vec::new()];
+                for values in &self.knobs {
+                    combinations = combinations.iter()
+                        .flat_map(|combination| values.iter().map(move |value| {
+                            let mut combination = combination.clone();
+                            combination.push(*value);
+                            combination
+                        }))
+                        .collect();
+                }
+                combinations.iter().map(|values| self.params.with_knob_values(values)).collect()
+            }
+            SearchMethod::Random { samples, seed } => {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                (0..samples)
+                    .map(|_| {
+                        let values: Vec<Decimal> = self.knobs.iter()
+                            .map(|values| random_value(&mut rng, values))
+                            .collect();
+                        self.params.with_knob_values(&values)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn random_value(rng: &mut ChaCha8Rng, values: &[Decimal]) -> Decimal {
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else { return Decimal::ZERO; };
+    if values.iter().all(|value| value.fract().is_zero()) {
+        let (min, max) = (min.to_i64().unwrap_or_default(), max.to_i64().unwrap_or_default());
+        return Decimal::from(rng.random_range(min..=max));
+    }
+    let fraction = Decimal::from_f64(rng.random::<f64>()).unwrap_or_default();
+    (min + (max - min) * fraction).round_dp(6)
+}
+
+/// Metric sweep results are ranked by, best first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RankBy {
+    Sharpe,
+    Pnl,
+    MaxDrawdown,
+    ProfitFactor,
+}
+
+impl FromStr for RankBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sharpe" => Ok(RankBy::Sharpe),
+            "pnl" => Ok(RankBy::Pnl),
+            "drawdown" | "max-drawdown" => Ok(RankBy::MaxDrawdown),
+            "profit-factor" | "pf" => Ok(RankBy::ProfitFactor),
+            other => Err(format!("unknown ranking '{}', expected sharpe, pnl, drawdown or profit-factor", other)),
+        }
+    }
+}
+
+/// Headline metrics of one backtest, combined across instruments
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RunMetrics {
+    /// Total PnL of every instrument
+    pub pnl: Decimal,
+    /// Mean daily Sharpe ratio of the instruments whose returns varied, barter reports
+    /// `Decimal::MAX` for a zero standard deviation
+    pub sharpe: Option<Decimal>,
+    /// Deepest PnL drawdown of any instrument, as a fraction of its peak
+    pub max_drawdown: Decimal,
+    /// Mean profit factor of the instruments that closed positions
+    pub profit_factor: Option<Decimal>,
+}
+
+impl RunMetrics {
+    pub fn from_summary(summary: &TradingSummary<Daily>) -> Self {
+        let tear_sheets: Vec<_> = summary.instruments.values().collect();
+
+        let pnl = tear_sheets.iter().fold(Decimal::ZERO, |total, sheet| total.saturating_add(sheet.pnl));
+        let sharpes: Vec<Decimal> = tear_sheets.iter()
+            .map(|sheet| sheet.sharpe_ratio.value)
+            .filter(|sharpe| *sharpe != Decimal::MAX)
+            .collect();
+        let sharpe = mean(&sharpes);
+        let max_drawdown = tear_sheets.iter()
+            .filter_map(|sheet| sheet.pnl_drawdown_max.as_ref().map(|max| max.0.value))
+            .max()
+            .unwrap_or_default();
+
+        let profit_factors: Vec<Decimal> = tear_sheets.iter()
+            .filter_map(|sheet| sheet.profit_factor.as_ref().map(|factor| factor.value))
+            .collect();
+        let profit_factor = mean(&profit_factors);
+
+        Self { pnl, sharpe, max_drawdown, profit_factor }
+    }
+}
+
+impl fmt::Display for RunMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Rounded rather than formatted with a precision, which overflows for Decimal::MAX
+        let optional = |value: Option<Decimal>, dp| value
+            .map(|value| value.round_dp(dp).to_string())
+            .unwrap_or_else(|| "N/A".to_string());
+        write!(f, "PnL: {} | Sharpe: {} | Max DD: {}% | PF: {}",
+               self.pnl.round_dp(2),
+               optional(self.sharpe, 4),
+               (self.max_drawdown * dec!(100)).round_dp(2),
+               optional(self.profit_factor, 2)
+        )
+    }
+}
+
+/// Mean of `values`, saturating so `Decimal::MAX` profit factors (no losses) don't overflow
+fn mean(values: &[Decimal]) -> Option<Decimal> {
+    if values.is_empty() {
+        return None;
+    }
+    let total = values.iter().fold(Decimal::ZERO, |total, value| total.saturating_add(*value));
+    Some(total / Decimal::from(values.len()))
+}
+
+/// A finished sweep run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepResult<Params> {
+    pub params: Params,
+    pub metrics: RunMetrics,
+}
+
+/// Sorts results best first by `rank_by`; a lower drawdown is better, everything else higher
+pub fn rank_results<Params>(results: &mut [SweepResult<Params>], rank_by: RankBy) {
+    results.sort_by(|a, b| match rank_by {
+        RankBy::Sharpe => b.metrics.sharpe.cmp(&a.metrics.sharpe),
+        RankBy::Pnl => b.metrics.pnl.cmp(&a.metrics.pnl),
+        RankBy::MaxDrawdown => a.metrics.max_drawdown.cmp(&b.metrics.max_drawdown),
+        // Runs without a Sharpe ratio or profit factor rank last
+        RankBy::ProfitFactor => b.metrics.profit_factor.cmp(&a.metrics.profit_factor),
+    });
+}
+
+/// Backtests every candidate, up to `parallelism` at a time, and returns the results ranked
+///
+/// `backtest` runs one candidate; the engine of each run is spawned onto the runtime, so
+/// concurrent runs spread across its worker threads. Failed runs are logged and left out.
+pub async fn run_sweep<Params, Backtest, Run>(
+    candidates: Vec<Params>,
+    parallelism: usize,
+    rank_by: RankBy,
+    backtest: Backtest,
+) -> Vec<SweepResult<Params>>
+where
+    Params: StrategyParams,
+    Backtest: Fn(Params) -> Run,
+    Run: Future<Output = Result<TradingSummary<Daily>, Box<dyn std::error::Error>>>,
+{
+    let total = candidates.len();
+    let backtest = &backtest;
+
+    let mut runs = futures::stream::iter(candidates)
+        .map(|params| async move { (params, backtest(params).await) })
+        .buffer_unordered(parallelism.max(1));
+
+    let mut results = Vec::with_capacity(total);
+    let mut finished = 0;
+    while let Some((params, result)) = runs.next().await {
+        finished += 1;
+        match result {
+            Ok(summary) => {
+                let metrics = RunMetrics::from_summary(&summary);
+                println!("[{}] 🔬 SWEEP [{}/{}]: {} | {}",
+                         Local::now().format("%d-%m-%y %H:%M:%S"),
+                         finished,
+                         total,
+                         params,
+                         metrics
+                );
+                results.push(SweepResult { params, metrics });
+            }
+            Err(error) => {
+                println!("[{}] ❌ SWEEP [{}/{}]: {} | failed: {}",
+                         Local::now().format("%d-%m-%y %H:%M:%S"),
+                         finished,
+                         total,
+                         params,
+                         error
+                );
+            }
+        }
+    }
+
+    rank_results(&mut results, rank_by);
+    results
+}
+
+const METRIC_COLUMNS: [&str; 4] = ["pnl", "sharpe", "max_drawdown", "profit_factor"];
+
+/// JSON number of a decimal, `null` when it doesn't fit an f64
+pub fn json_number(value: Decimal) -> serde_json::Value {
+    value.to_f64().map(serde_json::Value::from).unwrap_or_default()
+}
+
+/// Metric columns of a result row, for CSV and JSON output
+pub fn metric_values(metrics: &RunMetrics) -> [Option<Decimal>; 4] {
+    [Some(metrics.pnl), metrics.sharpe, Some(metrics.max_drawdown), metrics.profit_factor]
+}
+
+/// Writes ranked results as CSV
+pub fn write_results_csv<Params: StrategyParams>(writer: impl Write, results: &[SweepResult<Params>]) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let header = std::iter::once("rank").chain(Params::KNOBS.iter().map(|knob| knob.name)).chain(METRIC_COLUMNS);
+    writer.write_record(header)?;
+    for (rank, result) in results.iter().enumerate() {
+        let params = result.params.values().into_iter().map(|value| value.to_string());
+        let metrics = metric_values(&result.metrics)
+            .into_iter()
+            .map(|value| value.map(|value| value.to_string()).unwrap_or_default());
+        writer.write_record(std::iter::once((rank + 1).to_string()).chain(params).chain(metrics))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Parameters and metrics of a result as a JSON object, numbers as JSON numbers
+pub fn result_json<Params: StrategyParams>(params: &Params, metrics: &RunMetrics) -> serde_json::Map<String, serde_json::Value> {
+    let params = Params::KNOBS.iter().zip(params.values()).map(|(knob, value)| (knob.name.to_string(), json_number(value)));
+    let metrics = METRIC_COLUMNS.iter()
+        .zip(metric_values(metrics))
+        .map(|(column, value)| (column.to_string(), value.map(json_number).unwrap_or_default()));
+    params.chain(metrics).collect()
+}
+
+/// Writes ranked results as a JSON array of objects
+pub fn write_results_json<Params: StrategyParams>(writer: impl Write, results: &[SweepResult<Params>]) -> serde_json::Result<()> {
+    let rows: Vec<serde_json::Value> = results.iter()
+        .enumerate()
+        .map(|(rank, result)| {
+            let mut row = serde_json::Map::new();
+            row.insert("rank".to_string(), serde_json::Value::from(rank + 1));
+            row.extend(result_json(&result.params, &result.metrics));
+            serde_json::Value::Object(row)
+        })
+        .collect();
+    serde_json::to_writer_pretty(writer, &rows)
+}
+
+/// Writes ranked results to `path`, as JSON for `.json` files and CSV otherwise
+pub fn write_results<Params: StrategyParams>(path: impl AsRef<Path>, results: &[SweepResult<Params>]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    if path.extension().is_some_and(|extension| extension == "json") {
+        write_results_json(writer, results)?;
+    } else {
+        write_results_csv(writer, results)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(pnl: Decimal, sharpe: Option<Decimal>, max_drawdown: Decimal, profit_factor: Option<Decimal>) -> SweepResult<GridParams> {
+        SweepResult {
+            params: GridParams::default(),
+            metrics: RunMetrics { pnl, sharpe, max_drawdown, profit_factor },
+        }
+    }
+
+    #[test]
+    fn test_grid_and_random_candidates() {
+        let space = ParameterSpace::from(GridParams::default())
+            .with_values("band_percentage", vec![dec!(0.03), dec!(0.05)]).unwrap()
+            .with_values("tma_period", vec![dec!(10), dec!(20)]).unwrap()
+            .with_values("grid_spacing_percentage", vec![dec!(0.005), dec!(0.01), dec!(0.02)]).unwrap();
+
+        let grid = space.candidates(SearchMethod::Grid);
+        assert_eq!(grid.len(), 12);
+        assert!(grid.iter().all(|params| params.risk_percentage == dec!(0.005) && params.max_grid_levels == 15));
+
+        let random = space.candidates(SearchMethod::Random { samples: 50, seed: 9 });
+        assert_eq!(random.len(), 50);
+        assert_eq!(random, space.candidates(SearchMethod::Random { samples: 50, seed: 9 }));
+        assert!(random.iter().all(|params| {
+            (dec!(0.03)..=dec!(0.05)).contains(&params.band_percentage)
+                && (10..=20).contains(&params.tma_period)
+                && (dec!(0.005)..=dec!(0.02)).contains(&params.grid_spacing_percentage)
+        }));
+    }
+
+    #[test]
+    fn test_knob_validation() {
+        let space = || ParameterSpace::from(GridParams::default());
+        assert!(space().with_values("grid_levels", vec![dec!(10)]).is_err());
+        assert!(space().with_values("tma_period", vec![dec!(-5)]).is_err());
+        assert!(space().with_values("tma_period", vec![dec!(10.5)]).is_err());
+        assert!(space().with_values("band_percentage", vec![dec!(2)]).is_err());
+
+        let levels = space().with_values("max_grid_levels", vec![dec!(100)]).unwrap().candidates(SearchMethod::Grid);
+        assert_eq!(levels[0].max_grid_levels, 100);
+    }
+
+    #[test]
+    fn test_candidates_keep_settings_that_are_not_knobs() {
+        let combination: SignalCombination = "vote:1,0.5,1".parse().unwrap();
+        let candidates = ParameterSpace::from(VwapParams { combination, ..VwapParams::default() })
+            .with_values("vwap_deadband", vec![dec!(0.001), dec!(0.002)]).unwrap()
+            .candidates(SearchMethod::Grid);
+
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.iter().all(|params| params.combination == combination));
+        assert!(candidates[0].to_string().ends_with("| daily | vote:1,0.5,1"));
+    }
+
+    #[test]
+    fn test_rank_results() {
+        let mut results = vec![
+            result(dec!(10), Some(dec!(0.5)), dec!(0.2), None),
+            result(dec!(30), None, dec!(0.05), Some(dec!(1.2))),
+            result(dec!(20), Some(dec!(0.9)), dec!(0.1), Some(dec!(2.5))),
+        ];
+
+        rank_results(&mut results, RankBy::Sharpe);
+        assert_eq!(results.iter().map(|result| result.metrics.pnl).collect::<Vec<_>>(), vec![dec!(20), dec!(10), dec!(30)]);
+        rank_results(&mut results, RankBy::MaxDrawdown);
+        assert_eq!(results.iter().map(|result| result.metrics.pnl).collect::<Vec<_>>(), vec![dec!(30), dec!(20), dec!(10)]);
+        rank_results(&mut results, RankBy::ProfitFactor);
+        assert_eq!(results.iter().map(|result| result.metrics.pnl).collect::<Vec<_>>(), vec![dec!(20), dec!(30), dec!(10)]);
+
+        let mut csv = Vec::new();
+        write_results_csv(&mut csv, &results).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().next().unwrap().starts_with("rank,band_percentage,tma_period"));
+        assert!(csv.lines().nth(3).unwrap().ends_with(",10,0.5,0.2,"));
+    }
+}
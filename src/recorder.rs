@@ -0,0 +1,215 @@
+use barter_data::event::{DataKind, MarketEvent};
+use barter_data::streams::consumer::MarketStreamEvent;
+use barter_data::streams::reconnect::Event;
+use barter_instrument::exchange::ExchangeId;
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::InstrumentIndex;
+use chrono::{Local, NaiveDate};
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use crate::historical::write_market_event;
+
+/// How often buffered events are flushed to disk
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+struct RecordingFile {
+    date: NaiveDate,
+    writer: BufWriter<File>,
+}
+
+/// Records live market events to disk in the newline-delimited format the backtester streams
+///
+/// Each instrument gets one file per UTC day (by exchange time), named
+/// `{instrument}_{yyyy-mm-dd}.jsonl`, so a recording directory can be passed straight to
+/// `backtest`. Every line is a `MarketStreamResult`, trades and L2 updates alike. A reconnection
+/// is written ahead of the next event of every instrument on the exchange, in whichever file
+/// that event lands in, so gaps in the data are explicit.
+#[derive(Debug)]
+pub struct MarketRecorder {
+    dir: PathBuf,
+    instruments: HashMap<InstrumentIndex, (ExchangeId, String)>,
+    files: HashMap<InstrumentIndex, RecordingFile>,
+    /// Instruments whose exchange reconnected since their last recorded event
+    pending_gaps: HashSet<InstrumentIndex>,
+    last_flush: Instant,
+}
+
+impl MarketRecorder {
+    pub fn new(dir: impl AsRef<Path>, instruments: &IndexedInstruments) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        let instruments = instruments.instruments()
+            .iter()
+            .map(|instrument| (
+                instrument.key,
+                (instrument.value.exchange.value, instrument.value.name_exchange.name().to_lowercase()),
+            ))
+            .collect();
+
+        Ok(Self {
+            dir,
+            instruments,
+            files: HashMap::new(),
+            pending_gaps: HashSet::new(),
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Records an event, logging rather than failing on write errors so trading carries on
+    pub fn record(&mut self, event: &MarketStreamEvent<InstrumentIndex, DataKind>) {
+        let result = match event {
+            Event::Item(market_event) => self.record_market_event(market_event, event),
+            Event::Reconnecting(exchange) => {
+                self.record_reconnection(*exchange);
+                Ok(())
+            }
+        };
+        if let Err(error) = result {
+            warn!(%error, dir = %self.dir.display(), "failed to record market event");
+        }
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            if let Err(error) = self.flush() {
+                warn!(%error, dir = %self.dir.display(), "failed to flush market recording");
+            }
+            self.last_flush = Instant::now();
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        for file in self.files.values_mut() {
+            file.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn record_market_event(
+        &mut self,
+        market_event: &MarketEvent<InstrumentIndex, DataKind>,
+        event: &MarketStreamEvent<InstrumentIndex, DataKind>,
+    ) -> io::Result<()> {
+        let date = market_event.time_exchange.date_naive();
+        let gap = self.pending_gaps.remove(&market_event.instrument);
+        let file = self.file(market_event.instrument, date)?;
+        if gap {
+            write_market_event(&mut file.writer, &Event::Reconnecting(market_event.exchange))?;
+        }
+        write_market_event(&mut file.writer, event)
+    }
+
+    /// Marks a gap for every instrument on the exchange, written before each one's next event
+    fn record_reconnection(&mut self, exchange: ExchangeId) {
+        println!("[{}] 🔌 RECORDING GAP: {} reconnecting",
+                 Local::now().format("%d-%m-%y %H:%M:%S"),
+                 exchange
+        );
+
+        let instruments = self.instruments.iter()
+            .filter(|(_, (instrument_exchange, _))| *instrument_exchange == exchange)
+            .map(|(instrument, _)| *instrument);
+        self.pending_gaps.extend(instruments);
+    }
+
+    /// Recording file of an instrument for `date`, rotating to a new file when the day changes
+    fn file(&mut self, instrument: InstrumentIndex, date: NaiveDate) -> io::Result<&mut RecordingFile> {
+        let rotate = self.files.get(&instrument).is_none_or(|file| file.date != date);
+        if rotate {
+            if let Some(mut previous) = self.files.remove(&instrument) {
+                previous.writer.flush()?;
+            }
+
+            let name = self.instruments.get(&instrument)
+                .map(|(_, name)| name.clone())
+                .unwrap_or_else(|| format!("instrument_{}", instrument.index()));
+            let path = self.dir.join(format!("{}_{}.jsonl", name, date.format("%Y-%m-%d")));
+
+            // Append so a restart on the same day continues the existing file
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            println!("[{}] 💾 RECORDING: {}",
+                     Local::now().format("%d-%m-%y %H:%M:%S"),
+                     path.display()
+            );
+            self.files.insert(instrument, RecordingFile { date, writer: BufWriter::new(file) });
+        }
+
+        Ok(self.files.get_mut(&instrument).expect("recording file was just opened"))
+    }
+}
+
+impl Drop for MarketRecorder {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::historical::stream_market_events;
+    use crate::test_util::btc_eth_instruments;
+    use barter_data::books::OrderBook;
+    use barter_data::subscription::book::OrderBookEvent;
+    use barter_data::subscription::trade::PublicTrade;
+    use barter_instrument::Side;
+    use chrono::{DateTime, Utc};
+    use rust_decimal_macros::dec;
+
+    fn event(instrument: usize, time: &str, kind: DataKind) -> MarketStreamEvent<InstrumentIndex, DataKind> {
+        let time = DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+        Event::Item(MarketEvent {
+            time_exchange: time,
+            time_received: time,
+            exchange: ExchangeId::BinanceSpot,
+            instrument: InstrumentIndex(instrument),
+            kind,
+        })
+    }
+
+    fn trade(price: f64) -> DataKind {
+        DataKind::Trade(PublicTrade { id: "1".to_string(), price, amount: 1.0, side: Side::Buy })
+    }
+
+    #[test]
+    fn test_records_rotating_files_readable_by_backtest() {
+        let dir = std::env::temp_dir().join(format!("market_recorder_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let instruments = btc_eth_instruments();
+
+        let book = OrderBook::new(1, None, vec![(dec!(99), dec!(1))], vec![(dec!(101), dec!(1))]);
+        let mut recorder = MarketRecorder::new(&dir, &instruments).unwrap();
+        for event in [
+            event(0, "2024-12-20T23:59:58Z", trade(100.0)),
+            event(1, "2024-12-20T23:59:59Z", DataKind::OrderBook(OrderBookEvent::Snapshot(book))),
+            Event::Reconnecting(ExchangeId::BinanceSpot),
+            event(0, "2024-12-21T00:00:01Z", trade(101.0)),
+            event(1, "2024-12-21T00:00:02Z", trade(3000.0)),
+        ] {
+            recorder.record(&event);
+        }
+        drop(recorder);
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["btcusdt_2024-12-20.jsonl", "btcusdt_2024-12-21.jsonl", "ethusdt_2024-12-20.jsonl", "ethusdt_2024-12-21.jsonl"]);
+
+        // Both instruments mark the gap ahead of their first event in the new day's file,
+        // and everything reads back in time order
+        let kinds: Vec<String> = stream_market_events(&[&dir]).unwrap()
+            .map(|event| match event {
+                Event::Item(event) => event.kind.kind_name().to_string(),
+                Event::Reconnecting(_) => "reconnect".to_string(),
+            })
+            .collect();
+        assert_eq!(kinds, vec!["public_trade", "l2", "reconnect", "public_trade", "reconnect", "public_trade"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
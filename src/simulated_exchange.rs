@@ -0,0 +1,1134 @@
+use barter::execution::request::ExecutionRequest;
+use barter_data::books::{Level, OrderBook, OrderBookSide};
+use barter_data::event::{DataKind, MarketEvent};
+use barter_data::subscription::book::OrderBookEvent;
+use barter_execution::balance::{AssetBalance, Balance};
+use barter_execution::client::mock::MockExecutionConfig;
+use barter_execution::error::{ApiError, OrderError};
+use barter_execution::map::generate_execution_instrument_map;
+use barter_execution::order::id::OrderId;
+use barter_execution::order::request::{OrderRequestCancel, OrderRequestOpen, OrderResponseCancel, RequestOpen};
+use barter_execution::order::state::{Cancelled, Open, OrderState};
+use barter_execution::order::{Order, OrderKind, TimeInForce};
+use barter_execution::trade::{AssetFees, Trade, TradeId};
+use barter_execution::{AccountEvent, AccountEventKind, AccountSnapshot};
+use barter_instrument::asset::AssetIndex;
+use barter_instrument::exchange::{ExchangeId, ExchangeIndex};
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::InstrumentIndex;
+use barter_instrument::Side;
+use barter_integration::snapshot::Snapshot;
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::tca::{Liquidity, TradeCost};
+use crate::util::{parse_window_length, standard_normal};
+
+/// One-way network latency between the engine and the simulated exchange
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyModel {
+    Fixed(Duration),
+    /// Uniformly distributed between `min` and `max`
+    Uniform { min: Duration, max: Duration },
+    /// Log-normal around `median`, `sigma` being the deviation of the log (fat right tail)
+    LogNormal { median: Duration, sigma: f64 },
+}
+
+impl LatencyModel {
+    fn sample(&self, rng: &mut ChaCha8Rng) -> TimeDelta {
+        let latency = match *self {
+            LatencyModel::Fixed(latency) => latency,
+            LatencyModel::Uniform { min, max } if max > min => {
+                Duration::try_from_secs_f64(rng.random_range(min.as_secs_f64()..max.as_secs_f64())).unwrap_or(max)
+            }
+            LatencyModel::Uniform { min, .. } => min,
+            // The tail can overflow a Duration, such a request never arrives
+            LatencyModel::LogNormal { median, sigma } => {
+                Duration::try_from_secs_f64(median.as_secs_f64() * (sigma * standard_normal(rng)).exp()).unwrap_or(Duration::MAX)
+            }
+        };
+        TimeDelta::from_std(latency).unwrap_or(TimeDelta::MAX)
+    }
+}
+
+impl FromStr for LatencyModel {
+    type Err = String;
+
+    /// Parses milliseconds: `100` or `fixed:100`, `uniform:50-150` and `lognormal:80,0.5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let millis = |value: &str| {
+            value.trim().parse::<f64>()
+                .ok()
+                .and_then(|millis| Duration::try_from_secs_f64(millis / 1_000.0).ok())
+                .ok_or_else(|| format!("invalid latency '{}', expected milliseconds", value))
+        };
+
+        let (kind, params) = s.split_once(':').unwrap_or(("fixed", s));
+        match kind.to_lowercase().as_str() {
+            "fixed" => Ok(LatencyModel::Fixed(millis(params)?)),
+            "uniform" => {
+                let (min, max) = params.split_once('-')
+                    .ok_or_else(|| format!("invalid uniform latency '{}', expected min-max", params))?;
+                let (min, max) = (millis(min)?, millis(max)?);
+                if max < min {
+                    return Err(format!("invalid uniform latency '{}', min is above max", params));
+                }
+                Ok(LatencyModel::Uniform { min, max })
+            }
+            "lognormal" => {
+                let (median, sigma) = params.split_once(',')
+                    .ok_or_else(|| format!("invalid lognormal latency '{}', expected median,sigma", params))?;
+                let sigma = sigma.trim().parse::<f64>()
+                    .ok()
+                    .filter(|sigma| sigma.is_finite() && *sigma >= 0.0)
+                    .ok_or_else(|| format!("invalid lognormal sigma '{}'", sigma))?;
+                Ok(LatencyModel::LogNormal { median: millis(median)?, sigma })
+            }
+            other => Err(format!("unknown latency model '{}', expected fixed, uniform or lognormal", other)),
+        }
+    }
+}
+
+/// Price a marketable order trades at when it takes liquidity
+///
+/// Models other than `Book` fill the whole order at one price, moved against it from the touch
+/// (the best opposite level, or the last print without a book). A limit order priced short of
+/// that fills nothing and is cancelled like immediate-or-cancel: resting, it would still cross
+/// the book and fill at its own price as the maker.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SlippageModel {
+    /// Walk the visible depth level by level
+    #[default]
+    Book,
+    /// Fixed basis points beyond the touch
+    FixedBps(Decimal),
+    /// A fraction of the quoted spread beyond the touch
+    Spread(Decimal),
+    /// `coefficient * sqrt(quantity / volume)` beyond the touch, `volume` being what printed over the trailing window
+    SquareRoot { coefficient: Decimal, window: TimeDelta },
+}
+
+impl FromStr for SlippageModel {
+    type Err = String;
+
+    /// Parses `book`, `fixed:5` (bps), `spread:0.5` (of the spread) and `sqrt:0.1` or `sqrt:0.1,1h` (volume window)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let decimal = |value: &str| {
+            value.trim().parse::<Decimal>()
+                .ok()
+                .filter(|value| *value >= Decimal::ZERO)
+                .ok_or_else(|| format!("invalid slippage parameter '{}'", value))
+        };
+
+        let (kind, params) = s.split_once(':').unwrap_or((s, ""));
+        match kind.to_lowercase().as_str() {
+            "book" => Ok(SlippageModel::Book),
+            "fixed" => Ok(SlippageModel::FixedBps(decimal(params)?)),
+            "spread" => Ok(SlippageModel::Spread(decimal(params)?)),
+            "sqrt" => {
+                let (coefficient, window) = params.split_once(',').unwrap_or((params, "1h"));
+                Ok(SlippageModel::SquareRoot { coefficient: decimal(coefficient)?, window: parse_window_length(window)? })
+            }
+            other => Err(format!("unknown slippage model '{}', expected book, fixed, spread or sqrt", other)),
+        }
+    }
+}
+
+/// How a simulated exchange fills backtest orders
+///
+/// Latency and fees left unset come from each exchange's mock execution config, reading its
+/// `fees_percent` as a percentage for both makers and takers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillSimulation {
+    latency: Option<LatencyModel>,
+    fees: Option<(Decimal, Decimal)>,
+    slippage: SlippageModel,
+    seed: u64,
+}
+
+impl FillSimulation {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            latency: None,
+            fees: None,
+            slippage: SlippageModel::default(),
+            seed,
+        }
+    }
+
+    pub fn with_latency(mut self, latency: LatencyModel) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Maker and taker fees as fractions of notional (0.001 = 0.1%)
+    pub fn with_fees(mut self, maker: Decimal, taker: Decimal) -> Self {
+        self.fees = Some((maker, taker));
+        self
+    }
+
+    pub fn with_slippage(mut self, slippage: SlippageModel) -> Self {
+        self.slippage = slippage;
+        self
+    }
+}
+
+/// Running totals of what a simulated exchange did
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FillStats {
+    pub orders: usize,
+    pub rejected: usize,
+    pub maker_fills: usize,
+    pub taker_fills: usize,
+    /// Fills that left part of their order working
+    pub partial_fills: usize,
+    pub cancelled: usize,
+    pub expired: usize,
+    pub fees: Decimal,
+}
+
+impl fmt::Display for FillStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} orders | {} maker fills | {} taker fills | {} partial | {} rejected | {} cancelled | {} expired | fees {}",
+               self.orders,
+               self.maker_fills,
+               self.taker_fills,
+               self.partial_fills,
+               self.rejected,
+               self.cancelled,
+               self.expired,
+               self.fees.round_dp(4)
+        )
+    }
+}
+
+/// An order accepted by the exchange, working in the book until it fills
+#[derive(Debug, Clone)]
+struct RestingOrder {
+    request: OrderRequestOpen,
+    id: OrderId,
+    filled: Decimal,
+    /// Displayed quantity ahead of the order at its price level
+    queue_ahead: Decimal,
+    /// When the engine sent it
+    time_signal: DateTime<Utc>,
+    time_placed: DateTime<Utc>,
+    /// Mid (or last print) when it reached the exchange
+    arrival_price: Decimal,
+}
+
+impl RestingOrder {
+    fn remaining(&self) -> Decimal {
+        self.request.state.quantity - self.filled
+    }
+}
+
+#[derive(Debug)]
+enum Pending {
+    /// An engine request reaching the exchange, with when it was sent
+    Arrive(ExecutionRequest, DateTime<Utc>),
+    /// An exchange notification reaching the engine
+    Deliver(AccountEvent),
+}
+
+/// Exchange that fills backtest orders against recorded L2 depth and trade prints, in event time
+///
+/// Limit orders join the back of the queue at their price level and only fill once trades print
+/// through it: prints beyond the price fill what they traded after the depth ahead, prints at
+/// the price first work through the queue ahead, so small prints fill partially. Cancels showing
+/// up in the L2 shrink the queue, and a book crossing the price fills no more than the depth
+/// crossing it.
+/// Marketable orders take liquidity as the taker, priced by the slippage model: by default they
+/// walk the visible book, market orders filling anything beyond it at the worst visible level.
+/// Every fill is recorded against its signal and arrival price for transaction cost analysis.
+/// Requests and notifications each travel with a sampled latency, kept in order like a single
+/// connection.
+#[derive(Debug)]
+pub struct SimulatedExchange {
+    exchange: ExchangeIndex,
+    exchange_id: ExchangeId,
+    latency: LatencyModel,
+    maker_fee: Decimal,
+    taker_fee: Decimal,
+    slippage: SlippageModel,
+    rng: ChaCha8Rng,
+    /// Base and quote asset of each instrument traded here
+    assets: HashMap<InstrumentIndex, (AssetIndex, AssetIndex)>,
+    balances: BTreeMap<AssetIndex, Balance>,
+    books: HashMap<InstrumentIndex, OrderBook>,
+    last_prices: HashMap<InstrumentIndex, Decimal>,
+    /// Recent print times and amounts, kept for the square-root impact window
+    volumes: HashMap<InstrumentIndex, VecDeque<(DateTime<Utc>, Decimal)>>,
+    resting: Vec<RestingOrder>,
+    pending: BTreeMap<(DateTime<Utc>, u64), Pending>,
+    last_arrival: DateTime<Utc>,
+    last_delivery: DateTime<Utc>,
+    time: DateTime<Utc>,
+    sequence: u64,
+    stats: FillStats,
+    costs: Vec<TradeCost>,
+}
+
+impl SimulatedExchange {
+    pub fn new(
+        instruments: &IndexedInstruments,
+        config: &MockExecutionConfig,
+        fills: &FillSimulation,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let exchange = instruments.find_exchange_index(config.mocked_exchange)?;
+        let map = generate_execution_instrument_map(instruments, config.mocked_exchange)?;
+        let balances = config.initial_state.balances.iter()
+            .map(|balance| Ok((map.find_asset_index(&balance.asset)?, balance.balance)))
+            .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+        let assets = instruments.instruments().iter()
+            .filter(|instrument| instrument.value.exchange.key == exchange)
+            .map(|instrument| (instrument.key, (instrument.value.underlying.base, instrument.value.underlying.quote)))
+            .collect();
+
+        let fee = config.fees_percent / Decimal::ONE_HUNDRED;
+        let (maker_fee, taker_fee) = fills.fees.unwrap_or((fee, fee));
+
+        Ok(Self {
+            exchange,
+            exchange_id: config.mocked_exchange,
+            latency: fills.latency.unwrap_or(LatencyModel::Fixed(Duration::from_millis(config.latency_ms))),
+            maker_fee,
+            taker_fee,
+            slippage: fills.slippage,
+            rng: ChaCha8Rng::seed_from_u64(fills.seed.wrapping_add(exchange.index() as u64)),
+            assets,
+            balances,
+            books: HashMap::new(),
+            last_prices: HashMap::new(),
+            volumes: HashMap::new(),
+            resting: Vec::new(),
+            pending: BTreeMap::new(),
+            last_arrival: DateTime::<Utc>::MIN_UTC,
+            last_delivery: DateTime::<Utc>::MIN_UTC,
+            time: DateTime::<Utc>::MIN_UTC,
+            sequence: 0,
+            stats: FillStats::default(),
+            costs: Vec::new(),
+        })
+    }
+
+    pub fn exchange_id(&self) -> ExchangeId {
+        self.exchange_id
+    }
+
+    pub fn stats(&self) -> &FillStats {
+        &self.stats
+    }
+
+    /// Costs of every fill so far, in fill order
+    pub fn costs(&self) -> &[TradeCost] {
+        &self.costs
+    }
+
+    /// Starting balances, as the account snapshot an exchange sends on connect
+    pub fn account_snapshot(&self, time: DateTime<Utc>) -> AccountEvent {
+        AccountEvent::new(self.exchange, AccountSnapshot {
+            exchange: self.exchange,
+            balances: self.balances.iter()
+                .map(|(asset, balance)| AssetBalance::new(*asset, *balance, time))
+                .collect(),
+            instruments: Vec::new(),
+        })
+    }
+
+    /// Sends an engine request at `time`, reaching the exchange after the sampled latency
+    pub fn submit(&mut self, request: ExecutionRequest, time: DateTime<Utc>) {
+        if let ExecutionRequest::Shutdown = request {
+            return;
+        }
+        let arrival = after_latency(time, self.latency.sample(&mut self.rng)).max(self.last_arrival);
+        self.last_arrival = arrival;
+        self.schedule(arrival, Pending::Arrive(request, time));
+    }
+
+    /// Next notification reaching the engine by `until`, handling any requests reaching the exchange before it
+    pub fn next_due(&mut self, until: DateTime<Utc>) -> Option<(DateTime<Utc>, AccountEvent)> {
+        while let Some(entry) = self.pending.first_entry() {
+            let (time, _) = *entry.key();
+            if time > until {
+                return None;
+            }
+            match entry.remove() {
+                Pending::Arrive(request, time_signal) => {
+                    self.time = self.time.max(time);
+                    let notifications = match request {
+                        ExecutionRequest::Open(request) => self.open(request, time_signal),
+                        ExecutionRequest::Cancel(request) => self.cancel(request),
+                        ExecutionRequest::Shutdown => Vec::new(),
+                    };
+                    self.respond(notifications);
+                }
+                Pending::Deliver(event) => return Some((time, event)),
+            }
+        }
+        None
+    }
+
+    /// Applies a market event, filling resting orders the prints or book move through
+    ///
+    /// Call `next_due` up to the event's time first so requests already at the exchange are in the book.
+    pub fn on_market(&mut self, event: &MarketEvent<InstrumentIndex, DataKind>) {
+        if !self.assets.contains_key(&event.instrument) {
+            return;
+        }
+        self.time = self.time.max(event.time_exchange);
+        let mut notifications = self.expire_day_orders();
+
+        match &event.kind {
+            DataKind::Trade(trade) => {
+                let (Ok(price), Ok(amount)) = (Decimal::try_from(trade.price), Decimal::try_from(trade.amount)) else {
+                    return self.respond(notifications);
+                };
+                self.last_prices.insert(event.instrument, price);
+                self.record_volume(event.instrument, amount);
+                notifications.extend(self.fill_from_print(event.instrument, price, amount, trade.side));
+            }
+            DataKind::OrderBook(book_event) => {
+                self.books.entry(event.instrument).or_default().update(book_event);
+                notifications.extend(self.fill_from_book(event.instrument));
+            }
+            DataKind::OrderBookL1(l1) => {
+                let book = OrderBook::new(0, None, l1.best_bid, l1.best_ask);
+                self.books.insert(event.instrument, book);
+                notifications.extend(self.fill_from_book(event.instrument));
+            }
+            DataKind::Candle(candle) => {
+                if let Ok(close) = Decimal::try_from(candle.close) {
+                    self.last_prices.insert(event.instrument, close);
+                }
+            }
+            DataKind::Liquidation(_) => {}
+        }
+
+        self.respond(notifications);
+    }
+
+    fn schedule(&mut self, time: DateTime<Utc>, pending: Pending) {
+        self.sequence += 1;
+        self.pending.insert((time, self.sequence), pending);
+    }
+
+    /// Sends notifications back to the engine together, after the sampled latency
+    fn respond(&mut self, notifications: Vec<AccountEvent>) {
+        if notifications.is_empty() {
+            return;
+        }
+        let delivery = after_latency(self.time, self.latency.sample(&mut self.rng)).max(self.last_delivery);
+        self.last_delivery = delivery;
+        for notification in notifications {
+            self.schedule(delivery, Pending::Deliver(notification));
+        }
+    }
+
+    fn next_id(&mut self) -> OrderId {
+        self.sequence += 1;
+        OrderId::new(self.sequence.to_string())
+    }
+
+    fn open(&mut self, request: OrderRequestOpen, time_signal: DateTime<Utc>) -> Vec<AccountEvent> {
+        self.stats.orders += 1;
+        let Some(&(base, quote)) = self.assets.get(&request.key.instrument) else {
+            let error = ApiError::InstrumentInvalid(request.key.instrument, "instrument not traded on this exchange".to_string());
+            return self.reject(request, error);
+        };
+
+        let RequestOpen { side, price, quantity, kind, time_in_force } = request.state.clone();
+        let quantity = quantity.abs();
+        let limit = (kind == OrderKind::Limit).then_some(price);
+        let marketable = match limit {
+            None => true,
+            Some(limit) => self.best_opposite(request.key.instrument, side).is_some_and(|best| crosses(side, limit, best)),
+        };
+
+        if marketable && matches!(time_in_force, TimeInForce::GoodUntilCancelled { post_only: true }) {
+            return self.reject(request, ApiError::OrderRejected("post-only order would take liquidity".to_string()));
+        }
+
+        let takes = if marketable { self.taker_fills(request.key.instrument, side, quantity, limit) } else { Vec::new() };
+        let taken: Decimal = takes.iter().map(|level| level.amount).sum();
+        if kind == OrderKind::Market && taken.is_zero() {
+            return self.reject(request, ApiError::OrderRejected("no market price to fill against".to_string()));
+        }
+        if time_in_force == TimeInForce::FillOrKill && taken < quantity {
+            return self.reject(request, ApiError::OrderRejected("fill or kill order could not be filled in full".to_string()));
+        }
+
+        let modelled_short = marketable && !matches!(self.slippage, SlippageModel::Book) && taken < quantity;
+        let rests = kind == OrderKind::Limit
+            && taken < quantity
+            && !modelled_short
+            && !matches!(time_in_force, TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill);
+        let resting = if rests { quantity - taken } else { Decimal::ZERO };
+
+        // Spot account: buys need the quote, sells need the base
+        let (asset, required) = match side {
+            Side::Buy => {
+                let taker_cost: Decimal = takes.iter().map(|level| level.price * level.amount * (Decimal::ONE + self.taker_fee)).sum();
+                (quote, taker_cost + resting * price * (Decimal::ONE + self.maker_fee))
+            }
+            Side::Sell => (base, taken + resting),
+        };
+        let available = self.balances.get(&asset).map(|balance| balance.free).unwrap_or_default();
+        if required > available {
+            let error = ApiError::BalanceInsufficient(asset, format!("Available Balance: {}, Required Balance inc. fees: {}", available, required));
+            return self.reject(request, error);
+        }
+
+        let mut order = RestingOrder {
+            id: self.next_id(),
+            filled: Decimal::ZERO,
+            queue_ahead: Decimal::ZERO,
+            time_signal,
+            time_placed: self.time,
+            arrival_price: self.arrival_price(request.key.instrument).unwrap_or(price),
+            request,
+        };
+        self.consume_liquidity(order.request.key.instrument, side, taken);
+        let mut notifications = Vec::new();
+        for level in &takes {
+            order.filled += level.amount;
+            notifications.extend(self.fill(&order, level.price, level.amount, false));
+        }
+        let filled = order.filled;
+        if !takes.is_empty() && (rests || filled < quantity) {
+            self.stats.partial_fills += 1;
+        }
+
+        let (request, id) = (order.request.clone(), order.id.clone());
+        let state = if rests {
+            self.reserve(base, quote, side, price, resting);
+            order.queue_ahead = self.displayed(request.key.instrument, side, price) + self.own_depth(request.key.instrument, side, price);
+            self.resting.push(order);
+            OrderState::active(Open::new(id, self.time, filled))
+        } else if filled < quantity {
+            // Immediate-or-cancel remainder
+            self.stats.cancelled += 1;
+            OrderState::inactive(Cancelled::new(id, self.time))
+        } else {
+            OrderState::fully_filled()
+        };
+
+        // The order update goes first so the engine never sees fills for an order it doesn't know
+        notifications.insert(0, self.order_snapshot(&request, state));
+        notifications
+    }
+
+    fn cancel(&mut self, request: OrderRequestCancel) -> Vec<AccountEvent> {
+        let Some(index) = self.resting.iter().position(|order| order.request.key.cid == request.key.cid) else {
+            return vec![AccountEvent::new(self.exchange, AccountEventKind::OrderCancelled(OrderResponseCancel {
+                key: request.key,
+                state: Err(OrderError::Rejected(ApiError::OrderRejected("order is not open".to_string()))),
+            }))];
+        };
+
+        self.stats.cancelled += 1;
+        let order = self.resting.remove(index);
+        let mut notifications = vec![AccountEvent::new(self.exchange, AccountEventKind::OrderCancelled(OrderResponseCancel {
+            key: request.key,
+            state: Ok(Cancelled::new(order.id.clone(), self.time)),
+        }))];
+        notifications.extend(self.release(&order));
+        notifications
+    }
+
+    /// Expires good-until-end-of-day orders once the UTC day they were placed on is over
+    fn expire_day_orders(&mut self) -> Vec<AccountEvent> {
+        let today = self.time.date_naive();
+        let (expired, working): (Vec<_>, Vec<_>) = std::mem::take(&mut self.resting).into_iter()
+            .partition(|order| order.request.state.time_in_force == TimeInForce::GoodUntilEndOfDay && order.time_placed.date_naive() < today);
+        self.resting = working;
+
+        let mut notifications = Vec::new();
+        for order in expired {
+            self.stats.expired += 1;
+            notifications.push(self.order_snapshot(&order.request, OrderState::expired()));
+            notifications.extend(self.release(&order));
+        }
+        notifications
+    }
+
+    /// Works a print through the resting orders on the passive side of it
+    fn fill_from_print(&mut self, instrument: InstrumentIndex, price: Decimal, amount: Decimal, aggressor: Side) -> Vec<AccountEvent> {
+        let book = self.books.get(&instrument);
+        let mut fills = Vec::new();
+        for (index, order) in self.resting.iter_mut().enumerate() {
+            if order.request.key.instrument != instrument {
+                continue;
+            }
+            let side = order.request.state.side;
+            let limit = order.request.state.price;
+            let through = match side {
+                Side::Buy => price < limit,
+                Side::Sell => price > limit,
+            };
+            let quantity = if through {
+                // Displayed depth at better prices and the queue at ours trade before us, then the level clears
+                let better = book.map(|book| match side {
+                    Side::Buy => depth_better(book.bids().levels(), side, limit),
+                    Side::Sell => depth_better(book.asks().levels(), side, limit),
+                });
+                let left_over = amount - better.unwrap_or_default() - order.queue_ahead;
+                order.queue_ahead = Decimal::ZERO;
+                left_over.max(Decimal::ZERO).min(order.remaining())
+            } else if price == limit && aggressor != side {
+                // Each order's queue already counts the orders of ours ahead of it
+                let left_over = amount - order.queue_ahead;
+                order.queue_ahead = (order.queue_ahead - amount).max(Decimal::ZERO);
+                left_over.max(Decimal::ZERO).min(order.remaining())
+            } else {
+                Decimal::ZERO
+            };
+            if quantity > Decimal::ZERO {
+                fills.push((index, quantity));
+            }
+        }
+        self.fill_resting(fills)
+    }
+
+    /// Shrinks queues to the displayed depth and fills orders the book has crossed, up to the
+    /// depth crossing them
+    fn fill_from_book(&mut self, instrument: InstrumentIndex) -> Vec<AccountEvent> {
+        let mut fills = Vec::new();
+        for index in 0..self.resting.len() {
+            let Some(book) = self.books.get(&instrument) else {
+                break;
+            };
+            let order = &mut self.resting[index];
+            if order.request.key.instrument != instrument {
+                continue;
+            }
+            let side = order.request.state.side;
+            let limit = order.request.state.price;
+            let (own_side, opposite) = match side {
+                Side::Buy => (book.bids().levels(), book.asks().levels()),
+                Side::Sell => (book.asks().levels(), book.bids().levels()),
+            };
+
+            // Each crossing order uses up the depth it fills against, the rest keeps resting
+            let crossing: Decimal = opposite.iter()
+                .filter(|level| crosses(side, limit, level.price))
+                .map(|level| level.amount)
+                .sum();
+            if crossing > Decimal::ZERO {
+                let quantity = crossing.min(order.remaining());
+                fills.push((index, quantity));
+                self.consume_liquidity(instrument, side, quantity);
+                continue;
+            }
+
+            // Depth can only leave ahead of us (cancels), anything added joins behind
+            match own_side.iter().find(|level| level.price == limit) {
+                Some(level) => order.queue_ahead = order.queue_ahead.min(level.amount),
+                None if within(own_side, limit) => order.queue_ahead = Decimal::ZERO,
+                None => {}
+            }
+        }
+        self.fill_resting(fills)
+    }
+
+    /// Fills resting orders as the maker at their own price, removing those that complete
+    fn fill_resting(&mut self, fills: Vec<(usize, Decimal)>) -> Vec<AccountEvent> {
+        let mut notifications = Vec::new();
+        for (index, quantity) in fills.iter().rev() {
+            let mut order = self.resting[*index].clone();
+            order.filled += *quantity;
+
+            let mut order_notifications = Vec::new();
+            let state = if order.remaining() > Decimal::ZERO {
+                self.stats.partial_fills += 1;
+                self.resting[*index] = order.clone();
+                OrderState::active(Open::new(order.id.clone(), self.time, order.filled))
+            } else {
+                self.resting.remove(*index);
+                OrderState::fully_filled()
+            };
+            order_notifications.push(self.order_snapshot(&order.request, state));
+            order_notifications.extend(self.fill(&order, order.request.state.price, *quantity, true));
+            notifications.push(order_notifications);
+        }
+        // Fills were worked back to front so removals kept the indices valid
+        notifications.into_iter().rev().flatten().collect()
+    }
+
+    /// Books a fill and its transaction costs, returning the trade and the balances it moved
+    fn fill(&mut self, order: &RestingOrder, price: Decimal, quantity: Decimal, maker: bool) -> Vec<AccountEvent> {
+        let request = &order.request;
+        let Some(&(base, quote)) = self.assets.get(&request.key.instrument) else {
+            return Vec::new();
+        };
+        let notional = price * quantity;
+        let fee = notional * if maker { self.maker_fee } else { self.taker_fee };
+        if maker {
+            self.stats.maker_fills += 1;
+        } else {
+            self.stats.taker_fills += 1;
+        }
+        self.stats.fees += fee;
+
+        // Resting orders already had their side reserved out of the free balance
+        let side = request.state.side;
+        let (paid, paid_amount, received, received_amount) = match side {
+            Side::Buy => (quote, notional + fee, base, quantity),
+            Side::Sell => (base, quantity, quote, notional - fee),
+        };
+        if let Some(balance) = self.balances.get_mut(&paid) {
+            balance.total -= paid_amount;
+            if !maker {
+                balance.free -= paid_amount;
+            }
+        }
+        let balance = self.balances.entry(received).or_default();
+        balance.total += received_amount;
+        balance.free += received_amount;
+
+        self.sequence += 1;
+        let trade = Trade {
+            id: TradeId::new(self.sequence.to_string()),
+            order_id: order.id.clone(),
+            instrument: request.key.instrument,
+            strategy: request.key.strategy.clone(),
+            time_exchange: self.time,
+            side,
+            price,
+            quantity,
+            fees: AssetFees::quote_fees(fee),
+        };
+        self.costs.push(TradeCost {
+            instrument: request.key.instrument,
+            side,
+            liquidity: if maker { Liquidity::Maker } else { Liquidity::Taker },
+            quantity,
+            signal_price: request.state.price,
+            arrival_price: order.arrival_price,
+            fill_price: price,
+            fees: fee,
+            time_signal: order.time_signal,
+            time_fill: self.time,
+        });
+        vec![
+            AccountEvent::new(self.exchange, AccountEventKind::Trade(trade)),
+            self.balance_snapshot(base),
+            self.balance_snapshot(quote),
+        ]
+    }
+
+    fn reserve(&mut self, base: AssetIndex, quote: AssetIndex, side: Side, price: Decimal, quantity: Decimal) {
+        let (asset, amount) = match side {
+            Side::Buy => (quote, quantity * price * (Decimal::ONE + self.maker_fee)),
+            Side::Sell => (base, quantity),
+        };
+        if let Some(balance) = self.balances.get_mut(&asset) {
+            balance.free -= amount;
+        }
+    }
+
+    /// Returns what a resting order still had reserved to the free balance
+    fn release(&mut self, order: &RestingOrder) -> Vec<AccountEvent> {
+        let Some(&(base, quote)) = self.assets.get(&order.request.key.instrument) else {
+            return Vec::new();
+        };
+        let (asset, amount) = match order.request.state.side {
+            Side::Buy => (quote, order.remaining() * order.request.state.price * (Decimal::ONE + self.maker_fee)),
+            Side::Sell => (base, order.remaining()),
+        };
+        if let Some(balance) = self.balances.get_mut(&asset) {
+            balance.free += amount;
+        }
+        vec![self.balance_snapshot(asset)]
+    }
+
+    /// Prices and amounts a taker order fills at, nothing beyond `limit` for limit orders
+    fn taker_fills(&self, instrument: InstrumentIndex, side: Side, quantity: Decimal, limit: Option<Decimal>) -> Vec<Level> {
+        let slippage = match self.slippage {
+            SlippageModel::Book => return self.book_fills(instrument, side, quantity, limit),
+            SlippageModel::FixedBps(bps) => bps / dec!(10000),
+            SlippageModel::Spread(fraction) => {
+                // Relative to the mid, nothing without a two-sided book
+                let spread = self.books.get(&instrument).and_then(|book| match (book.bids().best(), book.asks().best()) {
+                    (Some(bid), Some(ask)) if bid.price > Decimal::ZERO => Some((ask.price - bid.price).max(Decimal::ZERO) * Decimal::TWO / (bid.price + ask.price)),
+                    _ => None,
+                });
+                fraction * spread.unwrap_or_default()
+            }
+            SlippageModel::SquareRoot { coefficient, window } => {
+                // Prints only age out as new ones arrive, so older ones may still be queued
+                let volume: Decimal = self.volumes.get(&instrument)
+                    .map(|prints| prints.iter().filter(|(time, _)| *time >= self.time - window).map(|(_, amount)| *amount).sum())
+                    .unwrap_or_default();
+                // Without recent volume the order is taken to be the whole of it
+                let participation = if volume > Decimal::ZERO { quantity / volume } else { Decimal::ONE };
+                coefficient * participation.sqrt().unwrap_or_default()
+            }
+        };
+        self.touch_fill(instrument, side, quantity, limit, slippage)
+    }
+
+    /// Whole quantity at the touch moved `slippage` (a fraction of it) against the order, or
+    /// nothing if that is beyond `limit`
+    fn touch_fill(&self, instrument: InstrumentIndex, side: Side, quantity: Decimal, limit: Option<Decimal>, slippage: Decimal) -> Vec<Level> {
+        let Some(touch) = self.best_opposite(instrument, side).or_else(|| self.last_prices.get(&instrument).copied()) else {
+            return Vec::new();
+        };
+        let price = match side {
+            Side::Buy => touch * (Decimal::ONE + slippage),
+            Side::Sell => (touch * (Decimal::ONE - slippage)).max(Decimal::ZERO),
+        };
+        if limit.is_some_and(|limit| !crosses(side, limit, price)) {
+            return Vec::new();
+        }
+        vec![Level::new(price, quantity)]
+    }
+
+    /// Visible levels a taker order fills against, up to `limit` for limit orders
+    fn book_fills(&self, instrument: InstrumentIndex, side: Side, quantity: Decimal, limit: Option<Decimal>) -> Vec<Level> {
+        let levels = self.books.get(&instrument).map(|book| match side {
+            Side::Buy => book.asks().levels(),
+            Side::Sell => book.bids().levels(),
+        }).unwrap_or_default();
+
+        let mut fills = Vec::new();
+        let mut remaining = quantity;
+        for level in levels.iter().take_while(|level| limit.is_none_or(|limit| crosses(side, limit, level.price))) {
+            if remaining.is_zero() {
+                break;
+            }
+            let amount = remaining.min(level.amount);
+            fills.push(Level::new(level.price, amount));
+            remaining -= amount;
+        }
+
+        // Market orders outlast the visible depth at its worst level, or trade at the last print without a book
+        if limit.is_none() && remaining > Decimal::ZERO {
+            let price = levels.last().map(|level| level.price).or_else(|| self.last_prices.get(&instrument).copied());
+            if let Some(price) = price {
+                fills.push(Level::new(price, remaining));
+            }
+        }
+        fills
+    }
+
+    /// Removes `taken` from the opposite side of the local book, best level first, until the next
+    /// update replaces it. Modelled fills priced away from the book still use up its depth.
+    fn consume_liquidity(&mut self, instrument: InstrumentIndex, side: Side, taken: Decimal) {
+        let Some(book) = self.books.get_mut(&instrument) else {
+            return;
+        };
+        let levels = match side {
+            Side::Buy => book.asks().levels(),
+            Side::Sell => book.bids().levels(),
+        };
+
+        let mut remaining = taken;
+        let mut left = Vec::new();
+        for level in levels {
+            if remaining.is_zero() {
+                break;
+            }
+            let amount = remaining.min(level.amount);
+            left.push(Level::new(level.price, level.amount - amount));
+            remaining -= amount;
+        }
+        let (bids, asks) = match side {
+            Side::Buy => (Vec::new(), left),
+            Side::Sell => (left, Vec::new()),
+        };
+        book.update(&OrderBookEvent::Update(OrderBook::new(book.sequence(), book.time_engine(), bids, asks)));
+    }
+
+    /// Keeps the prints inside the square-root impact window
+    fn record_volume(&mut self, instrument: InstrumentIndex, amount: Decimal) {
+        let SlippageModel::SquareRoot { window, .. } = self.slippage else {
+            return;
+        };
+        let prints = self.volumes.entry(instrument).or_default();
+        prints.push_back((self.time, amount));
+        while prints.front().is_some_and(|(time, _)| *time < self.time - window) {
+            prints.pop_front();
+        }
+    }
+
+    /// Mid of the book, or the last print without one
+    fn arrival_price(&self, instrument: InstrumentIndex) -> Option<Decimal> {
+        let mid = self.books.get(&instrument).and_then(|book| match (book.bids().best(), book.asks().best()) {
+            (Some(bid), Some(ask)) => Some((bid.price + ask.price) / Decimal::TWO),
+            _ => None,
+        });
+        mid.or_else(|| self.last_prices.get(&instrument).copied())
+    }
+
+    fn best_opposite(&self, instrument: InstrumentIndex, side: Side) -> Option<Decimal> {
+        let book = self.books.get(&instrument)?;
+        match side {
+            Side::Buy => book.asks().best(),
+            Side::Sell => book.bids().best(),
+        }
+        .map(|level| level.price)
+    }
+
+    /// Displayed depth on `side` of the book at `price`
+    fn displayed(&self, instrument: InstrumentIndex, side: Side, price: Decimal) -> Decimal {
+        let Some(book) = self.books.get(&instrument) else {
+            return Decimal::ZERO;
+        };
+        match side {
+            Side::Buy => level_amount(book.bids(), price),
+            Side::Sell => level_amount(book.asks(), price),
+        }
+    }
+
+    /// Our own resting quantity at `price`, already ahead of any new order there
+    fn own_depth(&self, instrument: InstrumentIndex, side: Side, price: Decimal) -> Decimal {
+        self.resting.iter()
+            .filter(|order| order.request.key.instrument == instrument && order.request.state.side == side && order.request.state.price == price)
+            .map(RestingOrder::remaining)
+            .sum()
+    }
+
+    fn reject(&mut self, request: OrderRequestOpen, error: ApiError) -> Vec<AccountEvent> {
+        self.stats.rejected += 1;
+        vec![self.order_snapshot(&request, OrderState::inactive(OrderError::Rejected(error)))]
+    }
+
+    fn order_snapshot(&self, request: &OrderRequestOpen, state: OrderState) -> AccountEvent {
+        AccountEvent::new(self.exchange, AccountEventKind::OrderSnapshot(Snapshot(Order {
+            key: request.key.clone(),
+            side: request.state.side,
+            price: request.state.price,
+            quantity: request.state.quantity,
+            kind: request.state.kind,
+            time_in_force: request.state.time_in_force,
+            state,
+        })))
+    }
+
+    fn balance_snapshot(&self, asset: AssetIndex) -> AccountEvent {
+        let balance = self.balances.get(&asset).copied().unwrap_or_default();
+        AccountEvent::new(self.exchange, AccountEventKind::BalanceSnapshot(Snapshot(AssetBalance::new(asset, balance, self.time))))
+    }
+}
+
+/// When something sent at `time` lands, saturating rather than overflowing for huge latencies
+fn after_latency(time: DateTime<Utc>, latency: TimeDelta) -> DateTime<Utc> {
+    time.checked_add_signed(latency).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Whether an order at `limit` on `side` trades against an opposite price
+fn crosses(side: Side, limit: Decimal, opposite: Decimal) -> bool {
+    match side {
+        Side::Buy => opposite <= limit,
+        Side::Sell => opposite >= limit,
+    }
+}
+
+fn level_amount<BookSide: fmt::Display + fmt::Debug>(book_side: &OrderBookSide<BookSide>, price: Decimal) -> Decimal {
+    book_side.levels().iter()
+        .find(|level| level.price == price)
+        .map(|level| level.amount)
+        .unwrap_or_default()
+}
+
+/// Displayed amount on one side of the book priced better than `limit` for an order on `side`
+fn depth_better(levels: &[Level], side: Side, limit: Decimal) -> Decimal {
+    levels.iter()
+        .filter(|level| match side {
+            Side::Buy => level.price > limit,
+            Side::Sell => level.price < limit,
+        })
+        .map(|level| level.amount)
+        .sum()
+}
+
+/// Whether `price` lies between the best and worst visible levels, where a missing level means no depth
+fn within(levels: &[Level], price: Decimal) -> bool {
+    match (levels.first(), levels.last()) {
+        (Some(best), Some(worst)) => price >= best.price.min(worst.price) && price <= best.price.max(worst.price),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter::system::config::{ExecutionConfig, SystemConfig};
+    use barter_data::subscription::trade::PublicTrade;
+    use barter_execution::order::id::{ClientOrderId, StrategyId};
+    use barter_execution::order::OrderKey;
+    use rust_decimal_macros::dec;
+
+    fn exchange(fills: FillSimulation) -> (SimulatedExchange, InstrumentIndex) {
+        let json = r#"{
+            "instruments": [
+                { "exchange": "binance_spot", "name_exchange": "BTCUSDT", "underlying": { "base": "btc", "quote": "usdt" }, "quote": "underlying_quote", "kind": "spot" }
+            ],
+            "executions": [
+                { "mocked_exchange": "binance_spot", "latency_ms": 100, "fees_percent": 0.1,
+                  "initial_state": { "exchange": "binance_spot", "balances": [
+                      { "asset": "usdt", "balance": { "total": 10000, "free": 10000 }, "time_exchange": "2025-03-24T21:30:00Z" },
+                      { "asset": "btc", "balance": { "total": 0, "free": 0 }, "time_exchange": "2025-03-24T21:30:00Z" }
+                  ], "instruments": [] } }
+            ]
+        }"#;
+        let SystemConfig { instruments, executions } = serde_json::from_str(json).unwrap();
+        let instruments = IndexedInstruments::new(instruments);
+        let ExecutionConfig::Mock(config) = &executions[0];
+        (SimulatedExchange::new(&instruments, config, &fills).unwrap(), InstrumentIndex(0))
+    }
+
+    fn time(millis: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp_millis(1_742_860_800_000 + millis).unwrap()
+    }
+
+    fn market(instrument: InstrumentIndex, millis: i64, kind: DataKind) -> MarketEvent<InstrumentIndex, DataKind> {
+        MarketEvent { time_exchange: time(millis), time_received: time(millis), exchange: ExchangeId::BinanceSpot, instrument, kind }
+    }
+
+    fn book(instrument: InstrumentIndex, millis: i64, bids: Vec<(Decimal, Decimal)>, asks: Vec<(Decimal, Decimal)>) -> MarketEvent<InstrumentIndex, DataKind> {
+        market(instrument, millis, DataKind::OrderBook(OrderBookEvent::Snapshot(OrderBook::new(0, None, bids, asks))))
+    }
+
+    fn print(instrument: InstrumentIndex, millis: i64, price: f64, amount: f64, side: Side) -> MarketEvent<InstrumentIndex, DataKind> {
+        market(instrument, millis, DataKind::Trade(PublicTrade { id: millis.to_string(), price, amount, side }))
+    }
+
+    fn open(instrument: InstrumentIndex, side: Side, kind: OrderKind, price: Decimal, quantity: Decimal) -> ExecutionRequest {
+        ExecutionRequest::Open(OrderRequestOpen {
+            key: OrderKey { exchange: ExchangeIndex(0), instrument, strategy: StrategyId::new("test"), cid: ClientOrderId::new("1") },
+            state: RequestOpen::new(side, price, quantity, kind, TimeInForce::GoodUntilCancelled { post_only: false }),
+        })
+    }
+
+    fn trades(exchange: &mut SimulatedExchange, until: i64) -> Vec<(Decimal, Decimal)> {
+        std::iter::from_fn(|| exchange.next_due(time(until)))
+            .filter_map(|(_, event)| match event.kind {
+                AccountEventKind::Trade(trade) => Some((trade.price, trade.quantity)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_latency_model() {
+        assert_eq!("100".parse(), Ok(LatencyModel::Fixed(Duration::from_millis(100))));
+        assert_eq!("uniform:50-150".parse(), Ok(LatencyModel::Uniform { min: Duration::from_millis(50), max: Duration::from_millis(150) }));
+        assert_eq!("lognormal:80,0.5".parse(), Ok(LatencyModel::LogNormal { median: Duration::from_millis(80), sigma: 0.5 }));
+        assert!("gaussian:80".parse::<LatencyModel>().is_err());
+        assert!("inf".parse::<LatencyModel>().is_err());
+        assert!("-5".parse::<LatencyModel>().is_err());
+        assert!("uniform:150-50".parse::<LatencyModel>().is_err());
+        assert!("lognormal:80,NaN".parse::<LatencyModel>().is_err());
+
+        // A tail too long for a Duration saturates instead of panicking
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let extreme = LatencyModel::LogNormal { median: Duration::from_secs(1), sigma: 1e6 };
+        for _ in 0..10 {
+            assert!(after_latency(time(0), extreme.sample(&mut rng)) >= time(0));
+        }
+    }
+
+    #[test]
+    fn test_limit_order_waits_for_queue_ahead() {
+        let (mut exchange, btc) = exchange(FillSimulation::new(1));
+        exchange.on_market(&book(btc, 0, vec![(dec!(100), dec!(3))], vec![(dec!(101), dec!(5))]));
+
+        // Arrives 100ms later behind the 3 already bid at 100
+        exchange.submit(open(btc, Side::Buy, OrderKind::Limit, dec!(100), dec!(2)), time(0));
+        assert!(trades(&mut exchange, 150).is_empty());
+
+        // Prints at our price work through the queue first, then fill us partially
+        exchange.on_market(&print(btc, 200, 100.0, 2.0, Side::Sell));
+        exchange.on_market(&print(btc, 300, 100.0, 2.0, Side::Sell));
+        // Buyers lifting the ask don't reach a resting bid
+        exchange.on_market(&print(btc, 400, 100.0, 5.0, Side::Buy));
+        assert_eq!(trades(&mut exchange, 500), vec![(dec!(100), dec!(1))]);
+
+        // A print through the level fills no more than it traded
+        exchange.on_market(&print(btc, 600, 99.5, 0.1, Side::Sell));
+        assert_eq!(trades(&mut exchange, 800), vec![(dec!(100), dec!(0.1))]);
+        exchange.on_market(&print(btc, 900, 99.5, 5.0, Side::Sell));
+        assert_eq!(trades(&mut exchange, 1100), vec![(dec!(100), dec!(0.9))]);
+        assert_eq!(exchange.stats().maker_fills, 3);
+        assert_eq!(exchange.stats().fees, dec!(0.2));
+    }
+
+    #[test]
+    fn test_crossed_limit_order_fills_only_the_crossing_depth() {
+        let (mut exchange, btc) = exchange(FillSimulation::new(1));
+        exchange.on_market(&book(btc, 0, vec![(dec!(99), dec!(1))], vec![(dec!(101), dec!(5))]));
+        exchange.submit(open(btc, Side::Buy, OrderKind::Limit, dec!(100), dec!(2)), time(0));
+        assert!(trades(&mut exchange, 150).is_empty());
+
+        // Only 0.5 offered at or below our bid, the rest of the order keeps resting
+        exchange.on_market(&book(btc, 200, vec![(dec!(99), dec!(1))], vec![(dec!(99.5), dec!(0.2)), (dec!(100), dec!(0.3)), (dec!(100.5), dec!(5))]));
+        assert_eq!(trades(&mut exchange, 400), vec![(dec!(100), dec!(0.5))]);
+        assert_eq!(exchange.resting[0].remaining(), dec!(1.5));
+        assert_eq!(exchange.best_opposite(btc, Side::Buy), Some(dec!(100.5)));
+    }
+
+    #[test]
+    fn test_market_order_walks_the_book_as_taker() {
+        let fills = FillSimulation::new(1)
+            .with_latency(LatencyModel::Fixed(Duration::from_millis(10)))
+            .with_fees(dec!(0.0002), dec!(0.001));
+        let (mut exchange, btc) = exchange(fills);
+        exchange.on_market(&book(btc, 0, vec![(dec!(99), dec!(1))], vec![(dec!(100), dec!(1)), (dec!(101), dec!(1))]));
+
+        exchange.submit(open(btc, Side::Buy, OrderKind::Market, dec!(100), dec!(3)), time(0));
+        assert_eq!(trades(&mut exchange, 100), vec![(dec!(100), dec!(1)), (dec!(101), dec!(1)), (dec!(101), dec!(1))]);
+        assert_eq!(exchange.stats().taker_fills, 3);
+        assert_eq!(exchange.stats().fees, dec!(0.302));
+
+        // Selling more than the account holds is rejected
+        exchange.submit(open(btc, Side::Sell, OrderKind::Market, dec!(99), dec!(5)), time(100));
+        assert!(trades(&mut exchange, 200).is_empty());
+        assert_eq!(exchange.stats().rejected, 1);
+    }
+
+    #[test]
+    fn test_slippage_models_price_taker_fills() {
+        assert_eq!("fixed:5".parse(), Ok(SlippageModel::FixedBps(dec!(5))));
+        assert_eq!("sqrt:0.1,30m".parse(), Ok(SlippageModel::SquareRoot { coefficient: dec!(0.1), window: TimeDelta::minutes(30) }));
+        assert!("spread:-1".parse::<SlippageModel>().is_err());
+
+        let fills = FillSimulation::new(1)
+            .with_latency(LatencyModel::Fixed(Duration::from_millis(10)))
+            .with_slippage("sqrt:0.1".parse().unwrap());
+        let (mut exchange, btc) = exchange(fills);
+        exchange.on_market(&book(btc, 0, vec![(dec!(99), dec!(1))], vec![(dec!(101), dec!(1))]));
+        exchange.on_market(&print(btc, 0, 101.0, 3.0, Side::Buy));
+        exchange.on_market(&print(btc, 0, 99.0, 1.0, Side::Sell));
+
+        // 1 against 4 traded: 0.1 * sqrt(0.25) = 5% over the ask, in one fill beyond the visible depth
+        exchange.submit(open(btc, Side::Buy, OrderKind::Market, dec!(98), dec!(1)), time(0));
+        assert_eq!(trades(&mut exchange, 100), vec![(dec!(106.05), dec!(1))]);
+
+        // The fill used up the ask even though it was priced beyond it
+        assert_eq!(exchange.best_opposite(btc, Side::Buy), None);
+
+        // Limit orders never fill beyond their price: 5% over 101 is past 102, so it's cancelled
+        // rather than left resting to fill at 102 as the maker on the next book
+        exchange.on_market(&book(btc, 100, vec![(dec!(99), dec!(1))], vec![(dec!(101), dec!(1))]));
+        exchange.submit(open(btc, Side::Buy, OrderKind::Limit, dec!(102), dec!(1)), time(100));
+        assert_eq!(trades(&mut exchange, 200), vec![]);
+        exchange.on_market(&book(btc, 200, vec![(dec!(99), dec!(1))], vec![(dec!(101), dec!(1))]));
+        assert_eq!(trades(&mut exchange, 300), vec![]);
+        assert!(exchange.resting.is_empty());
+        assert_eq!(exchange.stats().cancelled, 1);
+
+        let cost = &exchange.costs()[0];
+        assert_eq!((cost.signal_price, cost.arrival_price, cost.fill_price), (dec!(98), dec!(100), dec!(106.05)));
+        assert_eq!(cost.liquidity, Liquidity::Taker);
+        assert_eq!(cost.time_signal, time(0));
+        assert_eq!(cost.slippage_bps(), dec!(605));
+    }
+}
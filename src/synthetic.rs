@@ -0,0 +1,483 @@
+use barter_data::books::OrderBook;
+use barter_data::event::{DataKind, MarketEvent};
+use barter_data::streams::consumer::MarketStreamEvent;
+use barter_data::streams::reconnect::Event;
+use barter_data::subscription::book::OrderBookEvent;
+use barter_data::subscription::trade::PublicTrade;
+use barter_instrument::Side;
+use barter_instrument::exchange::ExchangeId;
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::InstrumentIndex;
+use chrono::{DateTime, Utc};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::time::Duration;
+use crate::util::standard_normal;
+
+/// Price process of a synthetic market, parameters are per step
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriceModel {
+    /// Geometric Brownian motion: log returns with constant drift and volatility
+    Gbm { drift: f64, volatility: f64 },
+    /// Ornstein-Uhlenbeck on log price, pulled back towards `mean` at rate `reversion`
+    OrnsteinUhlenbeck { mean: f64, reversion: f64, volatility: f64 },
+    /// GBM whose drift and volatility jump between regimes, leaving the current one with
+    /// probability `switch_probability` each step
+    RegimeSwitching { regimes: Vec<(f64, f64)>, switch_probability: f64 },
+}
+
+impl PriceModel {
+    /// Named scenario with per-second parameters: `gbm`, `ou` (mean-reverting around `price`)
+    /// or `regime` (calm, trending and volatile regimes)
+    pub fn preset(name: &str, price: f64) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "gbm" => Ok(PriceModel::Gbm { drift: 0.0, volatility: 0.0005 }),
+            "ou" => Ok(PriceModel::OrnsteinUhlenbeck { mean: price, reversion: 0.01, volatility: 0.0005 }),
+            "regime" => Ok(PriceModel::RegimeSwitching {
+                regimes: vec![(0.0, 0.0002), (0.00005, 0.0005), (-0.0001, 0.0015)],
+                switch_probability: 0.001,
+            }),
+            other => Err(format!("unknown synthetic model '{}', expected gbm, ou or regime", other)),
+        }
+    }
+}
+
+/// Sudden drop and linear recovery
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlashCrash {
+    pub probability: f64,
+    /// Fraction of price lost at the bottom (0.1 = 10%)
+    pub depth: f64,
+    pub recovery_steps: usize,
+}
+
+/// Price jumps with a pause in the data before them, like a halt or a feed outage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gaps {
+    pub probability: f64,
+    /// Largest jump as a fraction of price, in either direction
+    pub max_jump: f64,
+    pub pause: Duration,
+}
+
+/// GARCH(1,1)-style clustering: large moves raise the volatility, and traded volume, of the steps after them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clustering {
+    /// Weight of the last squared shock
+    pub reaction: f64,
+    /// Weight of the last variance
+    pub persistence: f64,
+}
+
+/// Seeded generator of trade and L2 streams for every configured instrument
+///
+/// Each instrument follows its own path from the scenario seed, on the ChaCha stream numbered
+/// by its index, so the same seed always produces the same events and no two instruments
+/// share a random sequence.
+#[derive(Debug, Clone)]
+pub struct SyntheticMarket {
+    model: PriceModel,
+    seed: u64,
+    start_price: f64,
+    start_prices: HashMap<String, f64>,
+    interval: Duration,
+    trade_amount: f64,
+    flash_crash: Option<FlashCrash>,
+    gaps: Option<Gaps>,
+    clustering: Option<Clustering>,
+    book_levels: usize,
+    spread: f64,
+}
+
+impl SyntheticMarket {
+    pub fn new(model: PriceModel, seed: u64) -> Self {
+        Self {
+            model,
+            seed,
+            start_price: 100.0,
+            start_prices: HashMap::new(),
+            interval: Duration::from_secs(1),
+            trade_amount: 1.0,
+            flash_crash: None,
+            gaps: None,
+            clustering: None,
+            book_levels: 10,
+            spread: 0.0002, // 2 bps
+        }
+    }
+
+    /// Starting price of instruments without their own (100 by default)
+    pub fn with_start_price(mut self, price: f64) -> Self {
+        self.start_price = price;
+        self
+    }
+
+    /// Starting price of one instrument by exchange name (eg/ "BTCUSDT")
+    pub fn with_instrument_price(mut self, instrument: &str, price: f64) -> Self {
+        self.start_prices.insert(instrument.to_lowercase(), price);
+        self
+    }
+
+    /// Time between steps (1s by default)
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Average size of the trade printed each step
+    pub fn with_trade_amount(mut self, amount: f64) -> Self {
+        self.trade_amount = amount;
+        self
+    }
+
+    pub fn with_flash_crashes(mut self, flash_crash: FlashCrash) -> Self {
+        self.flash_crash = Some(flash_crash);
+        self
+    }
+
+    pub fn with_gaps(mut self, gaps: Gaps) -> Self {
+        self.gaps = Some(gaps);
+        self
+    }
+
+    pub fn with_volatility_clustering(mut self, clustering: Clustering) -> Self {
+        self.clustering = Some(clustering);
+        self
+    }
+
+    /// Depth of the L2 snapshot published each step (10 by default), no book with 0 levels
+    pub fn with_book_levels(mut self, levels: usize) -> Self {
+        self.book_levels = levels;
+        self
+    }
+
+    /// Relative spread of the L2 snapshot published each step (2 bps by default)
+    pub fn with_spread(mut self, spread: f64) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// Lazily generated events for `steps` steps from `start`, each step printing one trade and
+    /// one L2 snapshot per instrument
+    pub fn events(&self, instruments: &IndexedInstruments, start: DateTime<Utc>, steps: usize) -> SyntheticEvents {
+        let mut paths: Vec<PricePath> = instruments.instruments()
+            .iter()
+            .map(|instrument| {
+                let name = instrument.value.name_exchange.name().to_lowercase();
+                let price = self.start_prices.get(&name).copied().unwrap_or(self.start_price);
+                PricePath::new(
+                    self.clone(),
+                    instrument.key,
+                    instrument.value.exchange.value,
+                    price,
+                    self.seed,
+                )
+            })
+            .collect();
+
+        let mut queue = BinaryHeap::new();
+        let mut upcoming = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter_mut().enumerate() {
+            let next = (steps > 0).then(|| path.advance(start));
+            if let Some((time, _)) = next {
+                queue.push(Reverse((time, index)));
+            }
+            upcoming.push(next.map(|(_, events)| events).unwrap_or_default());
+        }
+
+        SyntheticEvents { paths, start, steps, queue, upcoming, pending: VecDeque::new() }
+    }
+}
+
+/// State of one instrument's synthetic price path
+#[derive(Debug, Clone)]
+struct PricePath {
+    market: SyntheticMarket,
+    instrument: InstrumentIndex,
+    exchange: ExchangeId,
+    rng: ChaCha8Rng,
+    log_price: f64,
+    regime: usize,
+    variance: Option<f64>,
+    last_shock: f64,
+    crash_step: Option<usize>,
+    delay: chrono::Duration,
+    trade_id: u64,
+    step: usize,
+}
+
+impl PricePath {
+    fn new(market: SyntheticMarket, instrument: InstrumentIndex, exchange: ExchangeId, price: f64, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(instrument.index() as u64);
+        Self {
+            market,
+            instrument,
+            exchange,
+            rng,
+            log_price: price.ln(),
+            regime: 0,
+            variance: None,
+            last_shock: 0.0,
+            crash_step: None,
+            delay: chrono::Duration::zero(),
+            trade_id: 0,
+            step: 0,
+        }
+    }
+
+    /// Drift and volatility of the current step
+    fn parameters(&mut self) -> (f64, f64) {
+        match &self.market.model {
+            PriceModel::Gbm { drift, volatility } => (*drift, *volatility),
+            PriceModel::OrnsteinUhlenbeck { mean, reversion, volatility } => {
+                (reversion * (mean.ln() - self.log_price), *volatility)
+            }
+            PriceModel::RegimeSwitching { regimes, switch_probability } => {
+                if regimes.is_empty() {
+                    return (0.0, 0.0);
+                }
+                let (regimes, switch_probability) = (regimes.len(), *switch_probability);
+                if regimes > 1 && self.rng.random_bool(switch_probability.clamp(0.0, 1.0)) {
+                    self.regime = (self.regime + self.rng.random_range(1..regimes)) % regimes;
+                }
+                let PriceModel::RegimeSwitching { regimes, .. } = &self.market.model else { unreachable!() };
+                regimes[self.regime]
+            }
+        }
+    }
+
+    /// Advances one step and returns the traded price, its size, and any extra delay before it prints
+    fn step(&mut self, step: usize) -> (f64, f64, chrono::Duration) {
+        let (drift, base_volatility) = self.parameters();
+
+        // Clustered volatility scales both the shock and the traded volume
+        let volatility = match self.market.clustering {
+            Some(Clustering { reaction, persistence }) => {
+                let base_variance = base_volatility * base_volatility;
+                let previous = self.variance.unwrap_or(base_variance);
+                let variance = base_variance * (1.0 - reaction - persistence).max(0.0)
+                    + reaction * self.last_shock * self.last_shock
+                    + persistence * previous;
+                self.variance = Some(variance);
+                variance.sqrt()
+            }
+            None => base_volatility,
+        };
+
+        let shock = volatility * standard_normal(&mut self.rng);
+        self.last_shock = shock;
+        self.log_price += drift - 0.5 * volatility * volatility + shock;
+
+        let mut pause = chrono::Duration::zero();
+        if let Some(gaps) = self.market.gaps
+            && self.rng.random_bool(gaps.probability.clamp(0.0, 1.0))
+        {
+            let jump = self.rng.random_range(-gaps.max_jump..=gaps.max_jump);
+            self.log_price += (1.0 + jump).max(f64::MIN_POSITIVE).ln();
+            pause = chrono::Duration::from_std(gaps.pause).unwrap_or_default();
+        }
+
+        if let Some(crash) = self.market.flash_crash
+            && self.crash_step.is_none()
+            && self.rng.random_bool(crash.probability.clamp(0.0, 1.0))
+        {
+            self.crash_step = Some(step);
+        }
+
+        // The crash is an overlay on the underlying path, recovering linearly
+        let mut crash_factor = 1.0;
+        if let (Some(crash), Some(crash_step)) = (self.market.flash_crash, self.crash_step) {
+            let elapsed = step - crash_step;
+            if elapsed > crash.recovery_steps {
+                self.crash_step = None;
+            } else {
+                let remaining = 1.0 - elapsed as f64 / crash.recovery_steps.max(1) as f64;
+                crash_factor = 1.0 - crash.depth * remaining;
+            }
+        }
+
+        let activity = if base_volatility > 0.0 { volatility / base_volatility } else { 1.0 };
+        let amount = self.market.trade_amount * activity * (0.5 + self.rng.random::<f64>());
+
+        (self.log_price.exp() * crash_factor, amount, pause)
+    }
+
+    /// Takes the next step, returning its time and its trade and L2 snapshot
+    fn advance(&mut self, start: DateTime<Utc>) -> (DateTime<Utc>, Vec<MarketStreamEvent<InstrumentIndex, DataKind>>) {
+        let step = self.step;
+        self.step += 1;
+
+        let previous = self.log_price.exp();
+        let (price, amount, pause) = self.step(step);
+        self.delay += pause;
+        self.trade_id += 1;
+
+        let interval = chrono::Duration::from_std(self.market.interval).unwrap_or_default();
+        let time = start + interval * step as i32 + self.delay;
+        let side = if price >= previous { Side::Buy } else { Side::Sell };
+
+        let (exchange, instrument) = (self.exchange, self.instrument);
+        let event = |kind| Event::Item(MarketEvent {
+            time_exchange: time,
+            time_received: time,
+            exchange,
+            instrument,
+            kind,
+        });
+        let mut events = vec![event(DataKind::Trade(PublicTrade {
+            id: self.trade_id.to_string(),
+            price,
+            amount,
+            side,
+        }))];
+        if let Some(book) = self.book(price) {
+            events.push(event(DataKind::OrderBook(OrderBookEvent::Snapshot(book))));
+        }
+        (time, events)
+    }
+
+    fn book(&mut self, price: f64) -> Option<OrderBook> {
+        if self.market.book_levels == 0 {
+            return None;
+        }
+        let half_spread = price * self.market.spread / 2.0;
+        let tick = (price * self.market.spread).max(f64::EPSILON);
+        let mut bids = Vec::with_capacity(self.market.book_levels);
+        let mut asks = Vec::with_capacity(self.market.book_levels);
+        for level in 0..self.market.book_levels {
+            let offset = half_spread + tick * level as f64;
+            let bid_amount = self.market.trade_amount * (1.0 + self.rng.random::<f64>() * level as f64);
+            let ask_amount = self.market.trade_amount * (1.0 + self.rng.random::<f64>() * level as f64);
+            bids.push((decimal(price - offset), decimal(bid_amount)));
+            asks.push((decimal(price + offset), decimal(ask_amount)));
+        }
+        Some(OrderBook::new(self.trade_id, None, bids, asks))
+    }
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default().round_dp(8)
+}
+
+/// Iterator over a synthetic scenario's events in time order
+///
+/// Gaps pause one instrument's path but not the others, so paths are merged by the time of their
+/// next step rather than emitted step by step.
+#[derive(Debug, Clone)]
+pub struct SyntheticEvents {
+    paths: Vec<PricePath>,
+    start: DateTime<Utc>,
+    steps: usize,
+    /// Time of each path's next step, earliest first and then by path
+    queue: BinaryHeap<Reverse<(DateTime<Utc>, usize)>>,
+    /// Events of each path's next step
+    upcoming: Vec<Vec<MarketStreamEvent<InstrumentIndex, DataKind>>>,
+    pending: VecDeque<MarketStreamEvent<InstrumentIndex, DataKind>>,
+}
+
+impl Iterator for SyntheticEvents {
+    type Item = MarketStreamEvent<InstrumentIndex, DataKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let Reverse((_, index)) = self.queue.pop()?;
+            self.pending.extend(std::mem::take(&mut self.upcoming[index]));
+
+            let path = &mut self.paths[index];
+            if path.step < self.steps {
+                let (time, events) = path.advance(self.start);
+                self.queue.push(Reverse((time, index)));
+                self.upcoming[index] = events;
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::btc_eth_instruments;
+
+    fn prices(events: impl Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>, instrument: usize) -> Vec<f64> {
+        events
+            .filter_map(|event| match event {
+                Event::Item(MarketEvent { instrument: index, kind: DataKind::Trade(trade), .. }) if index.index() == instrument => Some(trade.price),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_events() {
+        let market = SyntheticMarket::new(PriceModel::Gbm { drift: 0.0, volatility: 0.01 }, 7)
+            .with_volatility_clustering(Clustering { reaction: 0.1, persistence: 0.85 });
+        let start = Utc::now();
+
+        let first: Vec<_> = market.events(&btc_eth_instruments(), start, 50).collect();
+        let second: Vec<_> = market.events(&btc_eth_instruments(), start, 50).collect();
+        // A trade and a book per instrument per step
+        assert_eq!(first.len(), 200);
+        assert_eq!(first, second);
+
+        let other_seed = SyntheticMarket::new(PriceModel::Gbm { drift: 0.0, volatility: 0.01 }, 8);
+        assert_ne!(prices(first.into_iter(), 0), prices(other_seed.events(&btc_eth_instruments(), start, 50), 0));
+
+        // Instruments draw from their own streams, so the second instrument doesn't replay
+        // the first instrument of the neighbouring seed
+        let plain = SyntheticMarket::new(PriceModel::Gbm { drift: 0.0, volatility: 0.01 }, 7);
+        assert_ne!(prices(plain.events(&btc_eth_instruments(), start, 50), 1), prices(other_seed.events(&btc_eth_instruments(), start, 50), 0));
+    }
+
+    #[test]
+    fn test_ornstein_uhlenbeck_reverts_to_mean() {
+        let market = SyntheticMarket::new(PriceModel::OrnsteinUhlenbeck { mean: 100.0, reversion: 0.1, volatility: 0.001 }, 1)
+            .with_start_price(150.0)
+            .with_book_levels(0);
+        let prices = prices(market.events(&btc_eth_instruments(), Utc::now(), 200), 0);
+        assert!((prices.last().unwrap() - 100.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_flash_crash_and_gap() {
+        let market = SyntheticMarket::new(PriceModel::Gbm { drift: 0.0, volatility: 0.0 }, 3)
+            .with_flash_crashes(FlashCrash { probability: 1.0, depth: 0.2, recovery_steps: 4 })
+            .with_book_levels(0);
+        let prices = prices(market.events(&btc_eth_instruments(), Utc::now(), 6), 0);
+        let rounded: Vec<f64> = prices.iter().map(|price| (price * 100.0).round() / 100.0).collect();
+        assert_eq!(rounded, vec![80.0, 85.0, 90.0, 95.0, 100.0, 100.0]);
+
+        let gapped = SyntheticMarket::new(PriceModel::Gbm { drift: 0.0, volatility: 0.0 }, 3)
+            .with_gaps(Gaps { probability: 1.0, max_jump: 0.05, pause: Duration::from_secs(60) })
+            .with_book_levels(0);
+        let times: Vec<DateTime<Utc>> = gapped.events(&btc_eth_instruments(), Utc::now(), 2)
+            .filter_map(|event| match event {
+                Event::Item(event) if event.instrument.index() == 0 => Some(event.time_exchange),
+                _ => None,
+            })
+            .collect();
+        // One second step plus the second gap's minute pause
+        assert_eq!((times[1] - times[0]).num_seconds(), 61);
+    }
+
+    #[test]
+    fn test_gapped_instruments_stay_in_time_order() {
+        let market = SyntheticMarket::new(PriceModel::Gbm { drift: 0.0, volatility: 0.001 }, 11)
+            .with_gaps(Gaps { probability: 0.2, max_jump: 0.05, pause: Duration::from_secs(30) });
+        let times: Vec<DateTime<Utc>> = market.events(&btc_eth_instruments(), Utc::now(), 500)
+            .filter_map(|event| match event {
+                Event::Item(event) => Some(event.time_exchange),
+                Event::Reconnecting(_) => None,
+            })
+            .collect();
+
+        // A trade and a book per instrument per step, however far the paths drift apart
+        assert_eq!(times.len(), 2000);
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -0,0 +1,214 @@
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::InstrumentIndex;
+use barter_instrument::Side;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::fmt;
+use std::io::Write;
+
+const BPS: Decimal = dec!(10000);
+
+/// Whether a fill added or took liquidity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+impl fmt::Display for Liquidity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Liquidity::Maker => "maker",
+            Liquidity::Taker => "taker",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Transaction costs of one fill
+///
+/// The signal price is what the strategy asked for (its order price), the arrival price the mid
+/// when the order reached the exchange, and the fill price what it actually traded at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeCost {
+    pub instrument: InstrumentIndex,
+    pub side: Side,
+    pub liquidity: Liquidity,
+    pub quantity: Decimal,
+    pub signal_price: Decimal,
+    pub arrival_price: Decimal,
+    pub fill_price: Decimal,
+    /// Fees in the quote asset
+    pub fees: Decimal,
+    pub time_signal: DateTime<Utc>,
+    pub time_fill: DateTime<Utc>,
+}
+
+impl TradeCost {
+    pub fn notional(&self) -> Decimal {
+        self.fill_price * self.quantity
+    }
+
+    /// Signal to arrival: what the market moved while the order was on its way
+    pub fn delay_bps(&self) -> Decimal {
+        cost_bps(self.side, self.signal_price, self.arrival_price)
+    }
+
+    /// Arrival to fill: spread, impact and queueing paid at the exchange
+    pub fn slippage_bps(&self) -> Decimal {
+        cost_bps(self.side, self.arrival_price, self.fill_price)
+    }
+
+    /// Signal to fill: the implementation shortfall, before fees
+    pub fn shortfall_bps(&self) -> Decimal {
+        cost_bps(self.side, self.signal_price, self.fill_price)
+    }
+
+    pub fn fee_bps(&self) -> Decimal {
+        let notional = self.notional();
+        if notional.is_zero() { Decimal::ZERO } else { self.fees / notional * BPS }
+    }
+}
+
+/// Move from `from` to `to` in basis points of `from`, positive when it cost the trade
+fn cost_bps(side: Side, from: Decimal, to: Decimal) -> Decimal {
+    if from.is_zero() {
+        return Decimal::ZERO;
+    }
+    let cost = match side {
+        Side::Buy => to - from,
+        Side::Sell => from - to,
+    };
+    cost / from * BPS
+}
+
+/// Notional-weighted average costs over a set of fills
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TcaSummary {
+    pub trades: usize,
+    pub notional: Decimal,
+    pub delay_bps: Decimal,
+    pub slippage_bps: Decimal,
+    pub shortfall_bps: Decimal,
+    pub fee_bps: Decimal,
+}
+
+impl TcaSummary {
+    pub fn new<'a>(costs: impl IntoIterator<Item = &'a TradeCost>) -> Self {
+        let mut summary = Self::default();
+        for cost in costs {
+            let notional = cost.notional();
+            summary.trades += 1;
+            summary.notional += notional;
+            summary.delay_bps += cost.delay_bps() * notional;
+            summary.slippage_bps += cost.slippage_bps() * notional;
+            summary.shortfall_bps += cost.shortfall_bps() * notional;
+            summary.fee_bps += cost.fee_bps() * notional;
+        }
+
+        if !summary.notional.is_zero() {
+            summary.delay_bps /= summary.notional;
+            summary.slippage_bps /= summary.notional;
+            summary.shortfall_bps /= summary.notional;
+            summary.fee_bps /= summary.notional;
+        }
+        summary
+    }
+}
+
+impl fmt::Display for TcaSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} trades | notional {} | delay {} bps | slippage {} bps | shortfall {} bps | fees {} bps",
+               self.trades,
+               self.notional.round_dp(2),
+               self.delay_bps.round_dp(2),
+               self.slippage_bps.round_dp(2),
+               self.shortfall_bps.round_dp(2),
+               self.fee_bps.round_dp(2)
+        )
+    }
+}
+
+/// Writes one row per fill with its prices and costs in basis points
+pub fn write_tca_csv(writer: impl Write, instruments: &IndexedInstruments, costs: &[TradeCost]) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record([
+        "time_signal", "time_fill", "instrument", "side", "liquidity", "quantity",
+        "signal_price", "arrival_price", "fill_price", "fees",
+        "delay_bps", "slippage_bps", "shortfall_bps", "fee_bps",
+    ])?;
+    for cost in costs {
+        let instrument = instruments.find_instrument(cost.instrument)
+            .map(|instrument| instrument.name_exchange.to_string())
+            .unwrap_or_else(|_| cost.instrument.to_string());
+        writer.write_record([
+            cost.time_signal.to_rfc3339(),
+            cost.time_fill.to_rfc3339(),
+            instrument,
+            cost.side.to_string(),
+            cost.liquidity.to_string(),
+            cost.quantity.to_string(),
+            cost.signal_price.to_string(),
+            cost.arrival_price.to_string(),
+            cost.fill_price.to_string(),
+            cost.fees.to_string(),
+            cost.delay_bps().round_dp(4).to_string(),
+            cost.slippage_bps().round_dp(4).to_string(),
+            cost.shortfall_bps().round_dp(4).to_string(),
+            cost.fee_bps().round_dp(4).to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn cost(side: Side, signal: Decimal, arrival: Decimal, fill: Decimal, quantity: Decimal) -> TradeCost {
+        TradeCost {
+            instrument: InstrumentIndex(0),
+            side,
+            liquidity: Liquidity::Taker,
+            quantity,
+            signal_price: signal,
+            arrival_price: arrival,
+            fill_price: fill,
+            fees: fill * quantity * dec!(0.001),
+            time_signal: DateTime::<Utc>::MIN_UTC,
+            time_fill: DateTime::<Utc>::MIN_UTC,
+        }
+    }
+
+    #[test]
+    fn test_costs_are_positive_against_the_trade() {
+        // Buying: the market ran up 10bps on the way and the fill paid another 5bps over the mid
+        let buy = cost(Side::Buy, dec!(100), dec!(100.1), dec!(100.15005), dec!(1));
+        assert_eq!(buy.delay_bps(), dec!(10));
+        assert_eq!(buy.slippage_bps().round_dp(6), dec!(5));
+        assert_eq!(buy.shortfall_bps().round_dp(4), dec!(15.005));
+        assert_eq!(buy.fee_bps(), dec!(10));
+
+        // Selling into a market that rose is a gain
+        let sell = cost(Side::Sell, dec!(100), dec!(100.2), dec!(100.1), dec!(1));
+        assert_eq!(sell.delay_bps(), dec!(-20));
+        assert_eq!(sell.shortfall_bps(), dec!(-10));
+    }
+
+    #[test]
+    fn test_summary_weights_by_notional() {
+        let costs = [
+            cost(Side::Buy, dec!(100), dec!(100), dec!(100.1), dec!(3)),
+            cost(Side::Sell, dec!(100), dec!(100), dec!(100), dec!(1)),
+        ];
+        let summary = TcaSummary::new(&costs);
+        assert_eq!(summary.trades, 2);
+        assert_eq!(summary.notional, dec!(400.3));
+        // 10bps on 300.3 of notional, nothing on the other 100
+        assert_eq!(summary.slippage_bps.round_dp(4), (dec!(3003) / dec!(400.3)).round_dp(4));
+        assert_eq!(summary.fee_bps.round_dp(8), dec!(10));
+    }
+}
@@ -0,0 +1,12 @@
+use barter_instrument::Underlying;
+use barter_instrument::exchange::ExchangeId;
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::Instrument;
+
+/// BTCUSDT and ETHUSDT Binance spot, indexed 0 and 1
+pub fn btc_eth_instruments() -> IndexedInstruments {
+    IndexedInstruments::new([
+        Instrument::spot(ExchangeId::BinanceSpot, "binance_spot_btc_usdt", "BTCUSDT", Underlying::new("btc", "usdt"), None),
+        Instrument::spot(ExchangeId::BinanceSpot, "binance_spot_eth_usdt", "ETHUSDT", Underlying::new("eth", "usdt"), None),
+    ])
+}
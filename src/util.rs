@@ -0,0 +1,55 @@
+use chrono::TimeDelta;
+use rand::Rng;
+
+/// Standard normal sample (Box-Muller)
+pub fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = rng.random::<f64>().max(f64::MIN_POSITIVE);
+    let u2: f64 = rng.random();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Parses a window length such as `30d`, `12h`, `45m` or `90s`
+pub fn parse_window_length(length: &str) -> Result<TimeDelta, String> {
+    let length = length.trim();
+    let invalid = || format!("invalid window length '{}', expected a number of d, h, m or s", length);
+    let unit = length.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = length[..length.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+
+    let window = match unit {
+        'd' => TimeDelta::try_days(amount),
+        'h' => TimeDelta::try_hours(amount),
+        'm' => TimeDelta::try_minutes(amount),
+        's' => TimeDelta::try_seconds(amount),
+        _ => return Err(invalid()),
+    };
+    window.ok_or_else(|| format!("window length '{}' is out of range", length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_standard_normal() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let samples: Vec<f64> = (0..20_000).map(|_| standard_normal(&mut rng)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|sample| (sample - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+
+        assert!(mean.abs() < 0.05);
+        assert!((variance - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_parse_window_length() {
+        assert_eq!(parse_window_length("7d"), Ok(TimeDelta::days(7)));
+        assert_eq!(parse_window_length(" 90s "), Ok(TimeDelta::seconds(90)));
+        assert!(parse_window_length("7w").is_err());
+        assert!(parse_window_length("").is_err());
+        // Multi-byte units are rejected rather than split mid-character
+        assert!(parse_window_length("7µ").is_err());
+        assert!(parse_window_length("99999999999999d").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use barter::engine::state::instrument::data::InstrumentDataState;
+use barter::system::config::ExecutionConfig;
+use barter_instrument::asset::AssetIndex;
+use barter_instrument::exchange::ExchangeId;
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::InstrumentIndex;
+use barter_data::event::DataKind;
+use barter_data::streams::consumer::MarketStreamEvent;
+use barter_data::streams::reconnect::Event;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use crate::algorithm::data::AlgorithmData;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValuationError {
+    /// No execution holds a balance in the quote asset of any of its instruments
+    NoQuoteBalance,
+    /// No price links the asset to the reporting currency
+    MissingPrice { asset: String, reporting: String },
+}
+
+impl fmt::Display for ValuationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValuationError::NoQuoteBalance => {
+                write!(f, "no execution has a balance in the quote asset of its configured instruments")
+            }
+            ValuationError::MissingPrice { asset, reporting } => {
+                write!(f, "no price available to value {} in {}", asset, reporting)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValuationError {}
+
+/// Values configured balances in a single reporting currency
+#[derive(Debug, Clone)]
+pub struct Valuation {
+    reporting: String,
+    // (base, quote) -> price of one base in quote, asset names lowercased
+    prices: HashMap<(String, String), Decimal>,
+}
+
+impl Valuation {
+    pub fn new(reporting: &str) -> Self {
+        Self {
+            reporting: reporting.to_lowercase(),
+            prices: HashMap::new(),
+        }
+    }
+
+    pub fn reporting(&self) -> &str {
+        &self.reporting
+    }
+
+    pub fn set_price(&mut self, base: &str, quote: &str, price: Decimal) {
+        self.prices.insert((base.to_lowercase(), quote.to_lowercase()), price);
+    }
+
+    /// Take the latest price of every instrument that has seen market data (eg/ after warm-up)
+    pub fn with_market_prices(mut self, instruments: &IndexedInstruments, data: &HashMap<InstrumentIndex, AlgorithmData>) -> Self {
+        for instrument in instruments.instruments() {
+            let Some(price) = data.get(&instrument.key).and_then(|data| data.price()) else { continue; };
+            let base = asset_name(instruments, instrument.value.underlying.base);
+            let quote = asset_name(instruments, instrument.value.underlying.quote);
+            self.set_price(&base, &quote, price);
+        }
+        self
+    }
+
+    /// Take each instrument's first trade price from a replay, reading only as far as it takes
+    /// to price every instrument, so a backtest values its wallet at the prices it starts from
+    pub fn with_replay_prices(
+        self,
+        instruments: &IndexedInstruments,
+        events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+    ) -> Self {
+        let mut first_prices = HashMap::new();
+        for event in events {
+            let Event::Item(event) = event else { continue; };
+            let DataKind::Trade(trade) = &event.kind else { continue; };
+            first_prices.entry(event.instrument)
+                .or_insert_with(|| Decimal::from_f64(trade.price).unwrap_or_default());
+            if first_prices.len() == instruments.instruments().len() {
+                break;
+            }
+        }
+
+        let mut valuation = self;
+        for instrument in instruments.instruments() {
+            let Some(price) = first_prices.get(&instrument.key) else { continue; };
+            let base = asset_name(instruments, instrument.value.underlying.base);
+            let quote = asset_name(instruments, instrument.value.underlying.quote);
+            valuation.set_price(&base, &quote, *price);
+        }
+        valuation
+    }
+
+    /// Converts `amount` of `asset` into the reporting currency, directly or via the inverse pair
+    pub fn convert(&self, asset: &str, amount: Decimal) -> Result<Decimal, ValuationError> {
+        let asset = asset.to_lowercase();
+        if asset == self.reporting {
+            return Ok(amount);
+        }
+        if let Some(price) = self.prices.get(&(asset.clone(), self.reporting.clone())) {
+            return Ok(amount * price);
+        }
+        if let Some(price) = self.prices.get(&(self.reporting.clone(), asset.clone()))
+            && *price != dec!(0)
+        {
+            return Ok(amount / price);
+        }
+        Err(ValuationError::MissingPrice { asset, reporting: self.reporting.clone() })
+    }
+
+    /// Total value, in the reporting currency, of the balances every execution holds in
+    /// the quote assets of the instruments configured on its exchange
+    pub fn wallet_size(&self, instruments: &IndexedInstruments, executions: &[ExecutionConfig]) -> Result<Decimal, ValuationError> {
+        let mut wallet_size = dec!(0);
+        let mut found = false;
+
+        for execution in executions {
+            let ExecutionConfig::Mock(mock_config) = execution;
+            let quotes = quote_assets(instruments, mock_config.mocked_exchange);
+
+            for balance in &mock_config.initial_state.balances {
+                let asset = balance.asset.name().to_lowercase();
+                if !quotes.contains(&asset) {
+                    continue;
+                }
+                wallet_size += self.convert(&asset, balance.balance.total)?;
+                found = true;
+            }
+        }
+
+        if !found {
+            return Err(ValuationError::NoQuoteBalance);
+        }
+        Ok(wallet_size)
+    }
+}
+
+/// Lowercased quote asset names of the instruments configured on `exchange`
+pub fn quote_assets(instruments: &IndexedInstruments, exchange: ExchangeId) -> BTreeSet<String> {
+    instruments.instruments()
+        .iter()
+        .filter(|instrument| instrument.value.exchange.value == exchange)
+        .map(|instrument| asset_name(instruments, instrument.value.underlying.quote))
+        .collect()
+}
+
+fn asset_name(instruments: &IndexedInstruments, asset: AssetIndex) -> String {
+    instruments.find_asset(asset)
+        .map(|asset| asset.asset.name_exchange.name().to_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter::system::config::SystemConfig;
+
+    fn config(balances: &str) -> (IndexedInstruments, Vec<ExecutionConfig>) {
+        let json = format!(r#"{{
+            "instruments": [
+                {{ "exchange": "binance_spot", "name_exchange": "BTCUSDT", "underlying": {{ "base": "btc", "quote": "usdt" }}, "quote": "underlying_quote", "kind": "spot" }},
+                {{ "exchange": "binance_spot", "name_exchange": "ETHBTC", "underlying": {{ "base": "eth", "quote": "btc" }}, "quote": "underlying_quote", "kind": "spot" }}
+            ],
+            "executions": [
+                {{ "mocked_exchange": "binance_spot", "latency_ms": 100, "fees_percent": 0.05,
+                   "initial_state": {{ "exchange": "binance_spot", "balances": [{}], "instruments": [] }} }}
+            ]
+        }}"#, balances);
+        let SystemConfig { instruments, executions } = serde_json::from_str(&json).unwrap();
+        (IndexedInstruments::new(instruments), executions)
+    }
+
+    fn balance(asset: &str, total: u32) -> String {
+        format!(r#"{{ "asset": "{}", "balance": {{ "total": {}, "free": {} }}, "time_exchange": "2025-03-24T21:30:00Z" }}"#, asset, total, total)
+    }
+
+    #[test]
+    fn test_wallet_size_across_quote_assets() {
+        let (instruments, executions) = config(&[balance("usdt", 1000), balance("btc", 1), balance("eth", 5)].join(","));
+
+        let mut valuation = Valuation::new("USDT");
+        // BTC is a quote asset (ETHBTC) so it counts, ETH is only a base asset so it doesn't
+        assert_eq!(
+            valuation.wallet_size(&instruments, &executions),
+            Err(ValuationError::MissingPrice { asset: "btc".to_string(), reporting: "usdt".to_string() })
+        );
+
+        valuation.set_price("btc", "usdt", dec!(50000));
+        assert_eq!(valuation.wallet_size(&instruments, &executions), Ok(dec!(51000)));
+    }
+
+    #[test]
+    fn test_replay_prices_value_non_reporting_quotes() {
+        use barter_data::event::MarketEvent;
+        use barter_data::subscription::trade::PublicTrade;
+        use barter_instrument::Side;
+        use chrono::{DateTime, Utc};
+
+        let (instruments, executions) = config(&[balance("usdt", 1000), balance("btc", 1)].join(","));
+        let time = DateTime::<Utc>::from_timestamp(1_742_860_800, 0).unwrap();
+        let trade = |instrument: usize, price: f64| Event::Item(MarketEvent {
+            time_exchange: time,
+            time_received: time,
+            exchange: ExchangeId::BinanceSpot,
+            instrument: InstrumentIndex(instrument),
+            kind: DataKind::Trade(PublicTrade { id: "1".to_string(), price, amount: 1.0, side: Side::Buy }),
+        });
+        let events = vec![
+            Event::Reconnecting(ExchangeId::BinanceSpot),
+            trade(0, 50000.0),
+            trade(0, 60000.0),
+            trade(1, 0.05),
+        ];
+
+        // BTC is valued at the first BTCUSDT print, not a later one
+        let valuation = Valuation::new("usdt").with_replay_prices(&instruments, events);
+        assert_eq!(valuation.wallet_size(&instruments, &executions), Ok(dec!(51000)));
+    }
+
+    #[test]
+    fn test_inverse_conversion() {
+        let mut valuation = Valuation::new("btc");
+        valuation.set_price("btc", "usdt", dec!(50000));
+        assert_eq!(valuation.convert("usdt", dec!(25000)), Ok(dec!(0.5)));
+    }
+
+    #[test]
+    fn test_no_quote_balance() {
+        let (instruments, executions) = config(&balance("eth", 5));
+        assert_eq!(Valuation::new("usdt").wallet_size(&instruments, &executions), Err(ValuationError::NoQuoteBalance));
+    }
+}
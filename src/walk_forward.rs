@@ -0,0 +1,454 @@
+use barter_data::event::DataKind;
+use barter_data::streams::consumer::MarketStreamEvent;
+use barter_data::streams::reconnect::Event;
+use barter_instrument::instrument::InstrumentIndex;
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::MathematicalOps;
+use rust_decimal::prelude::ToPrimitive;
+use std::fmt;
+use std::future::Future;
+use crate::backtest::BacktestResult;
+use crate::optimizer::{json_number, result_json, run_sweep, RankBy, RunMetrics, StrategyParams};
+
+/// Rolling walk-forward schedule: optimize on `in_sample`, trade the chosen parameters on the
+/// `out_of_sample` period that follows, then roll both forward by `out_of_sample`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WalkForward {
+    pub in_sample: TimeDelta,
+    pub out_of_sample: TimeDelta,
+}
+
+/// One in-sample/out-of-sample split, each a half-open `[start, end)` range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    pub in_sample: (DateTime<Utc>, DateTime<Utc>),
+    pub out_of_sample: (DateTime<Utc>, DateTime<Utc>),
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = "%Y-%m-%d %H:%M";
+        write!(f, "IS {} -> {} | OOS {} -> {}",
+               self.in_sample.0.format(format),
+               self.in_sample.1.format(format),
+               self.out_of_sample.0.format(format),
+               self.out_of_sample.1.format(format)
+        )
+    }
+}
+
+impl WalkForward {
+    pub fn new(in_sample: TimeDelta, out_of_sample: TimeDelta) -> Self {
+        Self { in_sample, out_of_sample }
+    }
+
+    /// Windows covering `[start, end]`; the last out-of-sample period is cut short at `end`
+    pub fn windows(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Window> {
+        let mut windows = Vec::new();
+        if self.in_sample <= TimeDelta::zero() || self.out_of_sample <= TimeDelta::zero() {
+            return windows;
+        }
+
+        let mut in_sample_start = start;
+        loop {
+            let in_sample_end = in_sample_start + self.in_sample;
+            if in_sample_end >= end {
+                break;
+            }
+            // Half-open, so nudge the final period past the last event
+            let out_of_sample_end = (in_sample_end + self.out_of_sample).min(end + TimeDelta::nanoseconds(1));
+            windows.push(Window {
+                in_sample: (in_sample_start, in_sample_end),
+                out_of_sample: (in_sample_end, out_of_sample_end),
+            });
+            in_sample_start += self.out_of_sample;
+        }
+        windows
+    }
+}
+
+/// Exchange times of the first and last market event
+pub fn data_range(
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    events.into_iter().fold(None, |range, event| match event {
+        Event::Item(event) => match range {
+            None => Some((event.time_exchange, event.time_exchange)),
+            Some((first, last)) => Some((first.min(event.time_exchange), last.max(event.time_exchange))),
+        },
+        Event::Reconnecting(_) => range,
+    })
+}
+
+/// Events of a time-ordered stream inside `[start, end)`, with the reconnections between them
+pub fn window_events(
+    events: impl IntoIterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>>,
+    (start, end): (DateTime<Utc>, DateTime<Utc>),
+) -> impl Iterator<Item = MarketStreamEvent<InstrumentIndex, DataKind>> {
+    let mut started = false;
+    events.into_iter()
+        .take_while(move |event| match event {
+            Event::Item(event) => event.time_exchange < end,
+            Event::Reconnecting(_) => true,
+        })
+        .filter(move |event| {
+            if let Event::Item(event) = event {
+                started = event.time_exchange >= start;
+            }
+            started
+        })
+}
+
+/// Parameters chosen in one window and how they did in and out of sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowResult<Params> {
+    pub window: Window,
+    pub params: Params,
+    pub in_sample: RunMetrics,
+    pub out_of_sample: RunMetrics,
+    /// Realised PnL of each position closed out of sample, in exit order
+    pub out_of_sample_exits: Vec<Decimal>,
+}
+
+/// Out-of-sample performance of every window traded back to back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StitchedMetrics {
+    pub windows: usize,
+    pub profitable_windows: usize,
+    pub pnl: Decimal,
+    /// Mean out-of-sample Sharpe ratio of the windows that have one
+    pub sharpe: Option<Decimal>,
+    /// Deepest drawdown of the equity curve stitched from every out-of-sample window
+    pub max_drawdown: Decimal,
+    /// Out-of-sample PnL as a fraction of the in-sample PnL the parameters were chosen on
+    pub efficiency: Option<Decimal>,
+}
+
+impl fmt::Display for StitchedMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OOS PnL: {} | Profitable windows: {}/{} | Mean Sharpe: {} | Max DD: {}% | Efficiency: {}",
+               self.pnl.round_dp(2),
+               self.profitable_windows,
+               self.windows,
+               self.sharpe.map(|sharpe| sharpe.round_dp(4).to_string()).unwrap_or_else(|| "N/A".to_string()),
+               (self.max_drawdown * Decimal::ONE_HUNDRED).round_dp(2),
+               self.efficiency.map(|efficiency| efficiency.round_dp(2).to_string()).unwrap_or_else(|| "N/A".to_string())
+        )
+    }
+}
+
+/// How much a knob's chosen value moved between windows
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnobStability {
+    pub knob: &'static str,
+    pub mean: Decimal,
+    pub std_dev: Decimal,
+    pub min: Decimal,
+    pub max: Decimal,
+    /// Number of windows whose value differs from the window before
+    pub changes: usize,
+}
+
+impl KnobStability {
+    /// Standard deviation relative to the mean, lower is more stable
+    pub fn coefficient_of_variation(&self) -> Option<Decimal> {
+        self.std_dev.checked_div(self.mean.abs())
+    }
+}
+
+impl fmt::Display for KnobStability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: mean {} | std dev {} | range {}-{} | changed {} times",
+               self.knob,
+               self.mean.round_dp(6),
+               self.std_dev.round_dp(6),
+               self.min,
+               self.max,
+               self.changes
+        )
+    }
+}
+
+/// Results of a walk-forward run, in window order
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkForwardReport<Params> {
+    /// Capital the stitched out-of-sample equity curve starts from
+    pub capital: Decimal,
+    pub windows: Vec<WindowResult<Params>>,
+}
+
+impl<Params: StrategyParams> WalkForwardReport<Params> {
+    pub fn stitched(&self) -> StitchedMetrics {
+        let pnl = self.windows.iter().map(|window| window.out_of_sample.pnl).sum();
+        let in_sample_pnl: Decimal = self.windows.iter().map(|window| window.in_sample.pnl).sum();
+        let sharpes: Vec<Decimal> = self.windows.iter().filter_map(|window| window.out_of_sample.sharpe).collect();
+
+        StitchedMetrics {
+            windows: self.windows.len(),
+            profitable_windows: self.windows.iter().filter(|window| window.out_of_sample.pnl > Decimal::ZERO).count(),
+            pnl,
+            sharpe: (!sharpes.is_empty()).then(|| sharpes.iter().sum::<Decimal>() / Decimal::from(sharpes.len())),
+            max_drawdown: max_drawdown(&self.equity_curve()),
+            efficiency: (in_sample_pnl > Decimal::ZERO).then(|| pnl / in_sample_pnl),
+        }
+    }
+
+    /// Equity after every out-of-sample exit, traded back to back from `capital`
+    ///
+    /// Each window ends on its full PnL, so positions still open when it closes are marked
+    /// to market before the next window starts.
+    pub fn equity_curve(&self) -> Vec<Decimal> {
+        let mut equity = self.capital;
+        let mut curve = vec![equity];
+        for window in &self.windows {
+            let window_start = equity;
+            for pnl in &window.out_of_sample_exits {
+                equity += pnl;
+                curve.push(equity);
+            }
+            equity = window_start + window.out_of_sample.pnl;
+            curve.push(equity);
+        }
+        curve
+    }
+
+    pub fn stability(&self) -> Vec<KnobStability> {
+        let chosen: Vec<Vec<Decimal>> = self.windows.iter().map(|window| window.params.values()).collect();
+
+        Params::KNOBS.iter()
+            .enumerate()
+            .filter_map(|(index, knob)| {
+                let values: Vec<Decimal> = chosen.iter().map(|values| values[index]).collect();
+                let count = Decimal::from(values.len());
+                let mean = values.iter().sum::<Decimal>().checked_div(count)?;
+                let variance = values.iter().map(|value| (value - mean) * (value - mean)).sum::<Decimal>() / count;
+
+                Some(KnobStability {
+                    knob: knob.name,
+                    mean,
+                    std_dev: variance.sqrt().unwrap_or_default(),
+                    min: values.iter().copied().min()?,
+                    max: values.iter().copied().max()?,
+                    changes: values.windows(2).filter(|pair| pair[0] != pair[1]).count(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let format = |time: DateTime<Utc>| time.to_rfc3339();
+        let windows: Vec<serde_json::Value> = self.windows.iter()
+            .map(|window| serde_json::json!({
+                "in_sample_start": format(window.window.in_sample.0),
+                "in_sample_end": format(window.window.in_sample.1),
+                "out_of_sample_start": format(window.window.out_of_sample.0),
+                "out_of_sample_end": format(window.window.out_of_sample.1),
+                "in_sample": result_json(&window.params, &window.in_sample),
+                "out_of_sample": result_json(&window.params, &window.out_of_sample),
+            }))
+            .collect();
+
+        let stitched = self.stitched();
+        let stability: Vec<serde_json::Value> = self.stability().iter()
+            .map(|knob| serde_json::json!({
+                "knob": knob.knob,
+                "mean": json_number(knob.mean),
+                "std_dev": json_number(knob.std_dev),
+                "min": json_number(knob.min),
+                "max": json_number(knob.max),
+                "changes": knob.changes,
+                "coefficient_of_variation": knob.coefficient_of_variation().and_then(|value| value.to_f64()),
+            }))
+            .collect();
+
+        serde_json::json!({
+            "windows": windows,
+            "stitched": {
+                "windows": stitched.windows,
+                "profitable_windows": stitched.profitable_windows,
+                "pnl": json_number(stitched.pnl),
+                "sharpe": stitched.sharpe.map(json_number),
+                "max_drawdown": json_number(stitched.max_drawdown),
+                "efficiency": stitched.efficiency.map(json_number),
+            },
+            "stability": stability,
+        })
+    }
+}
+
+/// Deepest fall from a running peak, as a fraction of the peak
+fn max_drawdown(curve: &[Decimal]) -> Decimal {
+    let mut peak = Decimal::ZERO;
+    let mut max_drawdown = Decimal::ZERO;
+    for equity in curve {
+        peak = peak.max(*equity);
+        if peak > Decimal::ZERO {
+            max_drawdown = max_drawdown.max((peak - equity) / peak);
+        }
+    }
+    max_drawdown
+}
+
+/// Optimizes on every in-sample window and evaluates the best candidate on its out-of-sample window
+///
+/// `backtest` runs a candidate over a `[start, end)` range of the data, warming its indicators up
+/// on a second range when one is given. Each in-sample window is a full `run_sweep` over
+/// `candidates`, and its out-of-sample run warms up on the in-sample events. Windows where every
+/// run failed are skipped.
+pub async fn run_walk_forward<Params, Backtest, Run>(
+    windows: Vec<Window>,
+    candidates: Vec<Params>,
+    parallelism: usize,
+    rank_by: RankBy,
+    capital: Decimal,
+    backtest: Backtest,
+) -> WalkForwardReport<Params>
+where
+    Params: StrategyParams,
+    Backtest: Fn(Params, (DateTime<Utc>, DateTime<Utc>), Option<(DateTime<Utc>, DateTime<Utc>)>) -> Run,
+    Run: Future<Output = Result<BacktestResult, Box<dyn std::error::Error>>>,
+{
+    let total = windows.len();
+    let mut results = Vec::with_capacity(total);
+
+    for (index, window) in windows.into_iter().enumerate() {
+        println!("[{}] 🪟 WALK-FORWARD [{}/{}]: {}",
+                 Local::now().format("%d-%m-%y %H:%M:%S"),
+                 index + 1,
+                 total,
+                 window
+        );
+
+        let sweep = run_sweep(candidates.clone(), parallelism, rank_by, |params| {
+            let run = backtest(params, window.in_sample, None);
+            async move { run.await.map(|result| result.summary) }
+        })
+        .await;
+        let Some(best) = sweep.into_iter().next() else {
+            println!("[{}] ❌ WALK-FORWARD [{}/{}]: no in-sample run finished, skipping window",
+                     Local::now().format("%d-%m-%y %H:%M:%S"),
+                     index + 1,
+                     total
+            );
+            continue;
+        };
+
+        let (out_of_sample, out_of_sample_exits) = match backtest(best.params, window.out_of_sample, Some(window.in_sample)).await {
+            Ok(result) => (
+                RunMetrics::from_summary(&result.summary),
+                result.trades.iter().map(|trade| trade.pnl_realised).collect(),
+            ),
+            Err(error) => {
+                println!("[{}] ❌ WALK-FORWARD [{}/{}]: out-of-sample run failed: {}",
+                         Local::now().format("%d-%m-%y %H:%M:%S"),
+                         index + 1,
+                         total,
+                         error
+                );
+                continue;
+            }
+        };
+
+        println!("[{}] ✅ WALK-FORWARD [{}/{}]: {} | IS {} | OOS {}",
+                 Local::now().format("%d-%m-%y %H:%M:%S"),
+                 index + 1,
+                 total,
+                 best.params,
+                 best.metrics,
+                 out_of_sample
+        );
+        results.push(WindowResult { window, params: best.params, in_sample: best.metrics, out_of_sample, out_of_sample_exits });
+    }
+
+    WalkForwardReport { capital, windows: results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barter_data::event::MarketEvent;
+    use barter_data::subscription::trade::PublicTrade;
+    use barter_instrument::Side;
+    use barter_instrument::exchange::ExchangeId;
+    use rust_decimal_macros::dec;
+    use crate::optimizer::GridParams;
+
+    fn time(hour: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp(0, 0).unwrap() + TimeDelta::hours(hour)
+    }
+
+    fn trade(hour: i64) -> MarketStreamEvent<InstrumentIndex, DataKind> {
+        Event::Item(MarketEvent {
+            time_exchange: time(hour),
+            time_received: time(hour),
+            exchange: ExchangeId::BinanceSpot,
+            instrument: InstrumentIndex(0),
+            kind: DataKind::Trade(PublicTrade { id: hour.to_string(), price: 100.0, amount: 1.0, side: Side::Buy }),
+        })
+    }
+
+    fn metrics(pnl: Decimal) -> RunMetrics {
+        RunMetrics { pnl, ..RunMetrics::default() }
+    }
+
+    #[test]
+    fn test_rolling_windows_and_window_events() {
+        let windows = WalkForward::new(TimeDelta::hours(4), TimeDelta::hours(2)).windows(time(0), time(9));
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].in_sample, (time(0), time(4)));
+        assert_eq!(windows[1].in_sample, (time(2), time(6)));
+        assert_eq!(windows[1].out_of_sample, (time(6), time(8)));
+        assert_eq!(windows[2].out_of_sample.0, time(8));
+        assert!(windows[2].out_of_sample.1 > time(9));
+
+        let events = vec![trade(1), trade(2), Event::Reconnecting(ExchangeId::BinanceSpot), trade(3), trade(4), trade(5)];
+        let hours: Vec<String> = window_events(events.clone(), (time(2), time(4)))
+            .map(|event| match event {
+                Event::Item(event) => event.time_exchange.format("%H").to_string(),
+                Event::Reconnecting(_) => "reconnect".to_string(),
+            })
+            .collect();
+        assert_eq!(hours, vec!["02", "reconnect", "03"]);
+        assert_eq!(data_range(events), Some((time(1), time(5))));
+    }
+
+    #[test]
+    fn test_stitched_metrics_and_stability() {
+        let window = WalkForward::new(TimeDelta::hours(4), TimeDelta::hours(2)).windows(time(0), time(9))[0];
+        let result = |spacing, in_sample, out_of_sample, exits: &[Decimal]| WindowResult {
+            window,
+            params: GridParams { grid_spacing_percentage: spacing, ..GridParams::default() },
+            in_sample: metrics(in_sample),
+            out_of_sample: metrics(out_of_sample),
+            out_of_sample_exits: exits.to_vec(),
+        };
+        let report = WalkForwardReport {
+            capital: dec!(1000),
+            windows: vec![
+                result(dec!(0.01), dec!(100), dec!(40), &[dec!(50), dec!(-20)]),
+                result(dec!(0.01), dec!(80), dec!(-10), &[dec!(-30)]),
+                result(dec!(0.02), dec!(20), dec!(30), &[dec!(30)]),
+            ],
+        };
+
+        let stitched = report.stitched();
+        assert_eq!(stitched.pnl, dec!(60));
+        assert_eq!(stitched.profitable_windows, 2);
+        assert_eq!(stitched.efficiency, Some(dec!(0.3)));
+
+        // The first window's open position marks back up to 1040 before the second window's
+        // losses, which run on from it down to 1010 across the window boundary
+        assert_eq!(report.equity_curve(), vec![dec!(1000), dec!(1050), dec!(1030), dec!(1040), dec!(1010), dec!(1030), dec!(1060), dec!(1060)]);
+        assert_eq!(stitched.max_drawdown, dec!(40) / dec!(1050));
+
+        let stability = report.stability();
+        assert_eq!(stability.len(), GridParams::KNOBS.len());
+        let spacing = &stability[3];
+        assert_eq!(spacing.knob, "grid_spacing_percentage");
+        assert_eq!(spacing.changes, 1);
+        assert_eq!((spacing.min, spacing.max), (dec!(0.01), dec!(0.02)));
+        assert_eq!(stability[1].changes, 0);
+        assert_eq!(stability[1].std_dev, Decimal::ZERO);
+    }
+}